@@ -0,0 +1,61 @@
+//  BUILD.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:11:10
+//  Last edited:
+//    14 Oct 2026, 18:11:32
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Build script that captures some metadata about the build (the git
+//!   commit and the time of building), such that it can be reported by
+//!   `GET /v1/version?detailed=true`.
+//
+
+use std::env;
+use std::process::Command;
+
+use chrono::{SecondsFormat, Utc};
+
+
+/***** CONSTANTS *****/
+/// The environment variable with which the git commit can be given explicitly, e.g., when building without the `.git` directory.
+const GIT_COMMIT_VAR: &str = "DND_GIT_COMMIT";
+/// The environment variable in which the build timestamp is passed to the crate.
+const BUILD_TIMESTAMP_VAR: &str = "DND_BUILD_TIMESTAMP";
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Finds the hash of the commit we're building from.
+///
+/// # Returns
+/// The hash as given by [`GIT_COMMIT_VAR`], or else as reported by `git`, or else `unknown`.
+fn git_commit() -> String {
+    if let Ok(commit) = env::var(GIT_COMMIT_VAR) {
+        return commit;
+    }
+    match Command::new("git").args(["rev-parse", "HEAD"]).output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim().into(),
+        _ => "unknown".into(),
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+fn main() {
+    // Only re-run when the commit changes (or is given explicitly)
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed={GIT_COMMIT_VAR}");
+
+    println!("cargo:rustc-env={GIT_COMMIT_VAR}={}", git_commit());
+    println!("cargo:rustc-env={BUILD_TIMESTAMP_VAR}={}", Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+}
@@ -4,36 +4,84 @@
 //  Created:
 //    08 Apr 2024, 11:36:08
 //  Last edited:
-//    09 Apr 2024, 13:02:31
+//    14 Oct 2026, 19:04:28
 //  Auto updated?
 //    Yes
 //
 //  Description:
 //!   Implements tooling for doing user authentication, like password
 //!   hashing.
+//!   
+//!   By default, login tokens are plain JSON that rely on being sent as a
+//!   [`PrivateCookie`](axum_extra::extract::PrivateCookieJar) for their
+//!   integrity. If the `jwt`-feature is enabled, they are instead emitted as
+//!   JSON Web Tokens signed with the server's [`Key`], which can be
+//!   validated by anyone with that key.
 //
 
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FResult};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::os::unix::fs::OpenOptionsExt as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTimeError;
 
 use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString};
-use argon2::Argon2;
-use chrono::{DateTime, Utc};
+use argon2::{Algorithm as Argon2Algorithm, Argon2, Params, Version as Argon2Version};
+use axum_extra::extract::cookie::Key;
+use chrono::{DateTime, Duration, Utc};
 use enum_debug::EnumDebug;
-use error_trace::trace;
+#[cfg(feature = "jwt")]
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use log::debug;
+use rand::distributions::Alphanumeric;
 use rand::rngs::OsRng;
+use rand::Rng as _;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use totp_rs::{Algorithm as TotpAlgorithm, Secret, SecretParseError, TotpUrlError, TOTP};
 
 use crate::database::{Database, UserInfo};
 
 
 /***** CONSTANTS *****/
-/// The time that a token is valid.
+/// The default time (in minutes) that a token is valid.
 pub const TOKEN_VALID_TIME_MIN: i64 = 360;
+/// The default time (in minutes) that a token is valid if the user asked to be remembered.
+pub const REMEMBER_VALID_TIME_MIN: i64 = 30 * 24 * 60;
+
+/// The default minimum length (in characters) of passwords.
+pub const PASSWORD_MIN_LEN: usize = 8;
+
+/// The role given to newly registered users.
+pub const DEFAULT_ROLE: Role = Role::Player;
+
+/// The length of the randomly generated token identifiers (`jti`s).
+pub const TOKEN_ID_LEN: usize = 32;
+
+/// The length of the randomly generated password reset tokens.
+pub const RESET_TOKEN_LEN: usize = 48;
+/// The time (in minutes) that a password reset token is valid.
+pub const RESET_TOKEN_VALID_TIME_MIN: i64 = 30;
+
+/// The number of digits in a TOTP code.
+pub const TOTP_DIGITS: usize = 6;
+/// The time (in seconds) that a single TOTP code is valid.
+pub const TOTP_STEP_SECS: u64 = 30;
+/// The number of steps before or after the current one that we still accept TOTP codes of, to account for clock drift.
+pub const TOTP_SKEW: u8 = 1;
 
 /// The name of the login token cookie.
 pub const LOGIN_TOKEN_NAME: &'static str = "login-token";
+/// The password that is hashed to get the dummy hash used by [`check_dummy_password()`].
+const DUMMY_PASSWORD: &str = "dummy-password-for-unknown-users";
+/// The prefixes with which (legacy) bcrypt password hashes start.
+const BCRYPT_PREFIXES: [&str; 4] = ["$2a$", "$2b$", "$2x$", "$2y$"];
+
+/// The length (in bytes) of the [`Key`] with which cookies are encrypted.
+pub const KEY_LEN: usize = 64;
 
 
 
@@ -56,6 +104,12 @@ impl Error for RoleFromU8Error {}
 pub enum PasswordError {
     /// Failed to hash a given password.
     Hash { err: argon2::password_hash::Error },
+    /// The given Argon2 parameters were invalid.
+    Params { params: Argon2Params, err: argon2::Error },
+    /// Failed to parse a stored password hash.
+    Parse { err: argon2::password_hash::Error },
+    /// Failed to parse or check a stored, legacy bcrypt password hash.
+    ParseBcrypt { err: bcrypt::BcryptError },
 }
 impl Display for PasswordError {
     #[inline]
@@ -63,6 +117,9 @@ impl Display for PasswordError {
         use PasswordError::*;
         match self {
             Hash { .. } => write!(f, "Failed to hash password"),
+            Params { params, .. } => write!(f, "Invalid Argon2 parameters {params:?}"),
+            Parse { .. } => write!(f, "Failed to parse password hash"),
+            ParseBcrypt { .. } => write!(f, "Failed to parse bcrypt password hash"),
         }
     }
 }
@@ -71,6 +128,108 @@ impl Error for PasswordError {
         use PasswordError::*;
         match self {
             Hash { err } => Some(err),
+            Params { err, .. } => Some(err),
+            Parse { err } => Some(err),
+            ParseBcrypt { err } => Some(err),
+        }
+    }
+}
+
+
+
+/// Defines the rules a password can fail to adhere to.
+#[derive(Debug)]
+pub enum PasswordPolicyError {
+    /// The password does not contain any digits.
+    NoDigit,
+    /// The password does not contain any letters.
+    NoLetter,
+    /// The password is shorter than the minimum length.
+    TooShort { len: usize, min: usize },
+}
+impl Display for PasswordPolicyError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> FResult {
+        use PasswordPolicyError::*;
+        match self {
+            NoDigit => write!(f, "Password must contain at least one digit"),
+            NoLetter => write!(f, "Password must contain at least one letter"),
+            TooShort { len, min } => write!(f, "Password must be at least {min} characters long (got {len})"),
+        }
+    }
+}
+impl Error for PasswordPolicyError {}
+
+
+
+/// Defines errors originating from TOTP (two-factor authentication) handling.
+#[derive(Debug)]
+pub enum TotpError {
+    /// Failed to get the current time.
+    Clock { err: SystemTimeError },
+    /// Failed to parse a stored TOTP secret.
+    SecretParse { err: SecretParseError },
+    /// Failed to build an `otpauth://`-URI.
+    Url { err: TotpUrlError },
+}
+impl Display for TotpError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> FResult {
+        use TotpError::*;
+        match self {
+            Clock { .. } => write!(f, "Failed to get current system time"),
+            SecretParse { .. } => write!(f, "Failed to parse TOTP secret"),
+            Url { .. } => write!(f, "Failed to build otpauth URI"),
+        }
+    }
+}
+impl Error for TotpError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn 'static + Error)> {
+        use TotpError::*;
+        match self {
+            Clock { err } => Some(err),
+            SecretParse { err } => Some(err),
+            Url { err } => Some(err),
+        }
+    }
+}
+
+
+
+/// Defines errors originating from loading the cookie [`Key`] from disk.
+#[derive(Debug)]
+pub enum KeyError {
+    /// Failed to create the directory the key file lives in.
+    DirCreate { path: PathBuf, err: io::Error },
+    /// The key file didn't have the right length.
+    Length { path: PathBuf, got: usize },
+    /// Failed to read the key file.
+    Read { path: PathBuf, err: io::Error },
+    /// Failed to write a newly generated key.
+    Write { path: PathBuf, err: io::Error },
+}
+impl Display for KeyError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> FResult {
+        use KeyError::*;
+        match self {
+            DirCreate { path, .. } => write!(f, "Failed to create key directory '{}'", path.display()),
+            Length { path, got } => write!(f, "Key file '{}' has {got} bytes, but expected exactly {KEY_LEN}", path.display()),
+            Read { path, .. } => write!(f, "Failed to read key file '{}'", path.display()),
+            Write { path, .. } => write!(f, "Failed to write new key to key file '{}'", path.display()),
+        }
+    }
+}
+impl Error for KeyError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn 'static + Error)> {
+        use KeyError::*;
+        match self {
+            DirCreate { err, .. } => Some(err),
+            Length { .. } => None,
+            Read { err, .. } => Some(err),
+            Write { err, .. } => Some(err),
         }
     }
 }
@@ -80,8 +239,13 @@ impl Error for PasswordError {
 /// Define errors originating from token managing/checking.
 #[derive(Debug)]
 pub enum TokenError {
+    /// Failed to encode the given login token as a JWT.
+    #[cfg(feature = "jwt")]
+    Encode { err: jsonwebtoken::errors::Error },
     /// Failed to serialize the given login token.
     Serialize { err: serde_json::Error },
+    /// Failed to check whether a token was revoked.
+    RevokedRetrieve { jti: String, err: crate::database::Error },
     /// Failed to get the info for a certain user.
     UserInfoRetrieve { id: u64, err: crate::database::Error },
 }
@@ -90,7 +254,10 @@ impl Display for TokenError {
     fn fmt(&self, f: &mut Formatter) -> FResult {
         use TokenError::*;
         match self {
+            #[cfg(feature = "jwt")]
+            Encode { .. } => write!(f, "Failed to encode login token as JWT"),
             Serialize { .. } => write!(f, "Failed to serialize login token"),
+            RevokedRetrieve { jti, .. } => write!(f, "Failed to check whether token '{jti}' is revoked in database"),
             UserInfoRetrieve { id, .. } => write!(f, "Failed to retrieve UserInfo for user {id} from database"),
         }
     }
@@ -100,7 +267,10 @@ impl Error for TokenError {
     fn source(&self) -> Option<&(dyn 'static + Error)> {
         use TokenError::*;
         match self {
+            #[cfg(feature = "jwt")]
+            Encode { err } => Some(err),
             Serialize { err } => Some(err),
+            RevokedRetrieve { err, .. } => Some(err),
             UserInfoRetrieve { err, .. } => Some(err),
         }
     }
@@ -115,6 +285,11 @@ pub enum TokenInvalid {
     Expired { id: u64, age: i64, valid_time: i64 },
     /// A token carried a role that didn't make sense.
     IncorrectRole { id: u64, got: Role, expected: Role },
+    /// Failed to decode (or verify) some string as a JWT.
+    #[cfg(feature = "jwt")]
+    Jwt { err: jsonwebtoken::errors::Error },
+    /// The given token was explicitly revoked (e.g., by logging out).
+    Revoked { id: u64, jti: String },
     /// A user presented a token for a user that was deleted (or at least, not in the DB).
     UserNotFound { id: u64 },
 }
@@ -138,6 +313,9 @@ impl Display for TokenInvalid {
             IncorrectRole { id, got, expected } => {
                 write!(f, "User {id} role in token does not match role in database (got {}, expected {})", got.variant(), expected.variant())
             },
+            #[cfg(feature = "jwt")]
+            Jwt { .. } => write!(f, "Failed to decode or verify login token as JWT"),
+            Revoked { id, jti } => write!(f, "User {id} presented token '{jti}', which was revoked"),
             UserNotFound { id } => write!(f, "User {id} in token not found"),
         }
     }
@@ -150,6 +328,9 @@ impl Error for TokenInvalid {
             Deserialize { err, .. } => Some(err),
             Expired { .. } => None,
             IncorrectRole { .. } => None,
+            #[cfg(feature = "jwt")]
+            Jwt { err } => Some(err),
+            Revoked { .. } => None,
             UserNotFound { .. } => None,
         }
     }
@@ -160,16 +341,68 @@ impl Error for TokenInvalid {
 
 
 /***** AUXILLARY *****/
+/// Defines the cost parameters used when hashing passwords with Argon2.
+///
+/// The [`Default`] implementation matches the parameters used by [`Argon2::default()`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Argon2Params {
+    /// The memory cost, in KiB.
+    pub m_cost: u32,
+    /// The time cost, in number of iterations.
+    pub t_cost: u32,
+    /// The parallelism cost, in number of lanes.
+    pub p_cost: u32,
+}
+impl Default for Argon2Params {
+    #[inline]
+    fn default() -> Self { Self { m_cost: Params::DEFAULT_M_COST, t_cost: Params::DEFAULT_T_COST, p_cost: Params::DEFAULT_P_COST } }
+}
+
+
+
 /// Defines recognized user roles and ordering between them.
-#[derive(Clone, Copy, Debug, Deserialize, EnumDebug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+///
+/// Roles are ordered by how powerful they are, i.e., `Player < DungeonMaster < Root`. As such, they can be used directly in permission checks.
+#[derive(Clone, Copy, Debug, Deserialize, EnumDebug, Eq, Hash, JsonSchema, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum Role {
+    /// A normal user that plays the game.
+    Player        = 1,
+    /// A user that can host and run campaigns.
+    DungeonMaster = 5,
     /// It's the most powerful role.
-    Root = 10,
+    Root          = 10,
+}
+impl Role {
+    /// Returns all roles, from least to most powerful.
+    ///
+    /// This should list every role that [`Role::try_from()`] parses, such that clients can be offered all of them.
+    ///
+    /// # Returns
+    /// A static slice with every variant of the Role.
+    #[inline]
+    pub const fn all() -> &'static [Self] { &[Self::Player, Self::DungeonMaster, Self::Root] }
+
+    /// Returns a human-readable name of this role, e.g., for showing in dropdowns.
+    ///
+    /// Unlike [`EnumDebug::variant()`], this may contain spaces. Clients may key off it, so it should not change.
+    ///
+    /// # Returns
+    /// A static string with the name of this role.
+    #[inline]
+    pub const fn display_name(&self) -> &'static str {
+        match self {
+            Self::Player => "Player",
+            Self::DungeonMaster => "Dungeon Master",
+            Self::Root => "Root",
+        }
+    }
 }
 impl From<Role> for u8 {
     #[inline]
     fn from(value: Role) -> Self {
         match value {
+            Role::Player => 1,
+            Role::DungeonMaster => 5,
             Role::Root => 10,
         }
     }
@@ -180,8 +413,10 @@ impl TryFrom<u8> for Role {
     #[inline]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
+            1 => Ok(Self::Player),
+            5 => Ok(Self::DungeonMaster),
             10 => Ok(Self::Root),
-            other => Err(RoleFromU8Error(other)),
+            value => Err(RoleFromU8Error(value)),
         }
     }
 }
@@ -189,12 +424,40 @@ impl TryFrom<u8> for Role {
 /// The thing that we sent to users that acts as an auth token.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct LoginToken {
+    /// A unique identifier for this token, used to revoke it.
+    pub jti:           String,
     /// The ID of the logged-in user.
-    pub id:     u64,
+    pub id:            u64,
     /// The role of the logged-in user.
-    pub role:   Role,
+    pub role:          Role,
     /// The time this token was issued.
-    pub issued: DateTime<Utc>,
+    pub issued:        DateTime<Utc>,
+    /// The time (in minutes) that this token is valid after it has been issued.
+    pub valid_minutes: i64,
+}
+impl LoginToken {
+    /// Returns the time at which this token expires.
+    ///
+    /// # Returns
+    /// A [`DateTime`] after which the token is no longer valid.
+    #[inline]
+    pub fn expires(&self) -> DateTime<Utc> { self.issued + Duration::minutes(self.valid_minutes) }
+}
+
+/// The claims embedded in a JWT login token.
+#[cfg(feature = "jwt")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoginClaims {
+    /// A unique identifier for this token, used to revoke it.
+    pub jti:  String,
+    /// The ID of the logged-in user.
+    pub sub:  u64,
+    /// The role of the logged-in user.
+    pub role: Role,
+    /// The time this token was issued, as a UNIX timestamp (in seconds).
+    pub iat:  i64,
+    /// The time this token expires, as a UNIX timestamp (in seconds).
+    pub exp:  i64,
 }
 
 
@@ -202,26 +465,78 @@ pub struct LoginToken {
 
 
 /***** LIBRARY *****/
+/// Validates whether a password is strong enough to be used.
+///
+/// Specifically, passwords must:
+/// - Be at least `min_len` characters long;
+/// - Contain at least one letter; and
+/// - Contain at least one digit.
+///
+/// # Arguments
+/// - `pass`: The password to validate.
+/// - `min_len`: The minimum length (in characters) of the password. Typically, this is [`PASSWORD_MIN_LEN`].
+///
+/// # Errors
+/// This function errors with a [`PasswordPolicyError`] describing the first rule that the password violates.
+pub fn validate_password(pass: &str, min_len: usize) -> Result<(), PasswordPolicyError> {
+    let len: usize = pass.chars().count();
+    if len < min_len {
+        return Err(PasswordPolicyError::TooShort { len, min: min_len });
+    }
+    if !pass.chars().any(char::is_alphabetic) {
+        return Err(PasswordPolicyError::NoLetter);
+    }
+    if !pass.chars().any(char::is_numeric) {
+        return Err(PasswordPolicyError::NoDigit);
+    }
+    Ok(())
+}
+
 /// Computes the hash of a password.
 ///
 /// # Arguments
 /// - `password`: The password to hash.
+/// - `params`: The [`Argon2Params`] that determine how expensive the hashing is.
 ///
 /// # Returns
 /// The hashed variant of the password, as a Base64-encoded string.
-pub fn hash_password(password: &str) -> Result<String, PasswordError> {
+///
+/// # Errors
+/// This function errors if the given `params` are invalid or if we failed to hash the password.
+pub fn hash_password(password: &str, params: &Argon2Params) -> Result<String, PasswordError> {
+    // Build the hasher
+    let argon2: Argon2 = match Params::new(params.m_cost, params.t_cost, params.p_cost, None) {
+        Ok(aparams) => Argon2::new(Argon2Algorithm::Argon2id, Argon2Version::V0x13, aparams),
+        Err(err) => return Err(PasswordError::Params { params: *params, err }),
+    };
+
     // Generate a saltstring
     let salt: SaltString = SaltString::generate(&mut OsRng);
 
     // Hash
-    match Argon2::default().hash_password(password.as_bytes(), &salt) {
+    match argon2.hash_password(password.as_bytes(), &salt) {
         Ok(pass) => Ok(pass.to_string()),
         Err(err) => Err(PasswordError::Hash { err }),
     }
 }
 
+/// Checks whether the given hash is a (legacy) bcrypt hash instead of one produced by [`hash_password()`].
+///
+/// Such hashes are recognized by their `$2a$`, `$2b$`, `$2x$` or `$2y$` prefix, e.g., when imported from another system.
+///
+/// # Arguments
+/// - `hash`: The password hash to check.
+///
+/// # Returns
+/// True if it is a bcrypt hash, or false otherwise.
+#[inline]
+pub fn is_bcrypt_hash(hash: &str) -> bool { BCRYPT_PREFIXES.iter().any(|prefix| hash.starts_with(prefix)) }
+
 /// Compares the hash of a password with a plaintext suggestion.
 ///
+/// Note that the parameters used to hash the password are read from the `hash` itself, so this works regardless of the [`Argon2Params`]
+/// given to [`hash_password()`]. Legacy bcrypt hashes (see [`is_bcrypt_hash()`]) are supported as well.
+///
 /// # Arguments
 /// - `password`: The given, plaintext password to compare.
 /// - `hash`: The password in the DB to compare to.
@@ -229,41 +544,328 @@ pub fn hash_password(password: &str) -> Result<String, PasswordError> {
 /// # Returns
 /// True if they are the same, or false if they aren't.
 ///
-/// # Panics
-/// This function panics if the given `hash` is not valid.
-pub fn check_password(password: &str, hash: &str) -> bool {
+/// # Errors
+/// This function errors if the given `hash` is not valid.
+pub fn check_password(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    if is_bcrypt_hash(hash) {
+        return match bcrypt::verify(password, hash) {
+            Ok(res) => Ok(res),
+            Err(err) => Err(PasswordError::ParseBcrypt { err }),
+        };
+    }
+
     // Parse the hash, then compare
-    let hash: PasswordHash = PasswordHash::new(hash).unwrap_or_else(|err| panic!("{}", trace!(("Illegal password hash '{hash}'"), err)));
-    Argon2::default().verify_password(password.as_bytes(), &hash).is_ok()
+    let hash: PasswordHash = match PasswordHash::new(hash) {
+        Ok(hash) => hash,
+        Err(err) => return Err(PasswordError::Parse { err }),
+    };
+    Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Checks whether a stored password hash is weaker than what [`hash_password()`] would produce with the given parameters.
+///
+/// This is the case for legacy bcrypt hashes (see [`is_bcrypt_hash()`]), for Argon2 hashes of another variant than Argon2id, and for
+/// Argon2id hashes of which any of the cost parameters is lower than in `params`.
+///
+/// # Arguments
+/// - `hash`: The password hash to check.
+/// - `params`: The [`Argon2Params`] with which new passwords are hashed.
+///
+/// # Returns
+/// True if the password should be re-hashed with `params`, or false otherwise (including when the `hash` cannot be parsed at all).
+pub fn needs_rehash(hash: &str, params: &Argon2Params) -> bool {
+    if is_bcrypt_hash(hash) {
+        return true;
+    }
+    let hash: PasswordHash = match PasswordHash::new(hash) {
+        Ok(hash) => hash,
+        Err(_) => return false,
+    };
+    if hash.algorithm != Argon2Algorithm::Argon2id.ident() {
+        return true;
+    }
+    match Params::try_from(&hash) {
+        Ok(hparams) => hparams.m_cost() < params.m_cost || hparams.t_cost() < params.t_cost || hparams.p_cost() < params.p_cost,
+        Err(_) => false,
+    }
+}
+
+/// Generates the dummy hash to give to [`check_dummy_password()`].
+///
+/// This is slow (it's a password hash, after all), so do it once when the [`ServerState`](crate::state::ServerState) is built or its
+/// configuration is reloaded, instead of during a login.
+///
+/// # Arguments
+/// - `params`: The [`Argon2Params`] with which new passwords are hashed.
+///
+/// # Returns
+/// The hash of a dummy password, hashed with the same parameters as new passwords.
+///
+/// # Errors
+/// This function errors if the given `params` are invalid or if we failed to hash the dummy password.
+#[inline]
+pub fn dummy_password_hash(params: &Argon2Params) -> Result<String, PasswordError> { hash_password(DUMMY_PASSWORD, params) }
+
+/// Compares a plaintext password against a dummy hash, discarding the result.
+///
+/// This is used when someone tries to log in as a user that doesn't exist. Without it, such attempts are answered much faster than
+/// attempts with a wrong password for an existing user, which would allow attackers to find out which usernames exist by timing the
+/// responses.
+///
+/// # Arguments
+/// - `password`: The given, plaintext password to compare.
+/// - `hash`: The dummy hash to compare against, as generated by [`dummy_password_hash()`].
+///
+/// # Errors
+/// This function errors if the given `hash` is not a valid password hash.
+#[inline]
+pub fn check_dummy_password(password: &str, hash: &str) -> Result<(), PasswordError> {
+    check_password(password, hash)?;
+    Ok(())
 }
 
 
 
+/// Generates a new, random secret for TOTP (two-factor authentication).
+///
+/// # Returns
+/// The secret, as a Base32-encoded string.
+#[inline]
+pub fn generate_totp_secret() -> String { Secret::generate_secret().to_encoded().to_string() }
+
+/// Builds an `otpauth://`-URI for the given TOTP secret, which can be given to authenticator apps to enroll.
+///
+/// # Arguments
+/// - `secret`: The Base32-encoded secret (see [`generate_totp_secret()`]).
+/// - `issuer`: The name of the service that issued the secret.
+/// - `account`: The name of the account for which the secret is used.
+///
+/// # Returns
+/// The `otpauth://`-URI as a string.
+///
+/// # Errors
+/// This function errors if the given `secret` is not valid Base32, or if the `issuer` or `account` contain illegal characters.
+pub fn totp_uri(secret: &str, issuer: &str, account: &str) -> Result<String, TotpError> {
+    let secret: Vec<u8> = match Secret::Encoded(secret.into()).to_bytes() {
+        Ok(secret) => secret,
+        Err(err) => return Err(TotpError::SecretParse { err }),
+    };
+    match TOTP::new(TotpAlgorithm::SHA1, TOTP_DIGITS, TOTP_SKEW, TOTP_STEP_SECS, secret, Some(issuer.into()), account.into()) {
+        Ok(totp) => Ok(totp.get_url()),
+        Err(err) => Err(TotpError::Url { err }),
+    }
+}
+
+/// Checks if a given TOTP code is valid for the current time.
+///
+/// # Arguments
+/// - `secret`: The Base32-encoded secret (see [`generate_totp_secret()`]).
+/// - `code`: The code given by the user.
+///
+/// # Returns
+/// True if the code is valid within [`TOTP_SKEW`] steps of [`TOTP_STEP_SECS`] seconds, or false otherwise.
+///
+/// # Errors
+/// This function errors if the given `secret` is not valid Base32 or if we failed to get the current time.
+pub fn check_totp(secret: &str, code: &str) -> Result<bool, TotpError> {
+    let secret: Vec<u8> = match Secret::Encoded(secret.into()).to_bytes() {
+        Ok(secret) => secret,
+        Err(err) => return Err(TotpError::SecretParse { err }),
+    };
+    match TOTP::new_unchecked(TotpAlgorithm::SHA1, TOTP_DIGITS, TOTP_SKEW, TOTP_STEP_SECS, secret, None, String::new()).check_current(code) {
+        Ok(valid) => Ok(valid),
+        Err(err) => Err(TotpError::Clock { err }),
+    }
+}
+
+
+
+/// Generates a new, random token that can be used once to reset a user's password.
+///
+/// # Returns
+/// A random alphanumeric string of [`RESET_TOKEN_LEN`] characters.
+///
+/// Note that only the hash of this token (see [`hash_reset_token()`]) should be stored.
+#[inline]
+pub fn generate_reset_token() -> String { OsRng.sample_iter(&Alphanumeric).take(RESET_TOKEN_LEN).map(char::from).collect() }
+
+/// Hashes a password reset token for storage.
+///
+/// Unlike passwords, reset tokens are long and random, so a fast, unsalted hash suffices and allows us to look them up by hash.
+///
+/// # Arguments
+/// - `token`: The token to hash (see [`generate_reset_token()`]).
+///
+/// # Returns
+/// The SHA-256 hash of the token, as a hexadecimal string.
+#[inline]
+pub fn hash_reset_token(token: &str) -> String { format!("{:x}", Sha256::digest(token.as_bytes())) }
+
+
+
+/// Loads the [`Key`] with which cookies are encrypted from a file, generating (and writing) a new one if it doesn't exist yet.
+///
+/// This keeps the login cookies valid across restarts of the server. The file contains the raw [`KEY_LEN`] bytes of the key, and is
+/// created readable by its owner only.
+///
+/// # Arguments
+/// - `path`: The path of the key file.
+///
+/// # Returns
+/// The loaded (or newly generated) [`Key`].
+///
+/// # Errors
+/// This function errors if we failed to read or write the file, or if it didn't contain a key of the right length.
+pub fn load_or_generate_key(path: &Path) -> Result<Key, KeyError> {
+    match fs::read(path) {
+        Ok(raw) => {
+            debug!("Loading cookie key from '{}'...", path.display());
+            if raw.len() != KEY_LEN {
+                return Err(KeyError::Length { path: path.into(), got: raw.len() });
+            }
+            Ok(Key::from(&raw))
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            debug!("Generating new cookie key in '{}'...", path.display());
+            if let Some(dir) = path.parent() {
+                if !dir.as_os_str().is_empty() {
+                    if let Err(err) = fs::create_dir_all(dir) {
+                        return Err(KeyError::DirCreate { path: dir.into(), err });
+                    }
+                }
+            }
+
+            // NOTE: We create it with restricted permissions right away, such that the key is never readable by others
+            let key: Key = Key::generate();
+            let mut file = match OpenOptions::new().write(true).create_new(true).mode(0o600).open(path) {
+                Ok(file) => file,
+                Err(err) => return Err(KeyError::Write { path: path.into(), err }),
+            };
+            if let Err(err) = file.write_all(key.master()).and_then(|_| file.sync_all()) {
+                return Err(KeyError::Write { path: path.into(), err });
+            }
+            Ok(key)
+        },
+        Err(err) => Err(KeyError::Read { path: path.into(), err }),
+    }
+}
+
+
+
+/// Generates a new, random identifier for a login token.
+///
+/// # Returns
+/// A random alphanumeric string of [`TOKEN_ID_LEN`] characters.
+#[inline]
+fn generate_token_id() -> String { OsRng.sample_iter(&Alphanumeric).take(TOKEN_ID_LEN).map(char::from).collect() }
+
 /// Creates an opaque login string that can be sent to users to authorize them post-login.
 ///
 /// # Arguments
+/// - `key`: The [`Key`] with which to sign the token. Only used if the `jwt`-feature is enabled.
 /// - `id`: The identifier of the user for which the token is valid.
 /// - `role`: The role of the user for which the token is valid.
+/// - `valid_min`: The time (in minutes) that the token is valid. This is embedded in the token itself.
 ///
 /// # Returns
 /// An already serialized string that embeds the token.
 ///
-/// Note that this token is not signed. Instead, another method of encryption must be used (e.g., [`PrivateCookieJar`](axum_extra::extract::PrivateCookieJar)s).
+/// Note that, unless the `jwt`-feature is enabled, this token is not signed. Instead, another method of encryption must be used (e.g.,
+/// [`PrivateCookieJar`](axum_extra::extract::PrivateCookieJar)s). This is also what prevents users from forging a longer validity.
 ///
 /// # Errors
 /// This function may error if we failed to serialize the token internally.
+#[cfg(not(feature = "jwt"))]
 #[inline]
-pub fn create_token(id: u64, role: Role) -> Result<String, TokenError> {
-    match serde_json::to_string(&LoginToken { id, role, issued: Utc::now() }) {
+pub fn create_token(_key: &Key, id: u64, role: Role, valid_min: i64) -> Result<String, TokenError> {
+    match serde_json::to_string(&LoginToken { jti: generate_token_id(), id, role, issued: Utc::now(), valid_minutes: valid_min }) {
         Ok(token) => Ok(token),
         Err(err) => Err(TokenError::Serialize { err }),
     }
 }
+/// Creates a JWT login string that can be sent to users to authorize them post-login.
+///
+/// # Arguments
+/// - `key`: The [`Key`] with which to sign the token (using HS256).
+/// - `id`: The identifier of the user for which the token is valid.
+/// - `role`: The role of the user for which the token is valid.
+/// - `valid_min`: The time (in minutes) that the token is valid. Used to compute its `exp`-claim, which is protected by the signature.
+///
+/// # Returns
+/// An already serialized and signed string that embeds the token.
+///
+/// # Errors
+/// This function may error if we failed to encode the token internally.
+#[cfg(feature = "jwt")]
+#[inline]
+pub fn create_token(key: &Key, id: u64, role: Role, valid_min: i64) -> Result<String, TokenError> {
+    let now: DateTime<Utc> = Utc::now();
+    let claims: LoginClaims =
+        LoginClaims { jti: generate_token_id(), sub: id, role, iat: now.timestamp(), exp: (now + Duration::minutes(valid_min)).timestamp() };
+    match jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(key.signing())) {
+        Ok(token) => Ok(token),
+        Err(err) => Err(TokenError::Encode { err }),
+    }
+}
+
+/// Decodes a raw token string to a [`LoginToken`], asserting it has not yet expired according to its own validity.
+///
+/// Note that this does _not_ check whether the token has been revoked or whether its user still exists; use [`check_token()`] for that.
+///
+/// # Arguments
+/// - `_key`: The [`Key`] with which the token is signed. Unused for plain JSON tokens.
+/// - `token`: Some opaque string token that we will decode.
+///
+/// # Returns
+/// The decoded [`LoginToken`], or a [`TokenInvalid`] describing why the token could not be decoded or has expired.
+#[cfg(not(feature = "jwt"))]
+pub fn decode_token(_key: &Key, token: &str) -> Result<LoginToken, TokenInvalid> {
+    match serde_json::from_str::<LoginToken>(token) {
+        Ok(token) => {
+            // Check if the token is still valid
+            let age: Duration = Utc::now() - token.issued;
+            if age >= Duration::minutes(token.valid_minutes) {
+                return Err(TokenInvalid::Expired { id: token.id, age: age.num_minutes(), valid_time: token.valid_minutes });
+            }
+            Ok(token)
+        },
+        Err(err) => Err(TokenInvalid::Deserialize { raw: token.into(), err }),
+    }
+}
+/// Decodes a raw JWT string to a [`LoginToken`], asserting its signature is valid and it has not yet expired according to its `exp`-claim.
+///
+/// Note that this does _not_ check whether the token has been revoked or whether its user still exists; use [`check_token()`] for that.
+///
+/// # Arguments
+/// - `key`: The [`Key`] with which the token is signed.
+/// - `token`: Some JWT string that we will decode.
+///
+/// # Returns
+/// The decoded [`LoginToken`], or a [`TokenInvalid`] describing why the token could not be decoded, verified or has expired.
+#[cfg(feature = "jwt")]
+pub fn decode_token(key: &Key, token: &str) -> Result<LoginToken, TokenInvalid> {
+    // Verify the signature only; we check the expiry ourselves for better error reporting
+    let mut validation: Validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = false;
+    let claims: LoginClaims = match jsonwebtoken::decode::<LoginClaims>(token, &DecodingKey::from_secret(key.signing()), &validation) {
+        Ok(data) => data.claims,
+        Err(err) => return Err(TokenInvalid::Jwt { err }),
+    };
+
+    // Check the expiry from the claim
+    let now: i64 = Utc::now().timestamp();
+    let valid_minutes: i64 = (claims.exp - claims.iat) / 60;
+    if now >= claims.exp {
+        return Err(TokenInvalid::Expired { id: claims.sub, age: (now - claims.iat) / 60, valid_time: valid_minutes });
+    }
+    Ok(LoginToken { jti: claims.jti, id: claims.sub, role: claims.role, issued: DateTime::from_timestamp(claims.iat, 0).unwrap_or_default(), valid_minutes })
+}
 
 /// Verifies if the given token is valid.
 ///
 /// # Arguments
 /// - `database`: A [`Database`] connection that we'll use to see if the user in the token exists.
+/// - `key`: The [`Key`] with which the token is signed. Only used if the `jwt`-feature is enabled.
 /// - `token`: Some opaque string token that we will check.
 ///
 /// # Returns
@@ -272,32 +874,169 @@ pub fn create_token(id: u64, role: Role) -> Result<String, TokenError> {
 /// # Errors
 /// This function errors if we failed to use the given database.
 #[inline]
-pub fn check_token(database: &Database, token: &str) -> Result<Result<UserInfo, TokenInvalid>, TokenError> {
-    match serde_json::from_str::<LoginToken>(token) {
-        Ok(token) => {
-            debug!("Got presented login token '{token:?}'");
+pub async fn check_token(database: &Database, key: &Key, token: &str) -> Result<Result<UserInfo, TokenInvalid>, TokenError> {
+    // First check if the token is decodable and still valid
+    let token: LoginToken = match decode_token(key, token) {
+        Ok(token) => token,
+        Err(err) => return Ok(Err(err)),
+    };
+    debug!("Got presented login token '{token:?}'");
 
-            // First check if the token is still valid
-            let age: i64 = (Utc::now() - token.issued).num_minutes();
-            if age > TOKEN_VALID_TIME_MIN {
-                // Assume not logged-in
-                return Ok(Err(TokenInvalid::Expired { id: token.id, age, valid_time: TOKEN_VALID_TIME_MIN }));
-            }
+    // Then check if it hasn't been revoked
+    match database.is_token_revoked(&token.jti).await {
+        Ok(false) => {},
+        Ok(true) => return Ok(Err(TokenInvalid::Revoked { id: token.id, jti: token.jti })),
+        Err(err) => return Err(TokenError::RevokedRetrieve { jti: token.jti, err }),
+    }
 
-            // Then check if we can get the user from the database
-            match database.get_user_by_id(token.id) {
-                Ok(Some(user)) => {
-                    // Finally, check if the role in the token is what we know of the user in the database
-                    if user.role == token.role {
-                        Ok(Ok(user))
-                    } else {
-                        Ok(Err(TokenInvalid::IncorrectRole { id: user.id, got: token.role, expected: user.role }))
-                    }
-                },
-                Ok(None) => Ok(Err(TokenInvalid::UserNotFound { id: token.id })),
-                Err(err) => Err(TokenError::UserInfoRetrieve { id: token.id, err }),
+    // Then check if we can get the user from the database
+    match database.get_user_by_id(token.id).await {
+        Ok(Some(user)) => {
+            // Finally, check if the role in the token is what we know of the user in the database
+            if user.role == token.role {
+                Ok(Ok(user))
+            } else {
+                Ok(Err(TokenInvalid::IncorrectRole { id: user.id, got: token.role, expected: user.role }))
             }
         },
-        Err(err) => Ok(Err(TokenInvalid::Deserialize { raw: token.into(), err })),
+        Ok(None) => Ok(Err(TokenInvalid::UserNotFound { id: token.id })),
+        Err(err) => Err(TokenError::UserInfoRetrieve { id: token.id, err }),
+    }
+}
+
+
+
+
+
+/***** TESTS *****/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The (cheapest possible) Argon2 parameters to hash passwords with, such that the tests don't spend all their time hashing.
+    const TEST_ARGON2: Argon2Params = Argon2Params { m_cost: 8, t_cost: 1, p_cost: 1 };
+
+    /// Creates a fresh, migrated in-memory database.
+    async fn test_db() -> Database {
+        let db: Database = Database::sqlite_in_memory().expect("Failed to create in-memory database");
+        db.migrate().await.expect("Failed to migrate in-memory database");
+        db
+    }
+
+
+
+    #[test]
+    fn test_role_u8() {
+        for (role, value) in [(Role::Player, 1), (Role::DungeonMaster, 5), (Role::Root, 10)] {
+            assert_eq!(u8::from(role), value);
+            assert_eq!(Role::try_from(value).unwrap(), role);
+        }
+        for value in [0, 2, 4, 6, 9, 11, u8::MAX] {
+            assert!(Role::try_from(value).is_err(), "{value} parsed as a role");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_roundtrip() {
+        let db: Database = test_db().await;
+        let key: Key = Key::generate();
+        for role in Role::all() {
+            let hash: String = hash_password("hunter22", &TEST_ARGON2).unwrap();
+            let id: u64 = db.create_user(format!("user-{}", u8::from(*role)), hash, *role).await.unwrap();
+
+            let token: String = create_token(&key, id, *role, TOKEN_VALID_TIME_MIN).unwrap();
+            let user: UserInfo = check_token(&db, &key, &token).await.unwrap().unwrap();
+            assert_eq!(user.id, id);
+            assert_eq!(user.role, *role);
+        }
+    }
+
+    #[test]
+    fn test_token_valid_zero_expires() {
+        let key: Key = Key::generate();
+        let token: String = create_token(&key, 1, Role::Player, 0).unwrap();
+        assert!(matches!(decode_token(&key, &token), Err(TokenInvalid::Expired { id: 1, valid_time: 0, .. })));
+    }
+
+    #[test]
+    fn test_custom_params_verify() {
+        let params: Argon2Params = Argon2Params { m_cost: 16, t_cost: 2, p_cost: 2 };
+        let hash: String = hash_password("hunter22", &params).unwrap();
+        assert!(hash.contains("m=16,t=2,p=2"), "Hash {hash:?} does not embed custom parameters");
+        assert!(check_password("hunter22", &hash).unwrap());
+        assert!(!check_password("hunter23", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_check_password_malformed() {
+        assert!(matches!(check_password("hunter22", "not-a-hash"), Err(PasswordError::Parse { .. })));
+        assert!(matches!(check_password("hunter22", "$argon2id$v=19$garbage"), Err(PasswordError::Parse { .. })));
+        assert!(matches!(check_password("hunter22", "$2b$garbage"), Err(PasswordError::ParseBcrypt { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_dummy_hash_precomputed() {
+        use semver::Version;
+
+        use crate::state::{ServerConfig, ServerState};
+
+        let config: ServerConfig = ServerConfig { argon2: TEST_ARGON2, ..Default::default() };
+        let state: ServerState = ServerState::new("test", Version::new(0, 0, 0), test_db().await, config.clone());
+        let hash: String = state.dummy_hash.read().clone().expect("No dummy hash generated when building the state");
+        assert!(hash.contains("m=8,t=1,p=1"), "Dummy hash {hash:?} does not use the configured parameters");
+        check_dummy_password("hunter22", &hash).unwrap();
+
+        // Reloading with other parameters regenerates it
+        let params: Argon2Params = Argon2Params { m_cost: 16, t_cost: 2, p_cost: 1 };
+        state.reload_config(ServerConfig { argon2: params, ..config });
+        let hash: String = state.dummy_hash.read().clone().expect("No dummy hash generated when reloading the config");
+        assert!(hash.contains("m=16,t=2,p=1"), "Dummy hash {hash:?} does not use the reloaded parameters");
+    }
+
+    #[test]
+    fn test_mixed_hashes() {
+        let argon2: String = hash_password("hunter22", &TEST_ARGON2).unwrap();
+        let bcrypt: String = bcrypt::hash("hunter22", 4).unwrap();
+        assert!(!is_bcrypt_hash(&argon2));
+        assert!(is_bcrypt_hash(&bcrypt));
+        for hash in [&argon2, &bcrypt] {
+            assert!(check_password("hunter22", hash).unwrap(), "Correct password rejected for {hash:?}");
+            assert!(!check_password("hunter23", hash).unwrap(), "Wrong password accepted for {hash:?}");
+        }
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let old: Argon2Params = TEST_ARGON2;
+        let new: Argon2Params = Argon2Params { m_cost: 16, t_cost: 2, p_cost: 1 };
+        let hash: String = hash_password("hunter22", &old).unwrap();
+        assert!(!needs_rehash(&hash, &old), "Hash with current parameters needs rehash");
+        assert!(needs_rehash(&hash, &new), "Hash with weaker parameters doesn't need rehash");
+
+        // Stronger than configured is fine (e.g., after lowering the parameters again)
+        let hash: String = hash_password("hunter22", &new).unwrap();
+        assert!(!needs_rehash(&hash, &old), "Hash with stronger parameters needs rehash");
+        assert!(!needs_rehash(&hash, &new));
+
+        // Other variants and algorithms always do, unparseable ones never do
+        let argon2i: String = Argon2::new(Argon2Algorithm::Argon2i, Argon2Version::V0x13, Params::new(old.m_cost, old.t_cost, old.p_cost, None).unwrap())
+            .hash_password(b"hunter22", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        assert!(needs_rehash(&argon2i, &old), "Argon2i hash doesn't need rehash");
+        assert!(needs_rehash(&bcrypt::hash("hunter22", 4).unwrap(), &old), "bcrypt hash doesn't need rehash");
+        assert!(!needs_rehash("not-a-hash", &old));
+    }
+
+    #[test]
+    fn test_role_all_complete() {
+        for value in 0..=u8::MAX {
+            if let Ok(role) = Role::try_from(value) {
+                assert!(Role::all().contains(&role), "Role::all() misses {role:?} (parsed from {value})");
+            }
+        }
+        for role in Role::all() {
+            assert_eq!(Role::try_from(u8::from(*role)).unwrap(), *role);
+        }
     }
 }
@@ -0,0 +1,229 @@
+//  CLIENT.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:02:52
+//  Last edited:
+//    14 Oct 2026, 18:03:32
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Provides a typed client for the API of the server, for use by other
+//!   Rust services.
+//!   
+//!   The [`DndClient`] keeps track of the login cookie, such that calls
+//!   made after [`DndClient::login()`] are done as the logged-in user.
+//!   
+//!   # Example
+//!   ```no_run
+//!   use dnd_server::client::DndClient;
+//!   use dnd_server::paths::auth::LoginRequest;
+//!   
+//!   # async fn example() -> Result<(), dnd_server::client::Error> {
+//!   let client = DndClient::new("https://dnd.example.com")?;
+//!   println!("Server is at version {}", client.version().await?.version);
+//!   
+//!   client.login(&LoginRequest { name: "amy".into(), pass: "hunter2".into(), totp: None, remember: false }).await?;
+//!   println!("Logged in as user {}", client.whoami().await?.id);
+//!   # Ok(())
+//!   # }
+//!   ```
+//
+
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::database::UserSummary;
+use crate::middleware::client_version::CLIENT_VERSION_HEADER;
+use crate::paths::auth::{LoginRequest, LOGIN_PATH, LOGOUT_PATH};
+use crate::paths::users::WHOAMI_PATH;
+use crate::paths::version::{VersionResponse, PATH as VERSION_PATH};
+use crate::spec::{ApiError, Path};
+
+
+/***** ERRORS *****/
+/// Defines errors originating from the [`DndClient`].
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to build the underlying [`reqwest::Client`].
+    ClientCreate { err: reqwest::Error },
+    /// Failed to send a request to the server.
+    Request { path: &'static str, err: reqwest::Error },
+    /// Failed to parse the body of the server's response.
+    ResponseParse { path: &'static str, err: reqwest::Error },
+    /// The server responded with a non-success status code.
+    Status { path: &'static str, status: StatusCode, message: String },
+}
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            ClientCreate { .. } => write!(f, "Failed to create HTTP client"),
+            Request { path, .. } => write!(f, "Failed to send request to '{path}'"),
+            ResponseParse { path, .. } => write!(f, "Failed to parse response of '{path}'"),
+            Status { path, status, message } => write!(f, "Request to '{path}' failed with {status}: {message}"),
+        }
+    }
+}
+impl error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            ClientCreate { err } => Some(err),
+            Request { err, .. } => Some(err),
+            ResponseParse { err, .. } => Some(err),
+            Status { .. } => None,
+        }
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// A typed client for the API of the server.
+///
+/// It sends its own version along in the `x-client-version`-header, and remembers the login cookie between calls.
+#[derive(Clone, Debug)]
+pub struct DndClient {
+    /// The base URL of the server (e.g., `https://dnd.example.com`).
+    base:   String,
+    /// The underlying client, which also stores the cookies.
+    client: Client,
+}
+impl DndClient {
+    /// Constructor for the DndClient.
+    ///
+    /// # Arguments
+    /// - `base`: The base URL of the server (e.g., `https://dnd.example.com`).
+    ///
+    /// # Returns
+    /// A new DndClient that isn't logged in yet.
+    ///
+    /// # Errors
+    /// This function errors if we failed to build the underlying HTTP client.
+    pub fn new(base: impl Into<String>) -> Result<Self, Error> {
+        let base: String = base.into();
+
+        // Build a client that keeps track of cookies and tells the server who we are
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert(CLIENT_VERSION_HEADER, HeaderValue::from_static(env!("CARGO_PKG_VERSION")));
+        match Client::builder().cookie_store(true).default_headers(headers).build() {
+            Ok(client) => Ok(Self { base, client }),
+            Err(err) => Err(Error::ClientCreate { err }),
+        }
+    }
+
+    /// Starts a request to the given path.
+    ///
+    /// # Arguments
+    /// - `path`: The [`Path`] to send the request to.
+    ///
+    /// # Returns
+    /// A [`RequestBuilder`] with the path's method and URL already filled in.
+    #[inline]
+    fn request(&self, path: &Path) -> RequestBuilder { path.request(&self.client, &self.base) }
+
+    /// Sends a request, and checks that the server responded with a success status code.
+    ///
+    /// # Arguments
+    /// - `path`: The [`Path`] that the request is sent to (used for debugging).
+    /// - `request`: The [`RequestBuilder`] of the request to send.
+    ///
+    /// # Returns
+    /// The server's [`Response`].
+    ///
+    /// # Errors
+    /// This function errors if we failed to send the request, or if the server responded with a non-success status code.
+    async fn send(path: &Path, request: RequestBuilder) -> Result<Response, Error> {
+        let res: Response = match request.send().await {
+            Ok(res) => res,
+            Err(err) => return Err(Error::Request { path: path.path, err }),
+        };
+        let status: StatusCode = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        // Prefer the message of an ApiError, if the server sent one
+        let message: String = res.text().await.unwrap_or_default();
+        let message: String = match serde_json::from_str::<ApiError>(&message) {
+            Ok(err) => err.message,
+            Err(_) => message,
+        };
+        Err(Error::Status { path: path.path, status, message })
+    }
+
+    /// Parses the JSON body of a response.
+    ///
+    /// # Arguments
+    /// - `path`: The [`Path`] that the request was sent to (used for debugging).
+    /// - `res`: The [`Response`] to parse the body of.
+    ///
+    /// # Returns
+    /// The parsed body.
+    ///
+    /// # Errors
+    /// This function errors if we failed to download the body or if it wasn't valid.
+    async fn json<T: DeserializeOwned>(path: &Path, res: Response) -> Result<T, Error> {
+        match res.json().await {
+            Ok(body) => Ok(body),
+            Err(err) => Err(Error::ResponseParse { path: path.path, err }),
+        }
+    }
+
+
+
+    /// Asks the server for its name and version.
+    ///
+    /// # Returns
+    /// The server's [`VersionResponse`].
+    ///
+    /// # Errors
+    /// This function errors if we failed to reach the server or if it responded with an error.
+    pub async fn version(&self) -> Result<VersionResponse<'static>, Error> {
+        let res: Response = Self::send(&VERSION_PATH, self.request(&VERSION_PATH)).await?;
+        Self::json(&VERSION_PATH, res).await
+    }
+
+    /// Logs in, such that subsequent calls are done as the given user.
+    ///
+    /// # Arguments
+    /// - `request`: The [`LoginRequest`] with the user's credentials.
+    ///
+    /// # Errors
+    /// This function errors if we failed to reach the server or if it refused the credentials (with `401 NOT AUTHORIZED`).
+    pub async fn login(&self, request: &LoginRequest<'_>) -> Result<(), Error> {
+        Self::send(&LOGIN_PATH, self.request(&LOGIN_PATH).json(request)).await?;
+        Ok(())
+    }
+
+    /// Logs out, revoking the login cookie.
+    ///
+    /// # Errors
+    /// This function errors if we failed to reach the server or if it responded with an error.
+    pub async fn logout(&self) -> Result<(), Error> {
+        Self::send(&LOGOUT_PATH, self.request(&LOGOUT_PATH)).await?;
+        Ok(())
+    }
+
+    /// Asks the server which user we're logged in as.
+    ///
+    /// # Returns
+    /// The [`UserSummary`] of the logged-in user.
+    ///
+    /// # Errors
+    /// This function errors if we failed to reach the server or if we're not logged in (with `401 NOT AUTHORIZED`).
+    pub async fn whoami(&self) -> Result<UserSummary, Error> {
+        let res: Response = Self::send(&WHOAMI_PATH, self.request(&WHOAMI_PATH)).await?;
+        Self::json(&WHOAMI_PATH, res).await
+    }
+}
@@ -0,0 +1,172 @@
+//  CONFIG.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:36:19
+//  Last edited:
+//    14 Oct 2026, 17:54:54
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the optional configuration file of the server.
+//!   
+//!   Every field in the file is optional, and overrides the matching
+//!   command-line argument if given. The file is re-read when the server
+//!   receives `SIGHUP`, upon which the hot-reloadable fields are applied
+//!   immediately:
+//!   - `token_valid_minutes`
+//!   - `remember_valid_minutes`
+//!   - `password_min_length`
+//!   - `argon2_m_cost`, `argon2_t_cost` and `argon2_p_cost`
+//!   - `auth_rate_limit` and `auth_rate_window`
+//!   - `min_client_version` and `require_client_version`
+//!   
+//!   Changes to the other fields (`database_url` and `db_pool_size`) are
+//!   ignored with a warning until the server is restarted.
+//
+
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{error, fs};
+
+use log::debug;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Argon2Params;
+use crate::state::ServerConfig;
+
+
+/***** ERRORS *****/
+/// Defines errors originating from loading a [`ConfigFile`].
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to parse the file as TOML.
+    FileParse { path: PathBuf, err: toml::de::Error },
+    /// Failed to read the file.
+    FileRead { path: PathBuf, err: std::io::Error },
+}
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            FileParse { path, .. } => write!(f, "Failed to parse config file '{}' as valid TOML", path.display()),
+            FileRead { path, .. } => write!(f, "Failed to read config file '{}'", path.display()),
+        }
+    }
+}
+impl error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            FileParse { err, .. } => Some(err),
+            FileRead { err, .. } => Some(err),
+        }
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// The layout of the configuration file.
+///
+/// See the module documentation for which fields may be hot-reloaded.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// The URL of the database to store persistent data in. Not hot-reloadable.
+    pub database_url: Option<String>,
+    /// The maximum number of connections to keep open to the database. Not hot-reloadable.
+    pub db_pool_size: Option<u32>,
+
+    /// The time (in minutes) that login tokens are valid.
+    pub token_valid_minutes:    Option<i64>,
+    /// The time (in minutes) that login tokens are valid if the user logs in with "remember me".
+    pub remember_valid_minutes: Option<i64>,
+    /// The minimum length (in characters) of user passwords.
+    pub password_min_length:    Option<usize>,
+
+    /// The Argon2 memory cost (in KiB) used when hashing passwords.
+    pub argon2_m_cost: Option<u32>,
+    /// The Argon2 time cost (in iterations) used when hashing passwords.
+    pub argon2_t_cost: Option<u32>,
+    /// The Argon2 parallelism cost (in lanes) used when hashing passwords.
+    pub argon2_p_cost: Option<u32>,
+
+    /// The maximum number of requests a single IP address may do to the `/v1/auth` paths per `auth_rate_window`.
+    pub auth_rate_limit:  Option<u32>,
+    /// The window (in seconds) in which a single IP address may do at most `auth_rate_limit` requests to the `/v1/auth` paths.
+    pub auth_rate_window: Option<u64>,
+
+    /// The oldest version of the client that is still compatible with this server.
+    pub min_client_version:     Option<Version>,
+    /// Whether clients must give their version on API requests.
+    pub require_client_version: Option<bool>,
+}
+impl ConfigFile {
+    /// Loads a ConfigFile from disk.
+    ///
+    /// # Arguments
+    /// - `path`: The path to the file to load.
+    ///
+    /// # Returns
+    /// A new ConfigFile.
+    ///
+    /// # Errors
+    /// This function errors if we failed to read the file or if it wasn't valid.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        debug!("Loading config file '{}'...", path.display());
+        let raw: String = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) => return Err(Error::FileRead { path: path.into(), err }),
+        };
+        match toml::from_str(&raw) {
+            Ok(file) => Ok(file),
+            Err(err) => Err(Error::FileParse { path: path.into(), err }),
+        }
+    }
+
+    /// Overrides the hot-reloadable fields of a [`ServerConfig`] with the ones given in this file.
+    ///
+    /// # Arguments
+    /// - `config`: The [`ServerConfig`] to override. Fields not given in this file are left as-is.
+    ///
+    /// # Returns
+    /// The overridden [`ServerConfig`].
+    pub fn apply(&self, mut config: ServerConfig) -> ServerConfig {
+        if let Some(token_valid_min) = self.token_valid_minutes {
+            config.token_valid_min = token_valid_min;
+        }
+        if let Some(remember_valid_min) = self.remember_valid_minutes {
+            config.remember_valid_min = remember_valid_min;
+        }
+        if let Some(password_min_len) = self.password_min_length {
+            config.password_min_len = password_min_len;
+        }
+        config.argon2 = Argon2Params {
+            m_cost: self.argon2_m_cost.unwrap_or(config.argon2.m_cost),
+            t_cost: self.argon2_t_cost.unwrap_or(config.argon2.t_cost),
+            p_cost: self.argon2_p_cost.unwrap_or(config.argon2.p_cost),
+        };
+        if let Some(auth_rate_limit) = self.auth_rate_limit {
+            config.auth_rate_limit = auth_rate_limit;
+        }
+        if let Some(auth_rate_window) = self.auth_rate_window {
+            config.auth_rate_window = Duration::from_secs(auth_rate_window);
+        }
+        if let Some(min_client_version) = &self.min_client_version {
+            config.min_client_version = min_client_version.clone();
+        }
+        if let Some(require_client_version) = self.require_client_version {
+            config.require_client_version = require_client_version;
+        }
+        config
+    }
+}
@@ -0,0 +1,420 @@
+//  DICE.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 19:02:37
+//  Last edited:
+//    14 Oct 2026, 19:03:23
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements parsing and rolling standard dice notation, e.g.,
+//!   `1d20+2d4-1`.
+//!   
+//!   Rolls are done by a [`DiceRoller`], which uses the thread-local
+//!   random number generator by default but can be given any other (e.g.,
+//!   a seeded one, for reproducible results).
+//
+
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::str::FromStr;
+
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+
+/***** CONSTANTS *****/
+/// The maximum number of dice that may be rolled in a single [`Notation`].
+pub const MAX_DICE: u32 = 1000;
+/// The maximum number of sides a single die may have.
+pub const MAX_SIDES: u32 = 1000;
+/// The maximum (absolute) value of a single constant modifier.
+pub const MAX_MODIFIER: i64 = 1_000_000;
+
+
+
+
+
+/***** ERRORS *****/
+/// Defines errors originating from parsing a [`Notation`].
+#[derive(Debug)]
+pub enum ParseError {
+    /// The notation was empty.
+    Empty,
+    /// Expected a number after a `d` at the given position.
+    ExpectedSides { pos: usize },
+    /// Expected a term at the given position.
+    ExpectedTerm { pos: usize },
+    /// A number was too large.
+    NumberTooLarge { pos: usize },
+    /// A modifier was too large.
+    ModifierTooLarge { pos: usize, max: i64 },
+    /// A die had too many sides.
+    TooManySides { pos: usize, sides: u32, max: u32 },
+    /// Too many dice in the notation.
+    TooManyDice { count: u64, max: u32 },
+    /// Found an unexpected character.
+    UnexpectedChar { pos: usize, c: char },
+    /// A term rolled no dice.
+    ZeroDice { pos: usize },
+    /// A die had no sides.
+    ZeroSides { pos: usize },
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use ParseError::*;
+        match self {
+            Empty => write!(f, "Empty dice notation"),
+            ExpectedSides { pos } => write!(f, "Expected number of sides after 'd' at position {pos}"),
+            ExpectedTerm { pos } => write!(f, "Expected dice (e.g., '2d6') or a number at position {pos}"),
+            NumberTooLarge { pos } => write!(f, "Number at position {pos} is too large"),
+            ModifierTooLarge { pos, max } => write!(f, "Modifier at position {pos} is too large (maximum is {max})"),
+            TooManySides { pos, sides, max } => write!(f, "Die at position {pos} has {sides} sides, but at most {max} are allowed"),
+            TooManyDice { count, max } => write!(f, "Notation rolls {count} dice, but at most {max} are allowed"),
+            UnexpectedChar { pos, c } => write!(f, "Unexpected character '{c}' at position {pos}"),
+            ZeroDice { pos } => write!(f, "Term at position {pos} rolls zero dice"),
+            ZeroSides { pos } => write!(f, "Die at position {pos} has zero sides"),
+        }
+    }
+}
+impl error::Error for ParseError {}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Defines how to roll d20s.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// Roll normally.
+    Normal,
+    /// Roll every d20 twice and keep the highest.
+    Advantage,
+    /// Roll every d20 twice and keep the lowest.
+    Disadvantage,
+}
+
+/// Defines a single term in a [`Notation`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Term {
+    /// Roll `count` dice with `sides` sides each.
+    Dice { negative: bool, count: u32, sides: u32 },
+    /// Add a constant.
+    Modifier(i64),
+}
+
+
+
+/// The result of rolling a single die.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct DieRoll {
+    /// The number of sides of the die.
+    pub sides:    u32,
+    /// The value rolled.
+    pub value:    u32,
+    /// If rolled with advantage or disadvantage, the value of the other die that was discarded.
+    pub dropped:  Option<u32>,
+    /// Whether this die is subtracted from the total instead of added.
+    pub negative: bool,
+}
+
+/// The result of rolling a [`Notation`].
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct RollResult {
+    /// The notation that was rolled.
+    pub notation: String,
+    /// The individual dice rolled.
+    pub dice:     Vec<DieRoll>,
+    /// The sum of all constant modifiers.
+    pub modifier: i64,
+    /// The total of the roll.
+    pub total:    i64,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Represents parsed dice notation, e.g., `1d20+2d4-1`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Notation {
+    /// The terms making up the notation.
+    pub terms: Vec<Term>,
+}
+impl Notation {
+    /// Rolls all dice in this notation.
+    ///
+    /// # Arguments
+    /// - `mode`: Whether to roll any d20s with advantage or disadvantage.
+    /// - `rng`: The random number generator to roll with.
+    ///
+    /// # Returns
+    /// A [`RollResult`] with every die and the total.
+    pub fn roll(&self, mode: Mode, rng: &mut impl Rng) -> RollResult {
+        let mut dice: Vec<DieRoll> = Vec::new();
+        let mut modifier: i64 = 0;
+        let mut total: i64 = 0;
+        for term in &self.terms {
+            match *term {
+                Term::Dice { negative, count, sides } => {
+                    for _ in 0..count {
+                        let mut value: u32 = rng.gen_range(1..=sides);
+                        let mut dropped: Option<u32> = None;
+                        if sides == 20 && mode != Mode::Normal {
+                            let other: u32 = rng.gen_range(1..=sides);
+                            if (mode == Mode::Advantage) == (other > value) {
+                                dropped = Some(value);
+                                value = other;
+                            } else {
+                                dropped = Some(other);
+                            }
+                        }
+                        total += if negative { -(value as i64) } else { value as i64 };
+                        dice.push(DieRoll { sides, value, dropped, negative });
+                    }
+                },
+                Term::Modifier(value) => {
+                    modifier += value;
+                    total += value;
+                },
+            }
+        }
+        RollResult { notation: self.to_string(), dice, modifier, total }
+    }
+}
+impl Display for Notation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        for (i, term) in self.terms.iter().enumerate() {
+            match *term {
+                Term::Dice { negative, count, sides } => {
+                    if negative {
+                        write!(f, "-")?;
+                    } else if i > 0 {
+                        write!(f, "+")?;
+                    }
+                    write!(f, "{count}d{sides}")?;
+                },
+                Term::Modifier(value) => {
+                    if value >= 0 && i > 0 {
+                        write!(f, "+")?;
+                    }
+                    write!(f, "{value}")?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+impl FromStr for Notation {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let chars: Vec<(usize, char)> = s.char_indices().collect();
+
+        // Skips whitespace starting at the given index into `chars`, returning the index of the next non-whitespace character (if any)
+        let skip_ws = |mut i: usize| -> usize {
+            while chars.get(i).map(|(_, c)| c.is_whitespace()).unwrap_or(false) {
+                i += 1;
+            }
+            i
+        };
+
+        // Parses a number starting at the given index into `chars`, returning it and the index after it (if any)
+        let parse_num = |mut i: usize| -> Result<Option<(u64, usize)>, ParseError> {
+            let start: usize = i;
+            let mut num: u64 = 0;
+            while let Some((pos, c)) = chars.get(i).copied() {
+                match c.to_digit(10) {
+                    Some(digit) => match num.checked_mul(10).and_then(|num| num.checked_add(digit as u64)) {
+                        Some(next) => num = next,
+                        None => return Err(ParseError::NumberTooLarge { pos }),
+                    },
+                    None => break,
+                }
+                i += 1;
+            }
+            Ok(if i > start { Some((num, i)) } else { None })
+        };
+
+        let mut terms: Vec<Term> = Vec::new();
+        let mut n_dice: u64 = 0;
+        let mut i: usize = 0;
+        loop {
+            i = skip_ws(i);
+            if i >= chars.len() {
+                break;
+            }
+
+            // Parse the sign
+            let negative: bool = match chars[i].1 {
+                '+' if !terms.is_empty() => {
+                    i += 1;
+                    false
+                },
+                '-' => {
+                    i += 1;
+                    true
+                },
+                c if !terms.is_empty() => return Err(ParseError::UnexpectedChar { pos: chars[i].0, c }),
+                _ => false,
+            };
+            i = skip_ws(i);
+            let pos: usize = match chars.get(i) {
+                Some((pos, _)) => *pos,
+                None => return Err(ParseError::ExpectedTerm { pos: s.len() }),
+            };
+
+            // Parse the count (or the modifier)
+            let count: Option<u64> = match parse_num(i)? {
+                Some((num, next)) => {
+                    i = next;
+                    Some(num)
+                },
+                None => None,
+            };
+
+            // Parse the dice, if any
+            match chars.get(i) {
+                Some((_, 'd' | 'D')) => {
+                    i += 1;
+                    let count: u64 = count.unwrap_or(1);
+                    let sides: u64 = match parse_num(i)? {
+                        Some((num, next)) => {
+                            i = next;
+                            num
+                        },
+                        None => return Err(ParseError::ExpectedSides { pos: chars.get(i).map(|(pos, _)| *pos).unwrap_or(s.len()) }),
+                    };
+                    if count == 0 {
+                        return Err(ParseError::ZeroDice { pos });
+                    }
+                    if sides == 0 {
+                        return Err(ParseError::ZeroSides { pos });
+                    }
+                    if sides > MAX_SIDES as u64 {
+                        return Err(ParseError::TooManySides { pos, sides: sides.min(u32::MAX as u64) as u32, max: MAX_SIDES });
+                    }
+                    n_dice = n_dice.saturating_add(count);
+                    if n_dice > MAX_DICE as u64 {
+                        return Err(ParseError::TooManyDice { count: n_dice, max: MAX_DICE });
+                    }
+                    terms.push(Term::Dice { negative, count: count as u32, sides: sides as u32 });
+                },
+                _ => match count {
+                    Some(value) => {
+                        if value > MAX_MODIFIER as u64 {
+                            return Err(ParseError::ModifierTooLarge { pos, max: MAX_MODIFIER });
+                        }
+                        terms.push(Term::Modifier(if negative { -(value as i64) } else { value as i64 }));
+                    },
+                    None => {
+                        return Err(match chars.get(i) {
+                            Some((pos, c)) => ParseError::UnexpectedChar { pos: *pos, c: *c },
+                            None => ParseError::ExpectedTerm { pos },
+                        });
+                    },
+                },
+            }
+        }
+        Ok(Self { terms })
+    }
+}
+
+
+
+/// Rolls [`Notation`]s with some random number generator.
+///
+/// Use [`DiceRoller::new()`] for actual games, which rolls with [`rand::thread_rng()`]. [`DiceRoller::with_rng()`] accepts any other
+/// generator, such as a [`StdRng`](rand::rngs::StdRng) seeded with [`SeedableRng::seed_from_u64()`](rand::SeedableRng::seed_from_u64())
+/// to get the same results every time. Note that seeds are never accepted from clients.
+#[derive(Clone, Debug)]
+pub struct DiceRoller<R> {
+    /// The random number generator to roll with.
+    rng: R,
+}
+impl DiceRoller<ThreadRng> {
+    /// Constructor for the DiceRoller that rolls with the thread-local random number generator.
+    ///
+    /// # Returns
+    /// A new DiceRoller.
+    #[inline]
+    pub fn new() -> Self { Self::with_rng(rand::thread_rng()) }
+}
+impl Default for DiceRoller<ThreadRng> {
+    #[inline]
+    fn default() -> Self { Self::new() }
+}
+impl<R: Rng> DiceRoller<R> {
+    /// Constructor for the DiceRoller that rolls with the given random number generator.
+    ///
+    /// # Arguments
+    /// - `rng`: The random number generator to roll with.
+    ///
+    /// # Returns
+    /// A new DiceRoller.
+    #[inline]
+    pub fn with_rng(rng: R) -> Self { Self { rng } }
+
+    /// Rolls all dice in the given notation.
+    ///
+    /// # Arguments
+    /// - `notation`: The [`Notation`] to roll.
+    /// - `mode`: Whether to roll any d20s with advantage or disadvantage.
+    ///
+    /// # Returns
+    /// A [`RollResult`] with every die and the total.
+    #[inline]
+    pub fn roll(&mut self, notation: &Notation, mode: Mode) -> RollResult { notation.roll(mode, &mut self.rng) }
+}
+
+
+
+
+
+/***** TESTS *****/
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng as _;
+
+    use super::*;
+
+
+    #[test]
+    fn test_2d6_in_range() {
+        let notation: Notation = "2d6".parse().unwrap();
+        let mut roller: DiceRoller<StdRng> = DiceRoller::with_rng(StdRng::seed_from_u64(42));
+        for _ in 0..1000 {
+            let res: RollResult = roller.roll(&notation, Mode::Normal);
+            assert_eq!(res.dice.len(), 2);
+            assert!((2..=12).contains(&res.total), "2d6 rolled {}", res.total);
+            assert_eq!(res.total, res.dice.iter().map(|die| die.value as i64).sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn test_seeded_reproducible() {
+        let notation: Notation = "4d20+2d4-1".parse().unwrap();
+        for mode in [Mode::Normal, Mode::Advantage, Mode::Disadvantage] {
+            let mut lhs: DiceRoller<StdRng> = DiceRoller::with_rng(StdRng::seed_from_u64(42));
+            let mut rhs: DiceRoller<StdRng> = DiceRoller::with_rng(StdRng::seed_from_u64(42));
+            for _ in 0..100 {
+                let (lhs, rhs): (RollResult, RollResult) = (lhs.roll(&notation, mode), rhs.roll(&notation, mode));
+                assert_eq!(lhs.total, rhs.total);
+                assert_eq!(
+                    lhs.dice.iter().map(|die| (die.value, die.dropped)).collect::<Vec<_>>(),
+                    rhs.dice.iter().map(|die| (die.value, die.dropped)).collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+}
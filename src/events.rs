@@ -0,0 +1,72 @@
+//  EVENTS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 20:05:49
+//  Last edited:
+//    14 Oct 2026, 18:28:58
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the events that are sent live to everyone playing in a
+//!   campaign.
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{ChatMessage, InitiativeEntry, Resources};
+use crate::dice::RollResult;
+
+
+/***** LIBRARY *****/
+/// Defines the events that are fanned out to all clients connected to a campaign.
+///
+/// On the wire, these are serialized as JSON objects with a `kind` field that determines the variant.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// Someone rolled the dice.
+    Roll {
+        /// The identifier of the user who rolled.
+        user:   u64,
+        /// The name of the user who rolled.
+        name:   String,
+        /// The outcome of the roll.
+        result: RollResult,
+    },
+    /// Someone posted a chat message.
+    Chat {
+        /// The name of the user who posted the message.
+        name:    String,
+        /// The message itself.
+        message: ChatMessage,
+    },
+    /// The turn order of the campaign's combat changed, or it moved to the next turn.
+    Initiative {
+        /// The new turn order. Is empty if combat ended.
+        entries: Vec<InitiativeEntry>,
+    },
+    /// Someone used or restored the spell slots or other resources of a character.
+    Resources {
+        /// The identifier of the character whose resources changed.
+        character: u64,
+        /// The character's new resources.
+        resources: Resources,
+    },
+    /// The hit points of a character changed.
+    Hp {
+        /// The identifier of the character whose hit points changed.
+        character: u64,
+        /// The change that was applied. Negative values are damage, positive values are healing.
+        delta:     i64,
+        /// The character's new current hit points.
+        current:   u32,
+        /// The character's new temporary hit points.
+        temp:      u32,
+        /// The character's maximum hit points.
+        max:       u32,
+        /// Whether the character is downed.
+        downed:    bool,
+    },
+}
@@ -4,7 +4,7 @@
 //  Created:
 //    06 Apr 2024, 15:25:37
 //  Last edited:
-//    09 Apr 2024, 12:15:53
+//    14 Oct 2026, 18:07:51
 //  Auto updated?
 //    Yes
 //
@@ -17,8 +17,19 @@
 
 // Declare modules
 pub mod auth;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config;
 pub mod database;
+pub mod dice;
+pub mod events;
+pub mod logging;
 pub mod middleware;
+pub mod migrations;
 pub mod paths;
+pub mod router;
 pub mod spec;
 pub mod state;
+
+// Re-export the entrypoint for embedding the server
+pub use router::{build_admin_router, build_public_router, build_router};
@@ -0,0 +1,353 @@
+//  LOGGING.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:55:02
+//  Last edited:
+//    14 Oct 2026, 17:57:19
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Provides a [`log`]-backend that writes structured JSON lines instead
+//!   of human-readable ones, for ingestion by log aggregators.
+//!   
+//!   Also provides a [`TeeLogger`] that writes logs to a size-rotated file
+//!   in addition to the terminal.
+//
+
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::{error, io};
+
+use chrono::{SecondsFormat, Utc};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+
+
+/***** CONSTANTS *****/
+/// The default size (in bytes) after which log files are rotated.
+pub const LOG_FILE_MAX_SIZE: u64 = 10 * 1024 * 1024;
+/// The default number of rotated log files that are kept around (besides the current one).
+pub const LOG_FILE_KEEP: usize = 5;
+
+
+
+
+
+/***** ERRORS *****/
+/// Defines errors originating from opening a [`RotatingFile`].
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to create the directory the log file lives in.
+    DirCreate { path: PathBuf, err: io::Error },
+    /// Failed to open the log file for appending.
+    FileOpen { path: PathBuf, err: io::Error },
+}
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            DirCreate { path, .. } => write!(f, "Failed to create log directory '{}'", path.display()),
+            FileOpen { path, .. } => write!(f, "Failed to open log file '{}' for writing", path.display()),
+        }
+    }
+}
+impl error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            DirCreate { err, .. } => Some(err),
+            FileOpen { err, .. } => Some(err),
+        }
+    }
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Splits the [`RequestId`](crate::middleware::request_id::RequestId) off a log message, if it has one.
+///
+/// Handlers prefix their log lines with `[<request id>] `, which we turn into a separate field.
+///
+/// # Arguments
+/// - `message`: The formatted log message.
+///
+/// # Returns
+/// A tuple of the request identifier (if any) and the remainder of the message.
+fn split_request_id(message: &str) -> (Option<&str>, &str) {
+    if let Some(rest) = message.strip_prefix('[') {
+        if let Some((rid, message)) = rest.split_once("] ") {
+            if !rid.is_empty() && !rid.contains(char::is_whitespace) {
+                return (Some(rid), message);
+            }
+        }
+    }
+    (None, message)
+}
+
+/// Formats a log record as a single JSON object.
+///
+/// # Arguments
+/// - `record`: The [`Record`] to format.
+///
+/// # Returns
+/// The serialized object, without a trailing newline.
+fn format_json(record: &Record) -> String {
+    let message: String = record.args().to_string();
+    let (rid, message): (Option<&str>, &str) = split_request_id(&message);
+    let mut line: Map<String, Value> = Map::new();
+    line.insert("timestamp".into(), json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)));
+    line.insert("level".into(), json!(record.level().as_str()));
+    line.insert("target".into(), json!(record.target()));
+    line.insert("message".into(), json!(message));
+    if let Some(rid) = rid {
+        line.insert("request_id".into(), json!(rid));
+    }
+    Value::Object(line).to_string()
+}
+
+/// Formats a log record as a single, plain line of text.
+///
+/// # Arguments
+/// - `record`: The [`Record`] to format.
+///
+/// # Returns
+/// The formatted line, without a trailing newline.
+fn format_plain(record: &Record) -> String {
+    format!("[{} {:<5} {}] {}", Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true), record.level(), record.target(), record.args())
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// A [`Log`]ger that writes every record as a single JSON object on its own line to stdout.
+///
+/// Every object has a `timestamp` (RFC 3339, UTC), `level`, `target` and `message` field, plus a `request_id` field for log lines
+/// belonging to a specific request.
+#[derive(Clone, Copy, Debug)]
+pub struct JsonLogger {
+    /// The most verbose level that we still log.
+    level: LevelFilter,
+}
+impl JsonLogger {
+    /// Constructor for the JsonLogger.
+    ///
+    /// # Arguments
+    /// - `level`: The most verbose [`LevelFilter`] that is still logged.
+    ///
+    /// # Returns
+    /// A new JsonLogger.
+    #[inline]
+    pub fn new(level: LevelFilter) -> Self { Self { level } }
+
+    /// Installs this logger as the global [`log`]-backend.
+    ///
+    /// # Errors
+    /// This function errors if another logger was already installed.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(self.level);
+        Ok(())
+    }
+}
+impl Log for JsonLogger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool { metadata.level() <= self.level }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // Write it in one go, such that concurrent lines don't interleave
+        // NOTE: Nothing sensible to do if stdout is gone, so we ignore errors
+        let _ = writeln!(std::io::stdout().lock(), "{}", format_json(record));
+    }
+
+    #[inline]
+    fn flush(&self) { let _ = std::io::stdout().lock().flush(); }
+}
+
+
+
+/// A log file that is rotated once it grows beyond a certain size.
+///
+/// Rotating renames `<path>` to `<path>.1`, `<path>.1` to `<path>.2`, etc., dropping the oldest one once there are more than the
+/// configured number of rotated files.
+#[derive(Debug)]
+pub struct RotatingFile {
+    /// The path of the current log file.
+    path:     PathBuf,
+    /// The handle to the current log file.
+    file:     File,
+    /// The current size (in bytes) of the log file.
+    size:     u64,
+    /// The size (in bytes) after which the file is rotated.
+    max_size: u64,
+    /// The number of rotated files to keep around.
+    keep:     usize,
+}
+impl RotatingFile {
+    /// Opens a log file for appending, creating it (and the directory it lives in) if it doesn't exist.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the log file.
+    /// - `max_size`: The size (in bytes) after which the file is rotated.
+    /// - `keep`: The number of rotated files to keep around.
+    ///
+    /// # Returns
+    /// A new RotatingFile.
+    ///
+    /// # Errors
+    /// This function errors if we failed to create the directory or to open the file, e.g., because it's not writable.
+    pub fn open(path: impl Into<PathBuf>, max_size: u64, keep: usize) -> Result<Self, Error> {
+        let path: PathBuf = path.into();
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() {
+                if let Err(err) = fs::create_dir_all(dir) {
+                    return Err(Error::DirCreate { path: dir.into(), err });
+                }
+            }
+        }
+        let file: File = Self::open_file(&path)?;
+        let size: u64 = file.metadata().map(|md| md.len()).unwrap_or(0);
+        Ok(Self { path, file, size, max_size, keep })
+    }
+
+    /// Opens the file at the given path for appending.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the file to open.
+    ///
+    /// # Returns
+    /// A [`File`] handle.
+    ///
+    /// # Errors
+    /// This function errors if we failed to open the file.
+    fn open_file(path: &Path) -> Result<File, Error> {
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Ok(file),
+            Err(err) => Err(Error::FileOpen { path: path.into(), err }),
+        }
+    }
+
+    /// Returns the path of the `i`th rotated file.
+    #[inline]
+    fn rotated_path(&self, i: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{i}"));
+        path.into()
+    }
+
+    /// Moves the current file out of the way and starts a new one.
+    ///
+    /// # Errors
+    /// This function errors if we failed to rename any of the files or to open the new one.
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for i in (1..self.keep).rev() {
+                let from: PathBuf = self.rotated_path(i);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(i + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = Self::open_file(&self.path).map_err(|err| match err {
+            Error::DirCreate { err, .. } | Error::FileOpen { err, .. } => err,
+        })?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Writes a single line to the file, rotating it first if it would grow too large.
+    ///
+    /// # Arguments
+    /// - `line`: The line to write, without a trailing newline.
+    ///
+    /// # Errors
+    /// This function errors if we failed to rotate or write to the file.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len: u64 = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+
+
+/// A [`Log`]ger that writes every record to some other logger (typically the terminal) as well as to a [`RotatingFile`].
+pub struct TeeLogger {
+    /// The logger that also receives every record.
+    inner: Box<dyn Log>,
+    /// The file to write records to.
+    file:  Mutex<RotatingFile>,
+    /// Whether to write records to the file as JSON (see [`JsonLogger`]) or as plain text.
+    json:  bool,
+    /// The most verbose level that we still log.
+    level: LevelFilter,
+}
+impl TeeLogger {
+    /// Constructor for the TeeLogger.
+    ///
+    /// # Arguments
+    /// - `inner`: Some other [`Log`]ger that receives every record as well.
+    /// - `file`: The [`RotatingFile`] to write records to.
+    /// - `json`: Whether to write records to the file as JSON instead of as plain text.
+    /// - `level`: The most verbose [`LevelFilter`] that is still logged.
+    ///
+    /// # Returns
+    /// A new TeeLogger.
+    #[inline]
+    pub fn new(inner: Box<dyn Log>, file: RotatingFile, json: bool, level: LevelFilter) -> Self { Self { inner, file: Mutex::new(file), json, level } }
+
+    /// Installs this logger as the global [`log`]-backend.
+    ///
+    /// # Errors
+    /// This function errors if another logger was already installed.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let level: LevelFilter = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+impl Log for TeeLogger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool { metadata.level() <= self.level }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.inner.log(record);
+
+        // Write it to the file as well
+        let line: String = if self.json { format_json(record) } else { format_plain(record) };
+        if let Err(err) = self.file.lock().write_line(&line) {
+            // NOTE: Can't log this, as that would recurse
+            eprintln!("WARNING: Failed to write to log file: {err}");
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        let _ = self.file.lock().file.flush();
+    }
+}
@@ -4,7 +4,7 @@
 //  Created:
 //    06 Apr 2024, 15:12:56
 //  Last edited:
-//    09 Apr 2024, 13:22:11
+//    14 Oct 2026, 19:17:26
 //  Auto updated?
 //    Yes
 //
@@ -12,48 +12,440 @@
 //!   Entrypoint to the DnD server binary.
 //
 
-use std::fs;
-use std::future::IntoFuture as _;
+use std::future::{Future, IntoFuture as _};
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::str::FromStr as _;
+use std::time::Duration;
 
-use axum::routing::{get, post};
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::http::{header, Extensions, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Version as HttpVersion};
+use axum::routing::get;
 use axum::Router;
-use clap::Parser;
-use dnd_server::database::Database;
-use dnd_server::paths;
-use dnd_server::state::ServerState;
+use axum_extra::extract::cookie::SameSite;
+use axum_server::tls_rustls::RustlsConfig;
+use clap::{Parser, Subcommand, ValueEnum};
+use dnd_server::auth::{
+    hash_password, load_or_generate_key, validate_password, Argon2Params, Role, PASSWORD_MIN_LEN, REMEMBER_VALID_TIME_MIN, TOKEN_VALID_TIME_MIN,
+};
+use dnd_server::config::ConfigFile;
+use dnd_server::database::{set_slow_query_threshold, Database, Error as DbError, BACKUP_KEEP, SLOW_QUERY_MS};
+use dnd_server::logging::{JsonLogger, RotatingFile, TeeLogger, LOG_FILE_KEEP, LOG_FILE_MAX_SIZE};
+use dnd_server::middleware::client_version::CLIENT_VERSION_HEADER;
+use dnd_server::middleware::inflight::InFlight;
+use dnd_server::middleware::ratelimit::{AUTH_RATE_LIMIT, AUTH_RATE_WINDOW_SECS};
+use dnd_server::state::{CookieConfig, ServerConfig, ServerState};
+use dnd_server::{build_admin_router, build_public_router, build_router, middleware, paths};
 use error_trace::trace;
+use futures_util::future::{try_join_all, TryJoinAll};
 use humanlog::{DebugMode, HumanLogger};
-use log::{debug, error, info};
+use log::{debug, error, info, warn, LevelFilter, Log, SetLoggerError};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use semver::Version;
 use tokio::net::TcpListener;
 use tokio::runtime::{Builder, Runtime};
 use tokio::signal::unix::{signal, Signal, SignalKind};
-use tower_http::services::ServeDir;
+use tokio::sync::watch;
+use tokio::time::Interval;
+use tower_http::compression::predicate::{NotForContentType, Predicate as _, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+
+/***** CONSTANTS *****/
+/// The interval at which we purge expired tokens from the revocation list.
+const REVOCATION_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// The minimum size (in bytes) of a response before we bother compressing it.
+const COMPRESSION_MIN_SIZE: u16 = 1024;
+
+
+
 
 
 /***** ARGUMENTS *****/
+/// Defines the formats in which the server can log.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable, coloured lines for in the terminal.
+    Human,
+    /// One JSON object per line, for ingestion by log aggregators (see [`JsonLogger`]).
+    Json,
+}
+
+/// Defines the values of the `SameSite`-attribute of the login cookie.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum CookieSameSite {
+    /// The cookie is only sent along with requests from the same site.
+    Strict,
+    /// The cookie is also sent along when navigating to the server from another site, but not with other cross-site requests.
+    Lax,
+    /// The cookie is sent along with all requests. Browsers require the cookie to be `Secure` for this.
+    None,
+}
+impl From<CookieSameSite> for SameSite {
+    #[inline]
+    fn from(value: CookieSameSite) -> Self {
+        match value {
+            CookieSameSite::Strict => Self::Strict,
+            CookieSameSite::Lax => Self::Lax,
+            CookieSameSite::None => Self::None,
+        }
+    }
+}
+
+/// Defines the roles that can be given to users created with `useradd`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum RoleArg {
+    /// A normal user that plays the game.
+    Player,
+    /// A user that can host and run campaigns.
+    DungeonMaster,
+    /// A user that can administrate the server.
+    Root,
+}
+impl From<RoleArg> for Role {
+    #[inline]
+    fn from(value: RoleArg) -> Self {
+        match value {
+            RoleArg::Player => Self::Player,
+            RoleArg::DungeonMaster => Self::DungeonMaster,
+            RoleArg::Root => Self::Root,
+        }
+    }
+}
+
+/// Defines the subcommands of the binary.
+#[derive(Subcommand)]
+enum Command {
+    /// Hosts the server. This is the default if no subcommand is given.
+    Serve,
+    /// Resets the password of an existing user in the database (prompting for the new one), and then exits. Use this to recover accounts
+    /// when resetting through the web isn't possible.
+    Passwd {
+        /// The name of the user to reset the password of.
+        #[clap(short, long)]
+        name: String,
+    },
+    /// Creates a new user in the database (prompting for its password), and then exits.
+    Useradd {
+        /// The name of the new user.
+        #[clap(short, long)]
+        name: String,
+        /// The role of the new user.
+        // NOTE: No short flag, as `-r` is already taken by `--root-path`
+        #[clap(long, value_enum, default_value_t = RoleArg::Player)]
+        role: RoleArg,
+    },
+}
+
 /// Defines arguments for the binary.
 #[derive(Parser)]
 struct Arguments {
+    /// What to do. Defaults to hosting the server.
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// If given, enables more verbose logging.
     #[clap(short, long, global = true)]
-    verbose: bool,
+    verbose:           bool,
+    /// The format in which to log.
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Human)]
+    log_format:        LogFormat,
+    /// If given, writes logs to the given file in addition to the terminal. Its directory is created if it doesn't exist.
+    #[clap(long, global = true)]
+    log_file:          Option<PathBuf>,
+    /// The size (in bytes) after which the `--log-file` is rotated.
+    #[clap(long, global = true, default_value_t = LOG_FILE_MAX_SIZE)]
+    log_file_max_size: u64,
+    /// The number of rotated `--log-file`s to keep around (as `<path>.1`, `<path>.2`, ...).
+    #[clap(long, global = true, default_value_t = LOG_FILE_KEEP)]
+    log_file_keep:     usize,
 
-    /// The address on which to host the server.
+    /// The address on which to host the server. Can be repeated to listen on multiple addresses (e.g., both an IPv4 and an IPv6 one).
     #[clap(short, long, global = true, default_value = "0.0.0.0:4200")]
-    address:     SocketAddr,
+    address:          Vec<SocketAddr>,
+    /// If given, hosts the administrative paths (user management, the audit log, backups, exports and imports) on this address only,
+    /// instead of on every `--address`. Use this to keep them on a port that is firewalled off from the public internet. They still
+    /// require a logged-in root user, so the login paths are served on this address too. See the README for what this does (and doesn't)
+    /// protect against.
+    #[clap(long, global = true)]
+    admin_address:    Option<SocketAddr>,
     /// The path to the client files.
     #[clap(short, long, global = true, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/src/client"))]
-    client_path: PathBuf,
-    /// The path to the persistent data file.
-    #[clap(short, long, global = true, default_value = "/data/data.db")]
-    data_path:   PathBuf,
+    client_path:      PathBuf,
+    /// The URL of the database to store persistent data in. Use `sqlite://<path>` for a local SQLite database file, or
+    /// `postgres://<user>:<pass>@<host>/<db>` for a Postgres server.
+    #[clap(short, long, global = true, default_value = "sqlite:///data/data.db")]
+    database_url:     String,
+    /// The maximum number of connections to keep open to the database (only used for SQLite databases).
+    #[clap(long, global = true, default_value_t = 8)]
+    db_pool_size:     u32,
+    /// The time (in milliseconds) above which database queries are logged as warnings. Faster queries are only logged with `--verbose`.
+    #[clap(long, global = true, default_value_t = SLOW_QUERY_MS)]
+    slow_query_ms:    u64,
+    /// The path to a PEM-encoded TLS certificate (chain). If given together with `--tls-key`, the server is hosted over HTTPS instead of HTTP.
+    #[clap(long, global = true, requires = "tls_key")]
+    tls_cert:         Option<PathBuf>,
+    /// The path to the PEM-encoded private key of the `--tls-cert`.
+    #[clap(long, global = true, requires = "tls_cert")]
+    tls_key:          Option<PathBuf>,
+    /// The time (in seconds) that in-flight requests get to finish after receiving SIGTERM or SIGINT before the server exits anyway.
+    #[clap(long, global = true, default_value_t = 30)]
+    shutdown_timeout: u64,
     /// The path to the root's credentials file. This is only used if the database needs to be initialized to generate the root user.
     #[clap(short, long, global = true, default_value = "/config/root.toml")]
-    root_path:   PathBuf,
+    root_path:        PathBuf,
+    /// If given, updates the name and password of the root user from `--root-path` even if it already exists. Use this to rotate the
+    /// root's password.
+    #[clap(long, global = true)]
+    reseed_root:      bool,
+    /// The path to an optional TOML configuration file. Its fields are named after the arguments (e.g., `token_valid_minutes`) and take
+    /// precedence over them. The file is re-read on SIGHUP, upon which `token_valid_minutes`, `remember_valid_minutes`,
+    /// `password_min_length`, the `argon2_*` costs, `auth_rate_limit`, `auth_rate_window`, `min_client_version` and `require_client_version`
+    /// are applied immediately; changes to `database_url` and `db_pool_size` are ignored with a warning until the server restarts.
+    #[clap(long, global = true)]
+    config:           Option<PathBuf>,
+
+    /// The time (in minutes) that login tokens are valid.
+    #[clap(long, global = true, default_value_t = TOKEN_VALID_TIME_MIN)]
+    token_valid_minutes:    i64,
+    /// The time (in minutes) that login tokens are valid if the user logs in with "remember me".
+    #[clap(long, global = true, default_value_t = REMEMBER_VALID_TIME_MIN)]
+    remember_valid_minutes: i64,
+    /// The minimum length (in characters) of user passwords (including the root's).
+    #[clap(long, global = true, default_value_t = PASSWORD_MIN_LEN)]
+    password_min_length:    usize,
+
+    /// The Argon2 memory cost (in KiB) used when hashing passwords.
+    #[clap(long, global = true, default_value_t = Argon2Params::default().m_cost)]
+    argon2_m_cost: u32,
+    /// The Argon2 time cost (in iterations) used when hashing passwords.
+    #[clap(long, global = true, default_value_t = Argon2Params::default().t_cost)]
+    argon2_t_cost: u32,
+    /// The Argon2 parallelism cost (in lanes) used when hashing passwords.
+    #[clap(long, global = true, default_value_t = Argon2Params::default().p_cost)]
+    argon2_p_cost: u32,
+
+    /// If given, records metrics about requests and authentication and exposes them at `/metrics` in the Prometheus text format. Note that
+    /// this endpoint is unauthenticated, so be sure to firewall it off from the public internet in production.
+    #[clap(long, global = true)]
+    metrics: bool,
+
+    /// The maximum number of requests a single IP address may do to the `/v1/auth` paths per `--auth-rate-window`.
+    #[clap(long, global = true, default_value_t = AUTH_RATE_LIMIT)]
+    auth_rate_limit:  u32,
+    /// The window (in seconds) in which a single IP address may do at most `--auth-rate-limit` requests to the `/v1/auth` paths.
+    #[clap(long, global = true, default_value_t = AUTH_RATE_WINDOW_SECS)]
+    auth_rate_window: u64,
+
+    /// The oldest version of the client that is still compatible with this server. Clients can check this with `GET /v1/version`, and API
+    /// requests of clients that give an older version in the `x-client-version`-header are refused with `426 UPGRADE REQUIRED`.
+    #[clap(long, global = true, default_value = "0.0.0")]
+    min_client_version:     Version,
+    /// If given, API requests without an `x-client-version`-header are refused with `426 UPGRADE REQUIRED` instead of being let through.
+    #[clap(long, global = true)]
+    require_client_version: bool,
+
+    /// The directory to which database backups are written, both by `POST /v1/admin/backup` and by `--backup-interval`.
+    #[clap(long, global = true, default_value = "/data/backups")]
+    backup_dir:      PathBuf,
+    /// If given, also backs up the database every this many seconds. Only supported for SQLite databases.
+    #[clap(long, global = true)]
+    backup_interval: Option<u64>,
+    /// The number of backups to keep in the `--backup-dir`; older ones are removed whenever a new one is made.
+    #[clap(long, global = true, default_value_t = BACKUP_KEEP)]
+    backup_keep:     usize,
+
+    /// An origin (e.g., `https://dnd.example.com`) of a separate frontend that may call the API with the user's credentials. Can be
+    /// repeated to allow multiple origins. If omitted, only same-origin requests are allowed.
+    #[clap(long = "cors-origin", global = true)]
+    cors_origins:    Vec<String>,
+    /// The maximum size (in bytes) of request bodies sent to the API. Larger requests are refused with `413 PAYLOAD TOO LARGE`.
+    #[clap(long, global = true, default_value_t = 64 * 1024)]
+    max_body_size:   usize,
+    /// If given, disables compressing responses (which is otherwise negotiated with clients with `Accept-Encoding`). Useful for debugging.
+    #[clap(long, global = true)]
+    no_compression:  bool,
+    /// The time (in seconds) that API requests get before they are aborted with `408 REQUEST TIMEOUT`. Does not apply to WebSockets.
+    #[clap(long, global = true, default_value_t = 30)]
+    request_timeout: u64,
+
+    /// The `SameSite`-attribute of the login cookie. Use `none` (together with `--cookie-secure`) if the frontend is hosted on another site
+    /// than the server.
+    #[clap(long, global = true, value_enum, default_value_t = CookieSameSite::Lax)]
+    cookie_same_site:    CookieSameSite,
+    /// Whether to set the `Secure`-attribute of the login cookie, such that browsers only send it over HTTPS. Defaults to whether the
+    /// server is hosted over HTTPS (see `--tls-cert`); set it explicitly when running behind a reverse proxy that terminates TLS.
+    #[clap(long, global = true)]
+    cookie_secure:       Option<bool>,
+    /// If given, doesn't set the `HttpOnly`-attribute of the login cookie, making it readable by scripts. Not recommended.
+    #[clap(long, global = true)]
+    cookie_no_http_only: bool,
+    /// The `Path`-attribute of the login cookie.
+    #[clap(long, global = true, default_value = "/")]
+    cookie_path:         String,
+    /// The `Domain`-attribute of the login cookie (e.g., `example.com` to share it with its subdomains). If omitted, the cookie is only
+    /// sent to the exact host that set it.
+    #[clap(long, global = true)]
+    cookie_domain:       Option<String>,
+    /// If given, loads the key with which login cookies are encrypted from this file, such that users stay logged in across restarts. If
+    /// it doesn't exist, a new key is generated and written to it (readable by the owner only). Otherwise, a new key is generated every
+    /// time the server starts.
+    #[clap(long, global = true)]
+    cookie_key_file:     Option<PathBuf>,
+
+    /// If given, browsers that aren't logged in are redirected (with `302 FOUND`) to this path (e.g., `/login`) when navigating to any
+    /// other page of the client. API requests still get `401 NOT AUTHORIZED`.
+    #[clap(long, global = true)]
+    login_redirect: Option<String>,
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Prompts for a new password on the terminal (without echoing it), and checks it against the password policy.
+///
+/// # Arguments
+/// - `config`: The [`ServerConfig`] with the password policy.
+/// - `name`: The name of the user whose password it is.
+///
+/// # Returns
+/// The new password, or [`None`] if we failed to read it or it wasn't valid (which is already logged).
+fn prompt_new_password(config: &ServerConfig, name: &str) -> Option<String> {
+    // Ask for it twice, to catch typos
+    let pass: String = match rpassword::prompt_password(format!("Password for '{name}': ")) {
+        Ok(pass) => pass,
+        Err(err) => {
+            error!("{}", trace!(("Failed to read password"), err));
+            return None;
+        },
+    };
+    match rpassword::prompt_password("Repeat password: ") {
+        Ok(repeat) if repeat == pass => {},
+        Ok(_) => {
+            error!("Passwords do not match");
+            return None;
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to read password"), err));
+            return None;
+        },
+    }
+    if let Err(err) = validate_password(&pass, config.password_min_len) {
+        error!("{}", trace!(("Password of user '{name}' does not meet the password policy"), err));
+        return None;
+    }
+    Some(pass)
+}
+
+/// Creates a new user in the database, prompting for its password on the terminal.
+///
+/// # Arguments
+/// - `runtime`: The tokio [`Runtime`] to talk to the database on.
+/// - `db`: The (migrated) [`Database`] to create the user in.
+/// - `config`: The [`ServerConfig`] with the password policy and hashing parameters.
+/// - `name`: The name of the new user.
+/// - `role`: The [`Role`] of the new user.
+///
+/// # Returns
+/// The exit code of the binary.
+fn useradd(runtime: &Runtime, db: &Database, config: &ServerConfig, name: &str, role: Role) -> i32 {
+    if name.is_empty() {
+        error!("Username cannot be empty");
+        return 1;
+    }
+
+    // Ask for the password
+    let pass: String = match prompt_new_password(config, name) {
+        Some(pass) => pass,
+        None => return 1,
+    };
+
+    // Hash it and insert the user
+    debug!("Hashing password of new user '{name}'...");
+    let hash: String = match hash_password(&pass, &config.argon2) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("{}", trace!(("Failed to hash password of user '{name}'"), err));
+            return 1;
+        },
+    };
+    match runtime.block_on(db.create_user(name, hash, role)) {
+        Ok(id) => {
+            info!("Created user '{name}' with identifier {id} (role: {role:?})");
+            0
+        },
+        Err(DbError::DuplicateName { .. }) => {
+            error!("User '{name}' already exists");
+            1
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to create user '{name}' in database"), err));
+            1
+        },
+    }
+}
+
+
+
+
+
+/// Resets the password of an existing user in the database, prompting for the new one on the terminal.
+///
+/// # Arguments
+/// - `runtime`: The tokio [`Runtime`] to talk to the database on.
+/// - `db`: The (migrated) [`Database`] in which the user lives.
+/// - `config`: The [`ServerConfig`] with the password policy and hashing parameters.
+/// - `name`: The name of the user to reset the password of.
+///
+/// # Returns
+/// The exit code of the binary.
+fn passwd(runtime: &Runtime, db: &Database, config: &ServerConfig, name: &str) -> i32 {
+    // Find the user first, such that we don't ask for a password in vain
+    let id: u64 = match runtime.block_on(db.get_user_by_name(name)) {
+        Ok(Some(user)) => user.id,
+        Ok(None) => {
+            error!("User '{name}' does not exist");
+            return 1;
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get user info for user '{name}' from database"), err));
+            return 1;
+        },
+    };
+
+    // Ask for the new password
+    let pass: String = match prompt_new_password(config, name) {
+        Some(pass) => pass,
+        None => return 1,
+    };
+
+    // Hash it and update the user
+    debug!("Hashing new password of user '{name}'...");
+    let hash: String = match hash_password(&pass, &config.argon2) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("{}", trace!(("Failed to hash password of user '{name}'"), err));
+            return 1;
+        },
+    };
+    match runtime.block_on(db.update_password(id, hash)) {
+        Ok(true) => {
+            info!("Updated password of user '{name}' (identifier {id})");
+            0
+        },
+        Ok(false) => {
+            error!("User '{name}' was removed while updating its password");
+            1
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to update password of user '{name}' in database"), err));
+            1
+        },
+    }
 }
 
 
@@ -66,100 +458,393 @@ fn main() {
     let args = Arguments::parse();
 
     // Setup the logger
-    if let Err(err) = HumanLogger::terminal(if args.verbose { DebugMode::Full } else { DebugMode::Debug }).init() {
+    let level: LevelFilter = if args.verbose { LevelFilter::Trace } else { LevelFilter::Debug };
+    let mode: DebugMode = if args.verbose { DebugMode::Full } else { DebugMode::Debug };
+    let res: Result<(), SetLoggerError> = match (&args.log_file, args.log_format) {
+        (Some(path), format) => {
+            // NOTE: We can't log yet, so we report problems the old-fashioned way
+            let file: RotatingFile = match RotatingFile::open(path, args.log_file_max_size, args.log_file_keep) {
+                Ok(file) => file,
+                Err(err) => {
+                    eprintln!("ERROR: {}", trace!(("Failed to open log file"), err));
+                    std::process::exit(1);
+                },
+            };
+            let terminal: Box<dyn Log> = match format {
+                LogFormat::Human => Box::new(HumanLogger::terminal(mode)),
+                LogFormat::Json => Box::new(JsonLogger::new(level)),
+            };
+            TeeLogger::new(terminal, file, format == LogFormat::Json, level).init()
+        },
+        (None, LogFormat::Human) => HumanLogger::terminal(mode).init(),
+        (None, LogFormat::Json) => JsonLogger::new(level).init(),
+    };
+    if let Err(err) = res {
         eprintln!("WARNING: Failed to setup logger: {err} (logging disabled for this session)");
     }
     info!("{} v{}", env!("CARGO_BIN_NAME"), env!("CARGO_PKG_VERSION"));
 
 
 
+    // Collect the tunable server parameters, overriding them with the config file if any
+    let cli_config: ServerConfig = ServerConfig {
+        token_valid_min:        args.token_valid_minutes,
+        remember_valid_min:     args.remember_valid_minutes,
+        password_min_len:       args.password_min_length,
+        argon2:                 Argon2Params { m_cost: args.argon2_m_cost, t_cost: args.argon2_t_cost, p_cost: args.argon2_p_cost },
+        auth_rate_limit:        args.auth_rate_limit,
+        auth_rate_window:       Duration::from_secs(args.auth_rate_window),
+        min_client_version:     args.min_client_version.clone(),
+        require_client_version: args.require_client_version,
+        backup_dir:             args.backup_dir.clone(),
+        backup_keep:            args.backup_keep,
+        max_body_size:          args.max_body_size,
+        request_timeout:        Duration::from_secs(args.request_timeout),
+        cookie:                 CookieConfig {
+            same_site: args.cookie_same_site.into(),
+            secure:    args.cookie_secure.unwrap_or(args.tls_cert.is_some()),
+            http_only: !args.cookie_no_http_only,
+            path:      args.cookie_path.clone(),
+            domain:    args.cookie_domain.clone(),
+        },
+        login_redirect:         args.login_redirect.clone(),
+    };
+    if cli_config.cookie.same_site == SameSite::None && !cli_config.cookie.secure {
+        error!("'--cookie-same-site none' requires the login cookie to be secure (see '--cookie-secure'); browsers ignore it otherwise");
+        std::process::exit(1);
+    }
+    let file: ConfigFile = match &args.config {
+        Some(path) => match ConfigFile::from_path(path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("{}", trace!(("Failed to load config file"), err));
+                std::process::exit(1);
+            },
+        },
+        None => ConfigFile::default(),
+    };
+    let config: ServerConfig = file.apply(cli_config.clone());
+    let database_url: String = file.database_url.clone().unwrap_or_else(|| args.database_url.clone());
+    let db_pool_size: u32 = file.db_pool_size.unwrap_or(args.db_pool_size);
+
+
+
+    /* RUNTIME */
+    // Build a tokio runtime to enter async mode (we need it to initialize the database too)
+    debug!("Building tokio runtime...");
+    let runtime: Runtime = match Builder::new_multi_thread().enable_io().enable_time().build() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            error!("{}", trace!(("Failed to create tokio runtime"), err));
+            std::process::exit(1);
+        },
+    };
+
+
+
     /* Database */
-    // Touch the database file alive if it doesn't exist
-    let needs_init: bool = if !args.data_path.exists() {
-        // Doesn't exist; touch the file and return it needs initing
-        debug!("Database file '{}' does not exist", args.data_path.display());
-        true
-    } else {
-        // Already exists, no init please
-        debug!("Database file '{}' already exists", args.data_path.display());
-
-        // ...unless its empty!
-        match fs::metadata(&args.data_path) {
-            Ok(md) => {
-                if md.len() == 0 {
-                    debug!("Database file '{}' is uninitialized", args.data_path.display());
-                    true
-                } else {
-                    false
-                }
+    set_slow_query_threshold(Duration::from_millis(args.slow_query_ms));
+
+    // Open a connection to the database based on the URL's scheme
+    let db: Database = if let Some(data_path) = database_url.strip_prefix("sqlite://") {
+        match Database::sqlite(data_path, db_pool_size) {
+            Ok(db) => db,
+            Err(err) => {
+                error!("{}", trace!(("Failed to open SQLite database file '{data_path}'"), err));
+                std::process::exit(1);
             },
+        }
+    } else if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        match Database::postgres(database_url.clone()) {
+            Ok(db) => db,
             Err(err) => {
-                error!("{}", trace!(("Failed to get database file '{}' metadata", args.data_path.display()), err));
+                error!("{}", trace!(("Failed to connect to Postgres database"), err));
                 std::process::exit(1);
             },
         }
+    } else {
+        error!("Unsupported database URL given (expected a 'sqlite://' or 'postgres://' URL)");
+        std::process::exit(1);
     };
 
-    // Open a connection to the database
-    let db: Database = Database::sqlite(&args.data_path);
+    // Fail fast if we can't reach it (Postgres connections are only established lazily)
+    debug!("Pinging database...");
+    if let Err(err) = runtime.block_on(db.ping()) {
+        error!("{}", trace!(("Failed to reach database"), err));
+        std::process::exit(1);
+    }
 
-    // If it needs initialization, do so
-    if needs_init {
-        debug!("Initializing database...");
-        if let Err(err) = db.init(&args.root_path) {
-            error!("{}", trace!(("Failed to initialize database file '{}'", args.data_path.display()), err));
+    // Bring its schema up-to-date
+    debug!("Migrating database...");
+    match runtime.block_on(db.migrate()) {
+        Ok(version) => debug!("Database is at schema version {version}"),
+        Err(err) => {
+            error!("{}", trace!(("Failed to migrate database"), err));
             std::process::exit(1);
-        }
+        },
+    }
+
+    // If we're only asked to manage a user, do so and quit
+    match &args.command {
+        Some(Command::Passwd { name }) => std::process::exit(passwd(&runtime, &db, &config, name)),
+        Some(Command::Useradd { name, role }) => std::process::exit(useradd(&runtime, &db, &config, name, (*role).into())),
+        Some(Command::Serve) | None => {},
+    }
+
+    // Scheduled backups are done with SQLite's backup API, so make sure we have it
+    if args.backup_interval.is_some() && !matches!(db, Database::SQLite { .. }) {
+        error!("Scheduled backups (see '--backup-interval') are only supported for SQLite databases");
+        std::process::exit(1);
+    }
+
+    // Ensure there's a root user
+    if let Err(err) = runtime.block_on(db.seed_root(&args.root_path, args.reseed_root, config.password_min_len, &config.argon2)) {
+        error!("{}", trace!(("Failed to initialize root user"), err));
+        std::process::exit(1);
     }
 
 
 
     /* PATH BUILDING */
     // Create a runtime state out of that
-    let state: ServerState = ServerState::new(env!("CARGO_BIN_NAME"), Version::from_str(env!("CARGO_PKG_VERSION")).unwrap(), db);
+    let name: &'static str = env!("CARGO_BIN_NAME");
+    let version: Version = Version::from_str(env!("CARGO_PKG_VERSION")).unwrap();
+    let state: ServerState = match &args.cookie_key_file {
+        Some(path) => match load_or_generate_key(path) {
+            Ok(key) => ServerState::with_key(name, version, db, config, key),
+            Err(err) => {
+                error!("{}", trace!(("Failed to load cookie key"), err));
+                std::process::exit(1);
+            },
+        },
+        None => ServerState::new(name, version, db, config),
+    };
+
+    // Build the API and file server paths, leaving out the admin paths if they're hosted separately
+    let mut routes: Router =
+        if args.admin_address.is_some() { build_public_router(state.clone(), &args.client_path) } else { build_router(state.clone(), &args.client_path) };
+
+    // Allow cross-origin requests, if asked to
+    if !args.cors_origins.is_empty() {
+        // Parse the origins first
+        let mut origins: Vec<HeaderValue> = Vec::with_capacity(args.cors_origins.len());
+        for origin in &args.cors_origins {
+            match HeaderValue::from_str(origin) {
+                Ok(origin) => origins.push(origin),
+                Err(err) => {
+                    error!("{}", trace!(("Invalid CORS origin {origin:?}"), err));
+                    std::process::exit(1);
+                },
+            }
+        }
+
+        // Allow them to call the API with credentials, such that the login cookie is sent along
+        debug!("Allowing cross-origin requests from {:?}...", args.cors_origins);
+        routes = routes.layer(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins))
+                .allow_credentials(true)
+                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+                .allow_headers([header::CONTENT_TYPE, HeaderName::from_static(CLIENT_VERSION_HEADER)]),
+        );
+    }
+
+    // Record metrics, if asked to
+    if args.metrics {
+        debug!("Installing Prometheus metrics recorder...");
+        let handle: PrometheusHandle = match PrometheusBuilder::new().install_recorder() {
+            Ok(handle) => handle,
+            Err(err) => {
+                error!("{}", trace!(("Failed to install Prometheus metrics recorder"), err));
+                std::process::exit(1);
+            },
+        };
+        // NOTE: We add the metrics path after the layer, such that scraping doesn't show up in the metrics themselves
+        routes = routes
+            .layer(axum::middleware::from_fn(middleware::metrics::handle))
+            .merge(Router::new().route("/metrics", get(paths::metrics::handle)).with_state(handle));
+    }
 
-    // Build the API paths
-    debug!("Building axum API paths...");
-    let auth: Router = Router::new().route("/auth/login", post(paths::auth::login)).with_state(state.clone());
-    let version: Router = Router::new().route("/version", get(paths::version::handle)).with_state(state);
-    let api: Router = Router::new().nest("/v1", auth).nest("/v1", version);
+    // Compress responses, if not told otherwise
+    if !args.no_compression {
+        // NOTE: Compressed formats (images, fonts) don't get any smaller, and the WebSocket upgrade shouldn't get a `Content-Encoding` at all.
+        //       Responses that already have a `Content-Encoding` are never compressed twice.
+        routes = routes.layer(
+            CompressionLayer::new().compress_when(
+                SizeAbove::new(COMPRESSION_MIN_SIZE)
+                    .and(NotForContentType::GRPC)
+                    .and(NotForContentType::IMAGES)
+                    .and(NotForContentType::SSE)
+                    .and(NotForContentType::const_new("font/"))
+                    .and(|status: StatusCode, _: HttpVersion, _: &HeaderMap, _: &Extensions| status != StatusCode::SWITCHING_PROTOCOLS),
+            ),
+        );
+    }
 
-    // Build the file server paths
-    debug!("Building axum file paths...");
-    // TODO: Write some better wrapper around `ServeDir` that logs and can do stuff like redirecting to the login page if not logged-in.
-    let main: Router = Router::new()
-        .nest_service("/", ServeDir::new(args.client_path.join("index.html")))
-        .nest_service("/index.html", ServeDir::new(args.client_path.join("index.html")));
-    let files: Router = Router::new().nest("/", main);
+    // Finally, keep track of running requests and give each of them an identifier to correlate log lines with
+    let inflight: InFlight = InFlight::default();
+    routes = routes
+        .layer(axum::middleware::from_fn_with_state(inflight.clone(), middleware::inflight::handle))
+        .layer(axum::middleware::from_fn(middleware::request_id::handle));
 
-    // Join them
-    let routes: Router = Router::new().nest("/", api).nest("/", files);
+    // Decide which routes are served where
+    // NOTE: The admin listener is meant to be internal, so it doesn't get CORS, metrics or compression
+    let mut listeners: Vec<(SocketAddr, Router, &'static str)> = args.address.iter().map(|address| (*address, routes.clone(), "")).collect();
+    if let Some(address) = args.admin_address {
+        let admin: Router = build_admin_router(state.clone())
+            .layer(axum::middleware::from_fn_with_state(inflight.clone(), middleware::inflight::handle))
+            .layer(axum::middleware::from_fn(middleware::request_id::handle));
+        listeners.push((address, admin, " (admin)"));
+    }
 
 
 
     /* EXECUTION */
-    // Build a tokio runtime to enter async mode
-    debug!("Building tokio runtime...");
-    let runtime: Runtime = match Builder::new_multi_thread().enable_io().enable_time().build() {
-        Ok(runtime) => runtime,
-        Err(err) => {
-            error!("{}", trace!(("Failed to create tokio runtime"), err));
-            std::process::exit(1);
-        },
-    };
     std::process::exit(runtime.block_on(async move {
-        // Bind a listener on the specified address for the server
-        debug!("Binding server listener to '{}'...", args.address);
-        let listener: TcpListener = match TcpListener::bind(args.address).await {
-            Ok(listener) => listener,
+        // Prepare a channel that tells the server to shut down gracefully
+        let (shutdown_tx, shutdown_rx): (watch::Sender<bool>, watch::Receiver<bool>) = watch::channel(false);
+
+        // Load the TLS certificate and key, if we're given any
+        let tls: Option<RustlsConfig> = if let (Some(cert), Some(key)) = (&args.tls_cert, &args.tls_key) {
+            debug!("Loading TLS certificate '{}' and key '{}'...", cert.display(), key.display());
+            match RustlsConfig::from_pem_file(cert, key).await {
+                Ok(config) => Some(config),
+                Err(err) => {
+                    error!("{}", trace!(("Failed to load TLS certificate '{}' and key '{}'", cert.display(), key.display()), err));
+                    return 1;
+                },
+            }
+        } else {
+            None
+        };
+
+        // Prepare a server per address, hosting over HTTPS if we have TLS
+        let mut servers: Vec<Pin<Box<dyn Send + Future<Output = Result<(), std::io::Error>>>>> = Vec::with_capacity(listeners.len());
+        for (address, routes, kind) in listeners {
+            let mut shutdown_rx: watch::Receiver<bool> = shutdown_rx.clone();
+            let service: IntoMakeServiceWithConnectInfo<Router, SocketAddr> = routes.into_make_service_with_connect_info::<SocketAddr>();
+            if let Some(config) = &tls {
+                // Translate the shutdown signal to the server's handle
+                let handle: axum_server::Handle = axum_server::Handle::new();
+                let shutdown_handle: axum_server::Handle = handle.clone();
+                tokio::spawn(async move {
+                    if shutdown_rx.changed().await.is_ok() {
+                        // NOTE: We do the timeout ourselves
+                        shutdown_handle.graceful_shutdown(None);
+                    }
+                });
+
+                // NOTE: This listener binds lazily, so any errors will be reported as the server failing
+                debug!("Binding HTTPS server listener to '{address}'...");
+                servers.push(Box::pin(axum_server::bind_rustls(address, config.clone()).handle(handle).serve(service)));
+                info!("Listening on 'https://{address}'{kind}");
+            } else {
+                debug!("Binding HTTP server listener to '{address}'...");
+                let listener: TcpListener = match TcpListener::bind(address).await {
+                    Ok(listener) => listener,
+                    Err(err) => {
+                        error!("{}", trace!(("Failed to bind to '{address}'"), err));
+                        return 1;
+                    },
+                };
+                servers.push(Box::pin(
+                    axum::serve(listener, service)
+                        .with_graceful_shutdown(async move {
+                            let _ = shutdown_rx.changed().await;
+                        })
+                        .into_future(),
+                ));
+                info!("Listening on 'http://{address}'{kind}");
+            }
+        }
+        // NOTE: This fails as soon as any of the servers does, upon which we exit
+        let mut server: TryJoinAll<Pin<Box<dyn Send + Future<Output = Result<(), std::io::Error>>>>> = try_join_all(servers);
+
+        // Spawn a task that reloads the config file on SIGHUP
+        debug!("Registering SIGHUP handler...");
+        let mut sighup: Signal = match signal(SignalKind::hangup()) {
+            Ok(handler) => handler,
             Err(err) => {
-                error!("{}", trace!(("Failed to bind to '{}'", args.address), err));
+                error!("{}", trace!(("Failed to create SIGHUP handler"), err));
                 return 1;
             },
         };
+        let reload_state: ServerState = state.clone();
+        let config_path: Option<PathBuf> = args.config.clone();
+        tokio::spawn(async move {
+            while sighup.recv().await.is_some() {
+                let path: &PathBuf = match &config_path {
+                    Some(path) => path,
+                    None => {
+                        warn!("Received SIGHUP, but no config file was given (see '--config'); nothing to reload");
+                        continue;
+                    },
+                };
+                info!("Received SIGHUP, reloading config file '{}'...", path.display());
+                let file: ConfigFile = match ConfigFile::from_path(path) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        error!("{}", trace!(("Failed to reload config file (keeping the current configuration)"), err));
+                        continue;
+                    },
+                };
 
-        // Build a listener for SIGTERM
-        debug!("Registering SIGTERM handler...");
+                // Warn about the fields we cannot swap while running
+                if file.database_url.as_ref().is_some_and(|url| *url != database_url) {
+                    warn!("Ignoring changed 'database_url' in config file '{}' (restart the server to apply it)", path.display());
+                }
+                if file.db_pool_size.is_some_and(|size| size != db_pool_size) {
+                    warn!("Ignoring changed 'db_pool_size' in config file '{}' (restart the server to apply it)", path.display());
+                }
+
+                // Swap the others
+                let config: ServerConfig = file.apply(cli_config.clone());
+                debug!("New configuration: {config:?}");
+                reload_state.reload_config(config);
+                info!("Reloaded config file '{}'", path.display());
+            }
+        });
+
+        // Spawn a task that periodically backs up the database, if asked to
+        if let Some(secs) = args.backup_interval {
+            debug!("Spawning backup task (every {secs}s)...");
+            let backup_state: ServerState = state.clone();
+            tokio::spawn(async move {
+                // NOTE: We skip the immediate first tick, such that restarting the server doesn't immediately make a new backup
+                let period: Duration = Duration::from_secs(secs.max(1));
+                let mut interval: Interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                loop {
+                    interval.tick().await;
+                    let (dir, keep): (PathBuf, usize) = {
+                        let config = backup_state.config.read();
+                        (config.backup_dir.clone(), config.backup_keep)
+                    };
+                    match backup_state.db.backup(dir, keep).await {
+                        Ok(path) => info!("Backed up database to '{}'", path.display()),
+                        Err(err) => error!("{}", trace!(("Failed to back up database"), err)),
+                    }
+                }
+            });
+        }
+
+        // Keep a handle to the database around, such that we can close it once we're done
+        let db: Database = state.db.clone();
+
+        // Spawn a task that periodically cleans the token revocation list
+        debug!("Spawning revocation list purge task...");
+        tokio::spawn(async move {
+            let mut interval: Interval = tokio::time::interval(REVOCATION_PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match state.db.purge_revoked_tokens().await {
+                    Ok(n) => debug!("Purged {n} expired token(s) from revocation list"),
+                    Err(err) => error!("{}", trace!(("Failed to purge revocation list"), err)),
+                }
+            }
+        });
+
+        // Build listeners for SIGTERM and SIGINT
+        debug!("Registering SIGTERM and SIGINT handlers...");
         let mut sigterm: Signal = match signal(SignalKind::terminate()) {
             Ok(handler) => handler,
             Err(err) => {
@@ -167,12 +852,19 @@ fn main() {
                 return 1;
             },
         };
+        let mut sigint: Signal = match signal(SignalKind::interrupt()) {
+            Ok(handler) => handler,
+            Err(err) => {
+                error!("{}", trace!(("Failed to create SIGINT handler"), err));
+                return 1;
+            },
+        };
 
-        // Run the server in a loop while alternating with listening for signals
+        // Run the server until it fails or we're asked to stop
         info!("Initialization complete, entering game loop");
         tokio::select! {
             // Let the server handle the stuff
-            res = axum::serve(listener, routes.into_make_service_with_connect_info::<SocketAddr>()).into_future() => match res {
+            res = &mut server => return match res {
                 Ok(_) => 0,
                 Err(err) => {
                     error!("{}", trace!(("Failed to run axum server"), err));
@@ -180,8 +872,37 @@ fn main() {
                 }
             },
 
-            // Wait for SIGTERM to be super Docker-friendly
-            _ = sigterm.recv() => 0,
+            // Wait for SIGTERM to be super Docker-friendly, or SIGINT to be Ctrl+C-friendly
+            _ = sigterm.recv() => info!("Received SIGTERM, shutting down..."),
+            _ = sigint.recv() => info!("Received SIGINT, shutting down..."),
+        }
+
+        // Stop accepting new connections, and give the in-flight requests some time to finish
+        let draining: usize = inflight.count();
+        info!("Draining {draining} in-flight request(s) (timeout: {}s)...", args.shutdown_timeout);
+        let _ = shutdown_tx.send(true);
+        let code: i32 = match tokio::time::timeout(Duration::from_secs(args.shutdown_timeout), server).await {
+            Ok(Ok(_)) => {
+                info!("Drained {draining} request(s)");
+                0
+            },
+            Ok(Err(err)) => {
+                error!("{}", trace!(("Failed to run axum server"), err));
+                1
+            },
+            Err(_) => {
+                error!("Timed out waiting for in-flight requests to finish; dropping {} request(s)", inflight.count());
+                1
+            },
+        };
+
+        // Flush the database before we go
+        debug!("Closing database...");
+        if let Err(err) = db.close().await {
+            error!("{}", trace!(("Failed to close database"), err));
+            return 1;
         }
+        info!("Goodbye");
+        code
     }));
 }
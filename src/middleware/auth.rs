@@ -4,32 +4,126 @@
 //  Created:
 //    09 Apr 2024, 12:52:49
 //  Last edited:
-//    09 Apr 2024, 13:06:03
+//    14 Oct 2026, 19:04:18
 //  Auto updated?
 //    Yes
 //
 //  Description:
 //!   Handles checking the login token in every request and resolving that
 //!   to a [`UserInfo`] or a `401 NOT AUTHORIZED`.
+//!   
+//!   Also provides [`require_role()`] (and [`RouterExt::require_role()`]
+//!   for whole routers) to further restrict routes to users with a certain
+//!   [`Role`], and the [`AuthUser`] extractor through which
+//!   handlers get the logged-in user. Routes that also serve anonymous
+//!   users can use the [`MaybeAuthUser`] extractor instead.
 //
 
+use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::pin::Pin;
 
-use axum::body::Body;
-use axum::extract::{ConnectInfo, Request, State};
+use axum::extract::{ConnectInfo, FromRequestParts, Request, State};
+use axum::http::request::Parts;
 use axum::middleware::Next;
-use axum::response::Response;
+use axum::response::{IntoResponse as _, Response};
+use axum::{async_trait, Router};
 use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::PrivateCookieJar;
+use enum_debug::EnumDebug as _;
 use error_trace::trace;
 use hyper::StatusCode;
 use log::{debug, error, info};
 
-use crate::auth::{check_token, LOGIN_TOKEN_NAME};
-use crate::database::UserInfo;
+use crate::auth::{check_token, Role, LOGIN_TOKEN_NAME};
+use crate::database::{AuditEventKind, UserInfo};
+use crate::spec::ApiError;
 use crate::state::ServerState;
 
 
+/***** AUXILLARY *****/
+/// The future returned by the middleware closures generated by [`require_role()`].
+pub type RequireRoleFuture = Pin<Box<dyn 'static + Send + Future<Output = Response>>>;
+
+
+
+/// Extracts the [`UserInfo`] of the logged-in user, as injected by [`handle()`].
+///
+/// Requests for which no user was injected (i.e., because the route isn't behind [`handle()`]) are rejected with `401 NOT AUTHORIZED`
+/// and an [`ApiError`] of kind `missing_token`.
+///
+/// ```ignore
+/// pub async fn whoami(AuthUser(user): AuthUser) -> Response {
+///     (StatusCode::OK, Json(UserSummary::from(user))).into_response()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AuthUser(pub UserInfo);
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<UserInfo>() {
+            Some(user) => Ok(Self(user.clone())),
+            None => {
+                error!("Extractor 'AuthUser' did not find any UserInfo in request; did you forget to run 'auth::handle' first?");
+                Err(ApiError::new(StatusCode::UNAUTHORIZED, "missing_token", format!("No '{LOGIN_TOKEN_NAME}' cookie given")).into_response())
+            },
+        }
+    }
+}
+
+/// Extracts the [`UserInfo`] of the logged-in user if there is one, for routes that serve anonymous users as well.
+///
+/// Unlike [`AuthUser`], this doesn't need [`handle()`] to run first; instead, it checks the login token itself if the client sent one.
+/// Missing or invalid tokens (or failures to check them) never reject the request, but simply yield [`None`].
+///
+/// ```ignore
+/// pub async fn hello(MaybeAuthUser(user): MaybeAuthUser) -> String {
+///     match user {
+///         Some(user) => format!("Hello, {}!", user.name),
+///         None => "Hello, stranger!".into(),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MaybeAuthUser(pub Option<UserInfo>);
+#[async_trait]
+impl FromRequestParts<ServerState> for MaybeAuthUser {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Re-use the user of the token checker, if it already ran
+        if let Some(user) = parts.extensions.get::<UserInfo>() {
+            return Ok(Self(Some(user.clone())));
+        }
+
+        // Otherwise, check the token ourselves
+        let jar: PrivateCookieJar = PrivateCookieJar::from_request_parts(parts, state).await?;
+        let token: Cookie = match jar.get(LOGIN_TOKEN_NAME) {
+            Some(token) => token,
+            None => return Ok(Self(None)),
+        };
+        match check_token(&state.db, &state.key, token.value()).await {
+            Ok(Ok(user)) => Ok(Self(Some(user))),
+            Ok(Err(err)) => {
+                debug!("{}", trace!(("Client provided an invalid token; treating it as anonymous"), err));
+                Ok(Self(None))
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check login token {:?}; treating client as anonymous", token.value()), err));
+                Ok(Self(None))
+            },
+        }
+    }
+}
+
+
+
+
+
 /***** LIBRARY *****/
 /// Handles checking the login token in every request and resolving that to a [`UserInfo`] or a `401 NOT AUTHORIZED`.
 ///
@@ -43,7 +137,8 @@ use crate::state::ServerState;
 /// - `next`: A [`Next`] handler to call after this one succeeded.
 ///
 /// # Returns
-/// A [`Response`] given by the `next` handler, or a `401 NOT AUTHORIZED` if the user's login token did not check out.
+/// A [`Response`] given by the `next` handler, or a `401 NOT AUTHORIZED` with an [`ApiError`] if the user's login token was missing (kind
+/// `missing_token`) or did not check out (kind `invalid_token`).
 pub async fn handle(
     State(state): State<ServerState>,
     ConnectInfo(client): ConnectInfo<SocketAddr>,
@@ -58,27 +153,24 @@ pub async fn handle(
         Some(token) => token,
         None => {
             debug!("Client '{client}' did not provide any token; login failed");
-            return Response::builder().status(StatusCode::UNAUTHORIZED).body(Body::new(format!("No '{LOGIN_TOKEN_NAME}' cookie given"))).unwrap();
+            return ApiError::new(StatusCode::UNAUTHORIZED, "missing_token", format!("No '{LOGIN_TOKEN_NAME}' cookie given")).into_response();
         },
     };
     debug!("Client '{}' provided token {:?}", client, token.value());
 
     // Run thru the checker
-    let user: UserInfo = match check_token(&state.db, token.value()) {
+    let user: UserInfo = match check_token(&state.db, &state.key, token.value()).await {
         Ok(Ok(user)) => user,
         Ok(Err(err)) => {
             debug!("{}", trace!(("Client '{client}' provided an invalid token"), err));
-            return Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .body(Body::new(format!("Invalid '{LOGIN_TOKEN_NAME}' cookie given")))
-                .unwrap();
+            if let Err(err) = state.db.log_event(AuditEventKind::TokenRejected, None, client.ip()).await {
+                error!("{}", trace!(("Failed to log audit event"), err));
+            }
+            return ApiError::new(StatusCode::UNAUTHORIZED, "invalid_token", format!("Invalid '{LOGIN_TOKEN_NAME}' cookie given")).into_response();
         },
         Err(err) => {
             error!("{}", trace!(("Failed to check login token {:?}", token.value()), err));
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::new(format!("Failed to check '{LOGIN_TOKEN_NAME}' cookie")))
-                .unwrap();
+            return ApiError::internal(format!("Failed to check '{LOGIN_TOKEN_NAME}' cookie")).into_response();
         },
     };
     debug!("Client '{}' token {:?} OK", client, token.value());
@@ -87,3 +179,148 @@ pub async fn handle(
     request.extensions_mut().insert(user);
     next.run(request).await
 }
+
+
+/// Generates a middleware that only lets users through that have at least the given [`Role`], returning `403 FORBIDDEN` with an
+/// [`ApiError`] of kind `insufficient_role` otherwise.
+///
+/// Note that this middleware relies on the [`UserInfo`] injected by [`handle()`]. As such, it must be run _after_ that one, i.e., its layer
+/// must be added _before_ the token-checking layer (since layers added later wrap the earlier ones):
+/// ```ignore
+/// Router::new()
+///     .route("/campaigns", post(paths::campaigns::create))
+///     .layer(axum::middleware::from_fn(require_role(Role::DungeonMaster)))
+///     .layer(axum::middleware::from_fn_with_state(state, middleware::auth::handle));
+/// ```
+///
+/// # Arguments
+/// - `min`: The minimum [`Role`] the user should have to be let through.
+///
+/// # Returns
+/// A closure that can be given to [`axum::middleware::from_fn()`].
+pub fn require_role(min: Role) -> impl 'static + Clone + Send + Sync + Fn(Request, Next) -> RequireRoleFuture {
+    move |request: Request, next: Next| -> RequireRoleFuture {
+        Box::pin(async move {
+            info!("Middleware 'require_role': asserting user has at least role {}", min.variant());
+
+            // Get the user injected by the token checker
+            let (id, role): (u64, Role) = match request.extensions().get::<UserInfo>() {
+                Some(user) => (user.id, user.role),
+                None => {
+                    error!("Middleware 'require_role' did not find any UserInfo in request; did you forget to run 'auth::handle' first?");
+                    return ApiError::internal("Failed to get user info").into_response();
+                },
+            };
+
+            // Compare the roles
+            if role < min {
+                debug!("User {id} has role {}, which is insufficient for required role {}", role.variant(), min.variant());
+                return ApiError::new(StatusCode::FORBIDDEN, "insufficient_role", format!("Role {} or higher is required for this path", min.variant()))
+                    .into_response();
+            }
+            debug!("User {id} has role {}, which is sufficient for required role {}", role.variant(), min.variant());
+
+            // OK, pass on
+            next.run(request).await
+        })
+    }
+}
+
+
+
+/// Extends a [`Router`] with a shorthand for protecting all of its routes with [`require_role()`] at once.
+pub trait RouterExt {
+    /// Only lets users through to any of the router's routes if they have at least the given [`Role`].
+    ///
+    /// Like [`require_role()`], this relies on the [`UserInfo`] injected by [`handle()`], and so must be called _before_ adding the
+    /// token-checking layer. Moreover, it only protects the routes that were added before calling it, so call it after adding all of them:
+    /// ```ignore
+    /// Router::new()
+    ///     .route("/audit", get(paths::audit::list))
+    ///     .route("/users", get(paths::users::list))
+    ///     .require_role(Role::Root)
+    ///     .route_layer(axum::middleware::from_fn_with_state(state, middleware::auth::handle));
+    /// ```
+    ///
+    /// # Arguments
+    /// - `min`: The minimum [`Role`] the user should have to be let through.
+    ///
+    /// # Returns
+    /// The same router, but with its routes protected.
+    fn require_role(self, min: Role) -> Self;
+}
+impl<S: 'static + Clone + Send + Sync> RouterExt for Router<S> {
+    #[inline]
+    fn require_role(self, min: Role) -> Self { self.route_layer(axum::middleware::from_fn(require_role(min))) }
+}
+
+
+
+
+
+/***** TESTS *****/
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::routing::get;
+    use axum::Extension;
+    use chrono::Utc;
+    use tower::ServiceExt as _;
+
+    use super::*;
+
+    /// Builds the [`UserInfo`] of a (fake) logged-in user.
+    fn test_user(role: Role) -> UserInfo {
+        UserInfo { id: 42, name: "amy".into(), pass: String::new(), role, added: Utc::now(), updated: Utc::now(), deleted: None }
+    }
+
+    /// A handler that needs a logged-in user.
+    async fn whoami(AuthUser(user): AuthUser) -> String { user.name }
+
+    /// Sends a `GET`-request to the given path through the router, returning its status code and the [`ApiError`] kind (if any).
+    async fn send(router: Router, path: &str) -> (StatusCode, Option<String>) {
+        let res: Response = match router.oneshot(Request::builder().uri(path).body(Body::empty()).unwrap()).await {
+            Ok(res) => res,
+            Err(err) => match err {},
+        };
+        let status: StatusCode = res.status();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice::<ApiError>(&body).ok().map(|err| err.kind))
+    }
+
+
+
+    #[tokio::test]
+    async fn test_auth_user_missing() {
+        let router: Router = Router::new().route("/whoami", get(whoami));
+        assert_eq!(send(router, "/whoami").await, (StatusCode::UNAUTHORIZED, Some("missing_token".into())));
+
+        // Once injected, it's found
+        let router: Router = Router::new().route("/whoami", get(whoami)).layer(Extension(test_user(Role::Player)));
+        assert_eq!(send(router, "/whoami").await, (StatusCode::OK, None));
+    }
+
+    #[tokio::test]
+    async fn test_router_require_role() {
+        for (role, expected) in [(Role::Player, StatusCode::FORBIDDEN), (Role::DungeonMaster, StatusCode::OK), (Role::Root, StatusCode::OK)] {
+            // NOTE: Stands in for the token checker, which must run first
+            let router: Router = Router::new()
+                .route("/a", get(whoami))
+                .route("/b", get(whoami))
+                .require_role(Role::DungeonMaster)
+                .route("/c", get(whoami))
+                .route_layer(axum::middleware::from_fn(move |mut request: Request, next: Next| async move {
+                    request.extensions_mut().insert(test_user(role));
+                    next.run(request).await
+                }));
+
+            // Every route added before protecting the group is protected...
+            for path in ["/a", "/b"] {
+                let kind: Option<String> = if expected == StatusCode::OK { None } else { Some("insufficient_role".into()) };
+                assert_eq!(send(router.clone(), path).await, (expected, kind), "Unexpected response for {path} as {role:?}");
+            }
+            // ...but those added after aren't
+            assert_eq!(send(router, "/c").await, (StatusCode::OK, None));
+        }
+    }
+}
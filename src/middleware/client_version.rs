@@ -0,0 +1,93 @@
+//  CLIENT_VERSION.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:58:07
+//  Last edited:
+//    14 Oct 2026, 17:54:54
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that refuses requests from clients that are
+//!   older than the minimum version supported by the server, such that
+//!   incompatible clients cannot corrupt any data.
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use hyper::StatusCode;
+use log::{debug, info};
+use semver::Version;
+
+use crate::spec::ApiError;
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The header in which clients give their version.
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles checking the version that the client gives in the `x-client-version`-header against the minimum supported version.
+///
+/// Requests without the header are let through, unless the server is configured to require it.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] that has the common state between paths (for us, this means the configuration).
+/// - `client`: Some [`SocketAddr`] of the client that connected.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler, or else an [`ApiError`] with:
+/// - `400 BAD REQUEST` if the header was not a valid semantic version (kind `invalid_version`); or
+/// - `426 UPGRADE REQUIRED` if the client is too old (kind `client_outdated`) or didn't give its version while that is required (kind
+///   `missing_client_version`).
+pub async fn handle(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    info!("Middleware 'client_version': inspecting client '{client}' version");
+    let (min, required): (Version, bool) = {
+        let config = state.config.read();
+        (config.min_client_version.clone(), config.require_client_version)
+    };
+
+    // Get the version, if any
+    let raw: &str = match request.headers().get(CLIENT_VERSION_HEADER).map(|value| value.to_str()) {
+        Some(Ok(raw)) => raw,
+        Some(Err(err)) => {
+            debug!("Client '{client}' gave non-ASCII version ({err}), returning 400 BAD REQUEST");
+            return ApiError::new(StatusCode::BAD_REQUEST, "invalid_version", format!("Invalid '{CLIENT_VERSION_HEADER}' header: {err}")).into_response();
+        },
+        None if required => {
+            debug!("Client '{client}' did not give its version, returning 426 UPGRADE REQUIRED");
+            return ApiError::new(StatusCode::UPGRADE_REQUIRED, "missing_client_version", format!("No '{CLIENT_VERSION_HEADER}' header given")).into_response();
+        },
+        None => return next.run(request).await,
+    };
+
+    // Check it
+    let version: Version = match Version::parse(raw) {
+        Ok(version) => version,
+        Err(err) => {
+            debug!("Client '{client}' gave invalid version {raw:?} ({err}), returning 400 BAD REQUEST");
+            return ApiError::new(StatusCode::BAD_REQUEST, "invalid_version", format!("Invalid client version {raw:?}: {err}")).into_response();
+        },
+    };
+    if version < min {
+        debug!("Client '{client}' has version {version}, which is older than {min}; returning 426 UPGRADE REQUIRED");
+        return ApiError::new(
+            StatusCode::UPGRADE_REQUIRED,
+            "client_outdated",
+            format!("Client version {version} is too old; please upgrade to {min} or newer"),
+        )
+        .into_response();
+    }
+    next.run(request).await
+}
@@ -0,0 +1,62 @@
+//  INFLIGHT.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 22:09:12
+//  Last edited:
+//    14 Oct 2026, 17:34:14
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that counts how many requests are currently
+//!   being handled, such that we can report what we drain on shutdown.
+//
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+
+/***** AUXILLARY *****/
+/// Decrements the in-flight counter when dropped, such that requests that are cancelled (e.g., because the client disconnected) are counted
+/// correctly too.
+struct InFlightGuard(Arc<AtomicUsize>);
+impl Drop for InFlightGuard {
+    #[inline]
+    fn drop(&mut self) { self.0.fetch_sub(1, Ordering::SeqCst); }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Keeps track of the number of requests currently being handled.
+#[derive(Clone, Debug, Default)]
+pub struct InFlight(Arc<AtomicUsize>);
+impl InFlight {
+    /// Returns the number of requests currently being handled.
+    #[inline]
+    pub fn count(&self) -> usize { self.0.load(Ordering::SeqCst) }
+}
+
+
+
+/// Handles counting the request as in-flight while the `next` handler runs.
+///
+/// # Arguments
+/// - `inflight`: The [`InFlight`] counter to keep up-to-date.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// The [`Response`] given by the `next` handler.
+pub async fn handle(State(inflight): State<InFlight>, request: Request, next: Next) -> Response {
+    inflight.0.fetch_add(1, Ordering::SeqCst);
+    let _guard: InFlightGuard = InFlightGuard(inflight.0.clone());
+    next.run(request).await
+}
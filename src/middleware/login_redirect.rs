@@ -0,0 +1,98 @@
+//  LOGIN_REDIRECT.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:35:09
+//  Last edited:
+//    14 Oct 2026, 19:07:44
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that redirects browsers that aren't logged in
+//!   to the login page when they navigate to the client, such that the
+//!   client doesn't have to guard its pages itself.
+//
+
+use axum::extract::{Request, State};
+use axum::http::header::{ACCEPT, LOCATION};
+use axum::http::{HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::PrivateCookieJar;
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, warn};
+
+use crate::auth::{check_token, LOGIN_TOKEN_NAME};
+use crate::state::ServerState;
+
+
+/***** HELPER FUNCTIONS *****/
+/// Checks whether a request is a browser navigating to a page, as opposed to, e.g., a script fetching data or an asset.
+///
+/// # Arguments
+/// - `request`: The [`Request`] to check.
+///
+/// # Returns
+/// True if it's a `GET`- or `HEAD`-request that accepts HTML, or false otherwise.
+fn is_navigation(request: &Request) -> bool {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return false;
+    }
+    match request.headers().get(ACCEPT).map(HeaderValue::to_str) {
+        Some(Ok(accept)) => accept.split(',').any(|media| media.split(';').next().map(str::trim) == Some("text/html")),
+        _ => false,
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles redirecting browsers that aren't logged in to the login page.
+///
+/// This only applies if [`ServerConfig::login_redirect`](crate::state::ServerConfig::login_redirect) is set, and only to navigations
+/// (i.e., `GET`-requests accepting `text/html`) to anything but the login page itself. Everything else (e.g., API requests accepting
+/// `application/json`, or assets) is passed on as-is, without checking the login token; so serving those never touches the database.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] that has the common state between paths (for us, this means the configuration and the database).
+/// - `jar`: A [`PrivateCookieJar`] with the login token of the client, if it sent any.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler, or else `302 FOUND` to the login page.
+pub async fn handle(State(state): State<ServerState>, jar: PrivateCookieJar, request: Request, next: Next) -> Response {
+    let login: Option<String> = state.config.read().login_redirect.clone();
+    let login: String = match login {
+        Some(login) => login,
+        None => return next.run(request).await,
+    };
+    if request.uri().path() == login || !is_navigation(&request) {
+        return next.run(request).await;
+    }
+
+    // Only then check if they're logged in, as that needs the database
+    let token: Option<Cookie> = jar.get(LOGIN_TOKEN_NAME);
+    if let Some(token) = token {
+        match check_token(&state.db, &state.key, token.value()).await {
+            Ok(Ok(_)) => return next.run(request).await,
+            Ok(Err(err)) => debug!("{}", trace!(("Browser provided an invalid token; treating it as not logged in"), err)),
+            Err(err) => error!("{}", trace!(("Failed to check login token {:?}; treating browser as not logged in", token.value()), err)),
+        }
+    }
+
+    // Send them to the login page instead
+    debug!("Browser navigating to '{}' is not logged in, returning 302 FOUND to '{login}'", request.uri().path());
+    match HeaderValue::from_str(&login) {
+        Ok(location) => (StatusCode::FOUND, [(LOCATION, location)]).into_response(),
+        Err(err) => {
+            warn!("Login redirect '{login}' is not a valid header value ({err}); serving '{}' as-is", request.uri().path());
+            next.run(request).await
+        },
+    }
+}
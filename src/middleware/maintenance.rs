@@ -0,0 +1,72 @@
+//  MAINTENANCE.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:37:57
+//  Last edited:
+//    14 Oct 2026, 18:38:13
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that refuses requests while the server is in
+//!   maintenance mode, e.g., during an upgrade.
+//
+
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+
+use axum::extract::{ConnectInfo, OriginalUri, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use hyper::StatusCode;
+use log::debug;
+
+use crate::paths::auth::LOGIN_PATH;
+use crate::spec::ApiError;
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The number of seconds after which clients are told to try again while the server is in maintenance mode.
+pub const MAINTENANCE_RETRY_AFTER_SECS: u64 = 300;
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles refusing requests while the server is in maintenance mode.
+///
+/// Logging in is still allowed, such that root users can reach the administrative paths to switch maintenance mode off again. Those
+/// (and the health probes) shouldn't be behind this middleware at all.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] that has the common state between paths (for us, this means the maintenance flag).
+/// - `client`: Some [`SocketAddr`] of the client that connected.
+/// - `uri`: The [`OriginalUri`] of the request, i.e., before any nesting stripped its prefix.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler, or else `503 SERVICE UNAVAILABLE` with an [`ApiError`] of kind `maintenance` and a
+/// `Retry-After`-header.
+pub async fn handle(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.maintenance.load(Ordering::Relaxed) || uri.path() == LOGIN_PATH.path {
+        return next.run(request).await;
+    }
+
+    debug!("Server is in maintenance mode, refusing {} {} from '{client}' with 503 SERVICE UNAVAILABLE", request.method(), uri.path());
+    let mut res: Response =
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "maintenance", "The server is down for maintenance; please try again later").into_response();
+    res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(MAINTENANCE_RETRY_AFTER_SECS));
+    res
+}
@@ -0,0 +1,134 @@
+//  METHOD.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:23:35
+//  Last edited:
+//    14 Oct 2026, 19:03:09
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that answers requests to known paths with the
+//!   wrong method with `405 METHOD NOT ALLOWED`, instead of letting them
+//!   fall through to the client files.
+//
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::header::ALLOW;
+use axum::http::{HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use hyper::StatusCode;
+use log::debug;
+
+use crate::spec::{ApiError, Endpoint};
+
+
+/***** HELPER FUNCTIONS *****/
+/// Matches a concrete request path against an axum path pattern (e.g., `/v1/campaigns/:id`).
+///
+/// # Arguments
+/// - `pattern`: The pattern to match against.
+/// - `path`: The concrete path of the request.
+///
+/// # Returns
+/// For every segment, whether it was matched literally (true) or by a parameter (false); or [`None`] if the path doesn't match at all.
+fn match_pattern(pattern: &str, path: &str) -> Option<Vec<bool>> {
+    let mut pattern_segs = pattern.split('/');
+    let mut path_segs = path.split('/');
+    let mut literal: Vec<bool> = Vec::new();
+    loop {
+        match (pattern_segs.next(), path_segs.next()) {
+            (Some(pat), Some(seg)) if pat.starts_with(':') && !seg.is_empty() => literal.push(false),
+            (Some(pat), Some(seg)) if pat == seg => literal.push(true),
+            (None, None) => return Some(literal),
+            _ => return None,
+        }
+    }
+}
+
+/// Finds the methods with which the given path can be accessed, according to the given endpoints.
+///
+/// Like axum's router, literal segments take precedence over parameters (e.g., `/v1/campaigns/join` is never matched by
+/// `/v1/campaigns/:id`).
+///
+/// # Arguments
+/// - `endpoints`: The [`Endpoint`]s that are mounted on the router.
+/// - `path`: The concrete path of the request.
+///
+/// # Returns
+/// The allowed [`Method`]s, or an empty list if the path is unknown.
+fn allowed_methods(endpoints: &[&'static Endpoint], path: &str) -> Vec<Method> {
+    // Find the most specific pattern matching the path
+    let mut best: Option<(Vec<bool>, &'static str)> = None;
+    for endpoint in endpoints {
+        if let Some(literal) = match_pattern(endpoint.path.path, path) {
+            if best.as_ref().map(|(best, _)| literal > *best).unwrap_or(true) {
+                best = Some((literal, endpoint.path.path));
+            }
+        }
+    }
+    let pattern: &'static str = match best {
+        Some((_, pattern)) => pattern,
+        None => return Vec::new(),
+    };
+
+    // Collect the methods of all endpoints on that pattern
+    let mut methods: Vec<Method> = Vec::new();
+    for endpoint in endpoints.iter().filter(|endpoint| endpoint.path.path == pattern) {
+        if !methods.contains(&endpoint.path.method) {
+            methods.push(endpoint.path.method.clone());
+        }
+        // NOTE: axum serves `HEAD` for every `GET` route
+        if endpoint.path.method == Method::GET && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
+        }
+    }
+    methods
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles refusing requests to known API paths that use a method the path doesn't support.
+///
+/// Which paths and methods are known is determined by the given endpoints, which should be those of the
+/// [`ENDPOINTS`](crate::paths::openapi::ENDPOINTS)-registry that are mounted on the router (e.g., without the administrative ones if those
+/// are hosted separately). Requests to unknown paths, `OPTIONS`-requests (e.g., CORS preflights) and requests with a supported method are
+/// passed on as-is.
+///
+/// # Arguments
+/// - `endpoints`: The [`Endpoint`]s that are mounted on the router.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler, or else `405 METHOD NOT ALLOWED` with an [`ApiError`] of kind `method_not_allowed` and an
+/// `Allow`-header listing the supported methods.
+pub async fn handle(State(endpoints): State<Arc<[&'static Endpoint]>>, request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+    let methods: Vec<Method> = allowed_methods(&endpoints, request.uri().path());
+    if methods.is_empty() || methods.contains(request.method()) {
+        return next.run(request).await;
+    }
+
+    // Tell the client what it should have done instead
+    let allow: String = methods.iter().map(Method::as_str).collect::<Vec<&str>>().join(", ");
+    debug!("Path '{}' does not support {}, returning 405 METHOD NOT ALLOWED (allowed: {allow})", request.uri().path(), request.method());
+    let mut res: Response = ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "method_not_allowed",
+        format!("Method {} is not allowed on '{}'; use one of {allow}", request.method(), request.uri().path()),
+    )
+    .into_response();
+    // NOTE: Safe to unwrap, since method names are valid header values
+    res.headers_mut().insert(ALLOW, HeaderValue::from_str(&allow).unwrap());
+    res
+}
@@ -0,0 +1,59 @@
+//  METRICS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 21:14:26
+//  Last edited:
+//    14 Oct 2026, 17:32:20
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that records request counts and durations
+//!   per route as Prometheus metrics.
+//
+
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+
+
+/***** CONSTANTS *****/
+/// The name of the counter that counts requests.
+pub const REQUESTS_TOTAL: &str = "http_requests_total";
+/// The name of the histogram that records request durations (in seconds).
+pub const REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles recording the count and duration of every request, labelled by method, route and status code.
+///
+/// The route used is the path template (e.g., `/v1/characters/:id`) instead of the concrete path, to keep the number of labels bounded.
+/// Requests that don't match any route are all recorded under `<unmatched>`.
+///
+/// # Arguments
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// The [`Response`] given by the `next` handler.
+pub async fn handle(request: Request, next: Next) -> Response {
+    let start: Instant = Instant::now();
+    let method: String = request.method().to_string();
+    let path: String = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().into(),
+        None => "<unmatched>".into(),
+    };
+
+    // Run the request, then record how it went
+    let res: Response = next.run(request).await;
+    let status: String = res.status().as_u16().to_string();
+    metrics::counter!(REQUESTS_TOTAL, "method" => method.clone(), "path" => path.clone(), "status" => status.clone()).increment(1);
+    metrics::histogram!(REQUEST_DURATION_SECONDS, "method" => method, "path" => path, "status" => status).record(start.elapsed().as_secs_f64());
+    res
+}
@@ -4,7 +4,7 @@
 //  Created:
 //    08 Apr 2024, 11:44:55
 //  Last edited:
-//    09 Apr 2024, 13:21:25
+//    14 Oct 2026, 18:38:13
 //  Auto updated?
 //    Yes
 //
@@ -14,3 +14,12 @@
 
 // Declare submodules
 pub mod auth;
+pub mod client_version;
+pub mod inflight;
+pub mod login_redirect;
+pub mod maintenance;
+pub mod method;
+pub mod metrics;
+pub mod ratelimit;
+pub mod request_id;
+pub mod static_cache;
@@ -0,0 +1,164 @@
+//  RATELIMIT.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:12:03
+//  Last edited:
+//    14 Oct 2026, 17:41:00
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that throttles clients by their IP address,
+//!   returning `429 TOO MANY REQUESTS` when they exceed their rate.
+//
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use hyper::header::RETRY_AFTER;
+use hyper::StatusCode;
+use log::{debug, info};
+use parking_lot::{Mutex, RwLock};
+
+use crate::spec::ApiError;
+
+
+/***** CONSTANTS *****/
+/// The default maximum number of requests a single client may do to the `/v1/auth` paths per [`AUTH_RATE_WINDOW_SECS`].
+pub const AUTH_RATE_LIMIT: u32 = 10;
+/// The default window (in seconds) in which a single client may do at most [`AUTH_RATE_LIMIT`] requests to the `/v1/auth` paths.
+pub const AUTH_RATE_WINDOW_SECS: u64 = 60;
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Defines the token bucket of a single client.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    /// The number of tokens (i.e., requests) left in the bucket.
+    tokens: f64,
+    /// The last time the bucket was refilled.
+    last:   Instant,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Defines a rate limiter that keeps track of a token bucket per client IP.
+///
+/// Every client can do at most `limit` requests in a burst, after which the bucket refills at a rate of `limit` requests per `window`.
+///
+/// Give it to [`handle()`] as state to use it as middleware:
+/// ```ignore
+/// Router::new()
+///     .route("/auth/login", post(paths::auth::login))
+///     .route_layer(axum::middleware::from_fn_with_state(RateLimiter::new(10, Duration::from_secs(60)), middleware::ratelimit::handle))
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    /// The maximum number of requests per window, and the window in which at most that many requests may be done.
+    rate:    Arc<RwLock<(u32, Duration)>>,
+    /// The buckets per client.
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+impl RateLimiter {
+    /// Constructor for the RateLimiter.
+    ///
+    /// # Arguments
+    /// - `limit`: The maximum number of requests a client can do per `window`.
+    /// - `window`: The window in which a client can do at most `limit` requests.
+    ///
+    /// # Returns
+    /// A new RateLimiter with no clients known yet.
+    #[inline]
+    pub fn new(limit: u32, window: Duration) -> Self { Self { rate: Arc::new(RwLock::new((limit, window))), buckets: Arc::new(Mutex::new(HashMap::new())) } }
+
+    /// Changes the rate of this limiter, e.g., when the configuration is reloaded.
+    ///
+    /// This affects all clones of this RateLimiter. Existing buckets are kept, but refill at the new rate.
+    ///
+    /// # Arguments
+    /// - `limit`: The new maximum number of requests a client can do per `window`.
+    /// - `window`: The new window in which a client can do at most `limit` requests.
+    #[inline]
+    pub fn set_rate(&self, limit: u32, window: Duration) { *self.rate.write() = (limit, window); }
+
+    /// Attempts to take a token from the bucket of the given client.
+    ///
+    /// # Arguments
+    /// - `ip`: The IP address of the client to take a token for.
+    ///
+    /// # Returns
+    /// [`None`] if the client had a token left (and may thus continue), or else the [`Duration`] after which a new token will be available.
+    pub fn check(&self, ip: IpAddr) -> Option<Duration> {
+        // A limit of zero means no requests at all
+        let (limit, window): (u32, Duration) = *self.rate.read();
+        if limit == 0 {
+            return Some(window);
+        }
+        let capacity: f64 = limit as f64;
+        let rate: f64 = capacity / window.as_secs_f64().max(f64::EPSILON);
+        let now: Instant = Instant::now();
+
+        // Get the client's bucket
+        let mut buckets = self.buckets.lock();
+        let bucket: &mut Bucket = buckets.entry(ip).or_insert(Bucket { tokens: capacity, last: now });
+
+        // Refill it, then attempt to take a token
+        bucket.tokens = (bucket.tokens + (now - bucket.last).as_secs_f64() * rate).min(capacity);
+        bucket.last = now;
+        let res: Option<Duration> = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        };
+
+        // Forget about clients that would be full again anyway, to keep memory in check
+        if buckets.len() > 1024 {
+            buckets.retain(|_, bucket| bucket.tokens + (now - bucket.last).as_secs_f64() * rate < capacity);
+        }
+        res
+    }
+}
+
+
+
+/// Handles throttling clients that do too many requests, returning `429 TOO MANY REQUESTS` if they do.
+///
+/// # Arguments
+/// - `limiter`: The [`RateLimiter`] that keeps track of how many requests clients have done.
+/// - `client`: Some [`SocketAddr`] of the client that connected.
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler, or a `429 TOO MANY REQUESTS` with a `Retry-After`-header and an [`ApiError`] of kind
+/// `rate_limited` if the client exceeded its rate.
+pub async fn handle(State(limiter): State<RateLimiter>, ConnectInfo(client): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    info!("Middleware 'ratelimit': checking client '{client}' rate");
+
+    // Check the client's bucket
+    if let Some(retry) = limiter.check(client.ip()) {
+        let retry: u64 = retry.as_secs() + if retry.subsec_nanos() > 0 { 1 } else { 0 };
+        debug!("Client '{client}' exceeded rate limit, returning 429 TOO MANY REQUESTS (retry after {retry}s)");
+        return (
+            [(RETRY_AFTER, retry.to_string())],
+            ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", format!("Too many requests; try again in {retry} seconds")),
+        )
+            .into_response();
+    }
+
+    // Otherwise, continue
+    next.run(request).await
+}
@@ -0,0 +1,79 @@
+//  REQUEST_ID.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 21:48:33
+//  Last edited:
+//    14 Oct 2026, 17:33:37
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware that attaches a unique [`RequestId`] to every
+//!   request, such that log lines of the same request can be correlated.
+//
+
+use std::fmt::{Display, Formatter, Result as FResult};
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use log::debug;
+use uuid::Uuid;
+
+
+/***** CONSTANTS *****/
+/// The header in which the request identifier is given (by clients) and returned (by us).
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The maximum length of request identifiers given by clients that we accept.
+pub const REQUEST_ID_MAX_LEN: usize = 128;
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Identifies a single request, such that its log lines can be correlated.
+///
+/// This is injected into the request's extensions by [`handle()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestId(pub String);
+impl Display for RequestId {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult { write!(f, "{}", self.0) }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles attaching a [`RequestId`] to every request, and returning it in the `x-request-id`-header of the response.
+///
+/// If the request already carries an `x-request-id`-header (e.g., because a reverse proxy set it), that identifier is re-used. Otherwise, a
+/// new UUID is generated.
+///
+/// # Arguments
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// The [`Response`] given by the `next` handler, with the `x-request-id`-header set.
+pub async fn handle(mut request: Request, next: Next) -> Response {
+    // Find the identifier
+    let id: HeaderValue = match request.headers().get(REQUEST_ID_HEADER) {
+        Some(id) if !id.is_empty() && id.len() <= REQUEST_ID_MAX_LEN && id.to_str().is_ok() => id.clone(),
+        _ => HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap(),
+    };
+    // NOTE: Safe to unwrap, since we checked it's valid ASCII above (or generated it ourselves)
+    let rid: RequestId = RequestId(id.to_str().unwrap().into());
+    debug!("Assigned request identifier '{rid}' to request for '{}'", request.uri().path());
+
+    // Inject it, run the request and return it
+    request.extensions_mut().insert(rid);
+    let mut res: Response = next.run(request).await;
+    res.headers_mut().insert(REQUEST_ID_HEADER, id);
+    res
+}
@@ -0,0 +1,139 @@
+//  STATIC_CACHE.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:37:12
+//  Last edited:
+//    14 Oct 2026, 18:36:16
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a middleware for the client files that adds `ETag`s and
+//!   `Cache-Control`-headers to them, and answers conditional requests
+//!   for unchanged files with `304 NOT MODIFIED`.
+//!   
+//!   The file server itself already does `Last-Modified` and
+//!   `If-Modified-Since`.
+//
+
+use axum::extract::Request;
+use axum::http::header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_NONE_MATCH, LAST_MODIFIED};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse as _, Response};
+use hyper::StatusCode;
+use log::debug;
+
+
+/***** CONSTANTS *****/
+/// The `Cache-Control` of files with a content hash in their name, which never change.
+pub const CACHE_CONTROL_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// The `Cache-Control` of anything else, which browsers must revalidate before using.
+pub const CACHE_CONTROL_REVALIDATE: &str = "no-cache";
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Checks whether the file at the given path has a content hash in its name, as bundlers emit (e.g., `index-4f9c2a1b.js`).
+///
+/// # Arguments
+/// - `path`: The path of the request.
+///
+/// # Returns
+/// True if the last `-`- or `.`-separated part of the file's stem looks like a hash, i.e., is 8 or more alphanumeric characters with at
+/// least one digit.
+fn is_hashed(path: &str) -> bool {
+    let name: &str = path.rsplit('/').next().unwrap_or(path);
+    let stem: &str = match name.rsplit_once('.') {
+        Some((stem, _)) => stem,
+        None => return false,
+    };
+    let hash: &str = stem.rsplit(['-', '.']).next().unwrap_or(stem);
+    hash.len() >= 8 && hash != stem && hash.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && hash.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Computes a weak `ETag` for a file response from its size and modification time.
+///
+/// # Arguments
+/// - `headers`: The [`HeaderMap`] of the response.
+///
+/// # Returns
+/// The `ETag`, or [`None`] if the response misses the `Content-Length` or `Last-Modified` to compute it from.
+fn etag(headers: &HeaderMap) -> Option<HeaderValue> {
+    let len: &str = headers.get(CONTENT_LENGTH)?.to_str().ok()?;
+    let modified: &str = headers.get(LAST_MODIFIED)?.to_str().ok()?;
+    // NOTE: The date is hashed down to keep the tag short and free of spaces
+    let modified: u64 = modified.bytes().fold(0xcbf29ce484222325, |hash: u64, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+    HeaderValue::from_str(&format!("W/\"{len}-{modified:x}\"")).ok()
+}
+
+/// Checks whether an `If-None-Match`-header matches the given `ETag`.
+///
+/// # Arguments
+/// - `if_none_match`: The value of the `If-None-Match`-header.
+/// - `etag`: The `ETag` of the file.
+///
+/// # Returns
+/// True if any of the given tags (weakly) matches, or if it's `*`.
+fn matches(if_none_match: &str, etag: &str) -> bool {
+    let etag: &str = etag.trim_start_matches("W/");
+    if_none_match.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles caching of the client files.
+///
+/// Files with a content hash in their name get a long-lived [`CACHE_CONTROL_IMMUTABLE`], while everything else (crucially, the
+/// `index.html`) gets [`CACHE_CONTROL_REVALIDATE`]. Successful responses get an `ETag`, which is compared against the request's
+/// `If-None-Match`.
+///
+/// # Arguments
+/// - `request`: A [`Request`] to pass to some...
+/// - `next`: A [`Next`] handler to call after this one succeeded.
+///
+/// # Returns
+/// A [`Response`] given by the `next` handler with caching headers added, or `304 NOT MODIFIED` if the client already has the file.
+pub async fn handle(request: Request, next: Next) -> Response {
+    let path: String = request.uri().path().into();
+    let if_none_match: Option<HeaderValue> = request.headers().get(IF_NONE_MATCH).cloned();
+    let mut res: Response = next.run(request).await;
+    if !res.status().is_success() && res.status() != StatusCode::NOT_MODIFIED {
+        return res;
+    }
+
+    // Decide how long the file may be cached
+    // NOTE: Unknown paths get the index, which must always be revalidated no matter what the path looks like
+    let is_html: bool = res.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(|ty| ty.starts_with("text/html")).unwrap_or(false);
+    let cache_control: &'static str = if !is_html && is_hashed(&path) { CACHE_CONTROL_IMMUTABLE } else { CACHE_CONTROL_REVALIDATE };
+    res.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+
+    // Tag the file, and check if the client already has it
+    if res.status() != StatusCode::OK {
+        return res;
+    }
+    let etag: HeaderValue = match etag(res.headers()) {
+        Some(etag) => etag,
+        None => return res,
+    };
+    if let (Some(Ok(if_none_match)), Ok(tag)) = (if_none_match.as_ref().map(HeaderValue::to_str), etag.to_str()) {
+        if matches(if_none_match, tag) {
+            debug!("Client already has '{path}' (ETag {tag}), returning 304 NOT MODIFIED");
+            let mut headers: HeaderMap = HeaderMap::with_capacity(3);
+            headers.insert(ETAG, etag);
+            headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+            if let Some(modified) = res.headers().get(LAST_MODIFIED) {
+                headers.insert(LAST_MODIFIED, modified.clone());
+            }
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+    }
+    res.headers_mut().insert(ETAG, etag);
+    res
+}
@@ -0,0 +1,289 @@
+//  MIGRATIONS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:40:12
+//  Last edited:
+//    14 Oct 2026, 19:17:12
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the versioned steps that bring the database schema up-to-date.
+//!   
+//!   Every backend has its own list, as their SQL dialects differ. Use
+//!   [`Database::migrate()`](crate::database::Database::migrate()) to apply
+//!   them.
+//
+
+
+/***** LIBRARY *****/
+/// Defines a single step in evolving the database schema.
+#[derive(Clone, Copy, Debug)]
+pub struct Migration {
+    /// The schema version that the database has after this migration has been applied. Should be strictly increasing.
+    pub version: u32,
+    /// The SQL (possibly multiple statements) that applies this migration.
+    pub up_sql:  &'static str,
+    /// A query that returns a row if the database already has the changes of this migration, in which case it is skipped (but still counts
+    /// as applied). Only needed for changes that older versions of the server made outside of migrations and that can't be expressed with
+    /// `IF NOT EXISTS`.
+    pub skip_if: Option<&'static str>,
+}
+
+
+
+/// The migrations for the SQLite backend, in order.
+///
+/// Note that the first migration is the schema from before migrations existed, and uses `IF NOT EXISTS` such that databases created back
+/// then converge to it. The ones after it catch up with what later versions of the server added outside of migrations.
+pub const SQLITE: &[Migration] = &[
+    Migration {
+        version: 1,
+        up_sql:  "CREATE TABLE IF NOT EXISTS users (id BIGINT UNSIGNED, name VARCHAR(32), password VARVAR(97), role TINYINT UNSIGNED, added TIMESTAMP);",
+        skip_if: None,
+    },
+    // Databases created before migrations existed may or may not have this column already, depending on the version of the server that
+    // created them; and SQLite has no `ADD COLUMN IF NOT EXISTS`.
+    Migration {
+        version: 2,
+        up_sql:  "ALTER TABLE users ADD COLUMN totp_secret VARCHAR(64);",
+        skip_if: Some("SELECT 1 FROM pragma_table_info('users') WHERE name = 'totp_secret'"),
+    },
+    Migration {
+        version: 3,
+        up_sql:  "CREATE TABLE IF NOT EXISTS revoked_tokens (jti VARCHAR(32) PRIMARY KEY, expires TIMESTAMP);
+                  CREATE TABLE IF NOT EXISTS password_resets (token_hash VARCHAR(64) PRIMARY KEY, user_id BIGINT UNSIGNED, expires TIMESTAMP);",
+        skip_if: None,
+    },
+    // Fixes the `VARVAR(97)` typo of the `password`-column, which is too short for Argon2 hashes anyway. SQLite cannot change column types
+    // in-place, so we rebuild the table.
+    Migration {
+        version: 4,
+        up_sql:  "CREATE TABLE users_new (id BIGINT UNSIGNED, name VARCHAR(32), password TEXT, role TINYINT UNSIGNED, added TIMESTAMP, totp_secret \
+                  VARCHAR(64));
+                  INSERT INTO users_new (id, name, password, role, added, totp_secret) SELECT id, name, password, role, added, totp_secret FROM users;
+                  DROP TABLE users;
+                  ALTER TABLE users_new RENAME TO users;",
+        skip_if: None,
+    },
+    Migration {
+        version: 5,
+        up_sql:  "CREATE TABLE audit_log (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id BIGINT UNSIGNED, kind VARCHAR(32), client_ip VARCHAR(45), \
+                  timestamp TIMESTAMP);",
+        skip_if: None,
+    },
+    // The `users`-table has no primary key in SQLite, but foreign keys can only reference unique columns; so we add an index on its `id` first.
+    Migration {
+        version: 6,
+        up_sql:  "CREATE UNIQUE INDEX users_id ON users (id);
+                  CREATE TABLE campaigns (id INTEGER PRIMARY KEY AUTOINCREMENT, name VARCHAR(64), dm_user_id BIGINT UNSIGNED REFERENCES users(id) ON \
+                  DELETE CASCADE, created TIMESTAMP);",
+        skip_if: None,
+    },
+    Migration {
+        version: 7,
+        up_sql:  "CREATE TABLE characters (id INTEGER PRIMARY KEY AUTOINCREMENT, owner_user_id BIGINT UNSIGNED REFERENCES users(id) ON DELETE CASCADE, \
+                  campaign_id BIGINT UNSIGNED REFERENCES campaigns(id) ON DELETE CASCADE, name VARCHAR(64), stats TEXT);",
+        skip_if: None,
+    },
+    Migration {
+        version: 8,
+        up_sql:  "CREATE TABLE rolls (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id BIGINT UNSIGNED REFERENCES users(id) ON DELETE CASCADE, campaign_id \
+                  BIGINT UNSIGNED REFERENCES campaigns(id) ON DELETE CASCADE, notation TEXT, result TEXT, timestamp TIMESTAMP);
+                  CREATE INDEX rolls_campaign ON rolls (campaign_id, id);",
+        skip_if: None,
+    },
+    Migration {
+        version: 9,
+        up_sql:  "CREATE TABLE messages (id INTEGER PRIMARY KEY AUTOINCREMENT, campaign_id BIGINT UNSIGNED REFERENCES campaigns(id) ON DELETE CASCADE, \
+                  user_id BIGINT UNSIGNED REFERENCES users(id) ON DELETE CASCADE, body TEXT, timestamp TIMESTAMP);
+                  CREATE INDEX messages_campaign ON messages (campaign_id, id);",
+        skip_if: None,
+    },
+    // Names used to be allowed to clash, in which case all but the first user are renamed by appending their identifier.
+    Migration {
+        version: 10,
+        up_sql:  "UPDATE users SET name = name || '-' || id WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY name);
+                  CREATE UNIQUE INDEX users_name ON users (name);",
+        skip_if: None,
+    },
+    // Lets SQLite allocate user identifiers instead of us. This once more requires rebuilding the table, which also drops its indices; the one
+    // on `id` is replaced by the primary key.
+    Migration {
+        version: 11,
+        up_sql:  "CREATE TABLE users_new (id INTEGER PRIMARY KEY AUTOINCREMENT, name VARCHAR(32), password TEXT, role TINYINT UNSIGNED, added \
+                  TIMESTAMP, totp_secret VARCHAR(64));
+                  INSERT INTO users_new (id, name, password, role, added, totp_secret) SELECT id, name, password, role, added, totp_secret FROM users;
+                  DROP TABLE users;
+                  ALTER TABLE users_new RENAME TO users;
+                  CREATE UNIQUE INDEX users_name ON users (name);",
+        skip_if: None,
+    },
+    Migration {
+        version: 12,
+        up_sql:  "CREATE TABLE campaign_members (campaign_id BIGINT UNSIGNED REFERENCES campaigns(id) ON DELETE CASCADE, user_id BIGINT UNSIGNED \
+                  REFERENCES users(id) ON DELETE CASCADE, joined TIMESTAMP, PRIMARY KEY (campaign_id, user_id));
+                  INSERT INTO campaign_members (campaign_id, user_id, joined) SELECT DISTINCT campaign_id, owner_user_id, CURRENT_TIMESTAMP FROM \
+                  characters;",
+        skip_if: None,
+    },
+    // Existing campaigns get a random (hexadecimal) code, which is fine since codes are only compared as a whole.
+    Migration {
+        version: 13,
+        up_sql:  "ALTER TABLE campaigns ADD COLUMN invite_code VARCHAR(16);
+                  UPDATE campaigns SET invite_code = upper(hex(randomblob(4)));
+                  CREATE UNIQUE INDEX campaigns_invite_code ON campaigns (invite_code);",
+        skip_if: None,
+    },
+    Migration {
+        version: 14,
+        up_sql:  "CREATE TABLE initiative (campaign_id BIGINT UNSIGNED REFERENCES campaigns(id) ON DELETE CASCADE, position INTEGER, name VARCHAR(64), \
+                  value INTEGER, is_current BOOLEAN, PRIMARY KEY (campaign_id, position));",
+        skip_if: None,
+    },
+    // Existing users are considered to be last changed when they were added.
+    Migration {
+        version: 15,
+        up_sql:  "ALTER TABLE users ADD COLUMN updated TIMESTAMP;
+                  UPDATE users SET updated = added;",
+        skip_if: None,
+    },
+    Migration { version: 16, up_sql: "ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP;", skip_if: None },
+    Migration { version: 17, up_sql: "ALTER TABLE characters ADD COLUMN resources TEXT NOT NULL DEFAULT '{}';", skip_if: None },
+    Migration { version: 18, up_sql: "ALTER TABLE characters ADD COLUMN updated_at TIMESTAMP;", skip_if: None },
+    // Makes names unique regardless of case. Names that only differed in case are renamed by appending their identifier like in migration
+    // 10, but as that may clash with another existing name (e.g., `alice` becoming `alice-2` while that exists as well), a `~<n>` is appended
+    // as well until it doesn't. Renamed names never clash with each other, as they all end in their (distinct) identifier.
+    Migration {
+        version: 19,
+        up_sql:  "UPDATE users SET name = (
+                      WITH RECURSIVE candidates(n, candidate) AS (
+                          SELECT 0, users.name || '-' || users.id
+                          UNION ALL
+                          SELECT n + 1, users.name || '-' || users.id || '~' || (n + 1) FROM candidates
+                           WHERE EXISTS (SELECT 1 FROM users AS other WHERE other.name = candidates.candidate COLLATE NOCASE)
+                      )
+                      SELECT candidate FROM candidates ORDER BY n DESC LIMIT 1
+                  ) WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY name COLLATE NOCASE);
+                  DROP INDEX users_name;
+                  CREATE UNIQUE INDEX users_name ON users (name COLLATE NOCASE);",
+        skip_if: None,
+    },
+    Migration { version: 20, up_sql: "ALTER TABLE users ADD COLUMN totp_pending VARCHAR(64);", skip_if: None },
+];
+
+/// The migrations for the Postgres backend, in order.
+///
+/// Note that the first migration uses `IF NOT EXISTS` throughout, such that databases created before migrations existed converge to it.
+pub const POSTGRES: &[Migration] = &[
+    Migration {
+        version: 1,
+        up_sql:  "CREATE TABLE IF NOT EXISTS users (id BIGINT PRIMARY KEY, name VARCHAR(32), password TEXT, role SMALLINT, added TIMESTAMPTZ, totp_secret \
+                  VARCHAR(64));
+                  CREATE TABLE IF NOT EXISTS revoked_tokens (jti VARCHAR(32) PRIMARY KEY, expires TIMESTAMPTZ);
+                  CREATE TABLE IF NOT EXISTS password_resets (token_hash VARCHAR(64) PRIMARY KEY, user_id BIGINT, expires TIMESTAMPTZ);",
+        skip_if: None,
+    },
+    Migration {
+        version: 2,
+        up_sql:  "CREATE TABLE audit_log (id BIGSERIAL PRIMARY KEY, user_id BIGINT, kind VARCHAR(32), client_ip VARCHAR(45), timestamp TIMESTAMPTZ);",
+        skip_if: None,
+    },
+    Migration {
+        version: 3,
+        up_sql:  "CREATE TABLE campaigns (id BIGSERIAL PRIMARY KEY, name VARCHAR(64), dm_user_id BIGINT REFERENCES users(id) ON DELETE CASCADE, created \
+                  TIMESTAMPTZ);",
+        skip_if: None,
+    },
+    Migration {
+        version: 4,
+        up_sql:  "CREATE TABLE characters (id BIGSERIAL PRIMARY KEY, owner_user_id BIGINT REFERENCES users(id) ON DELETE CASCADE, campaign_id BIGINT \
+                  REFERENCES campaigns(id) ON DELETE CASCADE, name VARCHAR(64), stats TEXT);",
+        skip_if: None,
+    },
+    Migration {
+        version: 5,
+        up_sql:  "CREATE TABLE rolls (id BIGSERIAL PRIMARY KEY, user_id BIGINT REFERENCES users(id) ON DELETE CASCADE, campaign_id BIGINT REFERENCES \
+                  campaigns(id) ON DELETE CASCADE, notation TEXT, result TEXT, timestamp TIMESTAMPTZ);
+                  CREATE INDEX rolls_campaign ON rolls (campaign_id, id);",
+        skip_if: None,
+    },
+    Migration {
+        version: 6,
+        up_sql:  "CREATE TABLE messages (id BIGSERIAL PRIMARY KEY, campaign_id BIGINT REFERENCES campaigns(id) ON DELETE CASCADE, user_id BIGINT REFERENCES \
+                  users(id) ON DELETE CASCADE, body TEXT, timestamp TIMESTAMPTZ);
+                  CREATE INDEX messages_campaign ON messages (campaign_id, id);",
+        skip_if: None,
+    },
+    // Names used to be allowed to clash, in which case all but the first user are renamed by appending their identifier.
+    Migration {
+        version: 7,
+        up_sql:  "UPDATE users SET name = name || '-' || id WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY name);
+                  CREATE UNIQUE INDEX users_name ON users (name);",
+        skip_if: None,
+    },
+    // Lets Postgres allocate user identifiers instead of us, continuing after the ones already handed out.
+    Migration {
+        version: 8,
+        up_sql:  "ALTER TABLE users ALTER COLUMN id ADD GENERATED BY DEFAULT AS IDENTITY;
+                  SELECT setval(pg_get_serial_sequence('users', 'id'), COALESCE((SELECT MAX(id) FROM users), 0) + 1, false);",
+        skip_if: None,
+    },
+    Migration {
+        version: 9,
+        up_sql:  "CREATE TABLE campaign_members (campaign_id BIGINT REFERENCES campaigns(id) ON DELETE CASCADE, user_id BIGINT REFERENCES users(id) ON \
+                  DELETE CASCADE, joined TIMESTAMPTZ, PRIMARY KEY (campaign_id, user_id));
+                  INSERT INTO campaign_members (campaign_id, user_id, joined) SELECT DISTINCT campaign_id, owner_user_id, CURRENT_TIMESTAMP FROM \
+                  characters;",
+        skip_if: None,
+    },
+    // Existing campaigns get a random (hexadecimal) code, which is fine since codes are only compared as a whole.
+    Migration {
+        version: 10,
+        up_sql:  "ALTER TABLE campaigns ADD COLUMN invite_code VARCHAR(16);
+                  UPDATE campaigns SET invite_code = upper(substr(md5(random()::text || id::text), 1, 8));
+                  CREATE UNIQUE INDEX campaigns_invite_code ON campaigns (invite_code);",
+        skip_if: None,
+    },
+    Migration {
+        version: 11,
+        up_sql:  "CREATE TABLE initiative (campaign_id BIGINT REFERENCES campaigns(id) ON DELETE CASCADE, position INTEGER, name VARCHAR(64), value \
+                  INTEGER, is_current BOOLEAN, PRIMARY KEY (campaign_id, position));",
+        skip_if: None,
+    },
+    // Existing users are considered to be last changed when they were added.
+    Migration {
+        version: 12,
+        up_sql:  "ALTER TABLE users ADD COLUMN updated TIMESTAMPTZ;
+                  UPDATE users SET updated = added;",
+        skip_if: None,
+    },
+    Migration { version: 13, up_sql: "ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;", skip_if: None },
+    Migration { version: 14, up_sql: "ALTER TABLE characters ADD COLUMN resources TEXT NOT NULL DEFAULT '{}';", skip_if: None },
+    Migration { version: 15, up_sql: "ALTER TABLE characters ADD COLUMN updated_at TIMESTAMPTZ;", skip_if: None },
+    // Makes names unique regardless of case. Names that only differed in case are renamed like in SQLite's migration 19.
+    Migration {
+        version: 16,
+        up_sql:  "DO $$
+                  DECLARE
+                      dup RECORD;
+                      candidate TEXT;
+                      n INTEGER;
+                  BEGIN
+                      FOR dup IN SELECT id, name FROM users WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY LOWER(name)) ORDER BY id LOOP
+                          candidate := dup.name || '-' || dup.id;
+                          n := 0;
+                          WHILE EXISTS (SELECT 1 FROM users WHERE LOWER(name) = LOWER(candidate)) LOOP
+                              n := n + 1;
+                              candidate := dup.name || '-' || dup.id || '~' || n;
+                          END LOOP;
+                          UPDATE users SET name = candidate WHERE id = dup.id;
+                      END LOOP;
+                  END $$;
+                  DROP INDEX users_name;
+                  CREATE UNIQUE INDEX users_name ON users (LOWER(name));",
+        skip_if: None,
+    },
+    Migration { version: 17, up_sql: "ALTER TABLE users ADD COLUMN totp_pending VARCHAR(64);", skip_if: None },
+];
@@ -0,0 +1,511 @@
+//  ADMIN.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:59:34
+//  Last edited:
+//    14 Oct 2026, 18:59:48
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for maintaining the server's database, such as making
+//!   backups of it or exporting its users.
+//
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use argon2::password_hash::PasswordHash;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Query, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use chrono::{DateTime, Utc};
+use enum_debug::EnumDebug as _;
+use error_trace::trace;
+use futures_util::stream;
+use hyper::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use hyper::StatusCode;
+use log::{debug, error, info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{is_bcrypt_hash, Role};
+use crate::database::{Error as DbError, UserInfo, UserSummary};
+use crate::middleware::auth::AuthUser;
+use crate::spec::{ApiError, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The number of users that are read from the database at a time when exporting them.
+pub const EXPORT_PAGE_SIZE: u64 = 256;
+
+/// The maximum length (in characters) of the names of imported users.
+pub const IMPORT_NAME_MAX_LEN: usize = 32;
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Defines how far along an export stream is.
+#[derive(Clone, Copy, Debug)]
+enum ExportStage {
+    /// We still have to send the header.
+    Header,
+    /// We're sending the page of users at the given offset.
+    Page { offset: u64 },
+    /// Everything has been sent.
+    Done,
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Escapes a field for use in a CSV file, as per RFC 4180.
+///
+/// # Arguments
+/// - `raw`: The field to escape.
+///
+/// # Returns
+/// The `raw` field if it needn't be escaped, or else a quoted version of it.
+fn csv_field(raw: &str) -> Cow<str> {
+    if raw.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", raw.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(raw)
+    }
+}
+
+/// Checks whether the given users are fit for importing.
+///
+/// # Arguments
+/// - `users`: The [`ImportUser`]s to check.
+///
+/// # Returns
+/// [`None`] if all users are valid, or else a `400 BAD REQUEST` [`Response`] with an [`ApiError`] of kind `invalid_import` that describes
+/// the first problem.
+fn validate_import(users: &[ImportUser]) -> Option<Response> {
+    let mut ids: HashSet<u64> = HashSet::with_capacity(users.len());
+    let mut names: HashSet<String> = HashSet::with_capacity(users.len());
+    for (i, user) in users.iter().enumerate() {
+        if user.name.is_empty() || user.name.chars().count() > IMPORT_NAME_MAX_LEN {
+            debug!("Imported user {i} has an invalid name, returning 400 BAD REQUEST");
+            return Some(
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", format!("User {i}: name must be between 1 and {IMPORT_NAME_MAX_LEN} characters"))
+                    .into_response(),
+            );
+        }
+        if is_bcrypt_hash(&user.password) {
+            if let Err(err) = user.password.parse::<bcrypt::HashParts>() {
+                debug!("Imported user {i} has an invalid bcrypt password hash ({err}), returning 400 BAD REQUEST");
+                return Some(
+                    ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", format!("User {i}: password is not a valid bcrypt hash: {err}")).into_response(),
+                );
+            }
+        } else if let Err(err) = PasswordHash::new(&user.password) {
+            debug!("Imported user {i} has an invalid password hash ({err}), returning 400 BAD REQUEST");
+            return Some(
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", format!("User {i}: password is not a valid PHC-formatted hash: {err}"))
+                    .into_response(),
+            );
+        }
+        if !ids.insert(user.id) {
+            debug!("Imported user {i} has a duplicate identifier, returning 400 BAD REQUEST");
+            return Some(
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", format!("User {i}: identifier {} is given more than once", user.id)).into_response(),
+            );
+        }
+        // NOTE: Names are unique regardless of (ASCII) case, like the database does
+        if !names.insert(user.name.to_ascii_lowercase()) {
+            debug!("Imported user {i} has a duplicate name, returning 400 BAD REQUEST");
+            return Some(
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", format!("User {i}: name '{}' is given more than once", user.name)).into_response(),
+            );
+        }
+    }
+    None
+}
+
+/// Serializes a page of users in the given format.
+///
+/// # Arguments
+/// - `format`: The [`ExportFormat`] to serialize to.
+/// - `users`: The page of [`UserInfo`]s to serialize. Their password hashes are left out.
+/// - `first`: Whether this is the first page, i.e., whether the first user should not be preceded by a separator.
+///
+/// # Returns
+/// The serialized page.
+///
+/// # Errors
+/// This function errors if we failed to serialize any of the users to JSON.
+fn export_page(format: ExportFormat, users: &[UserInfo], first: bool) -> Result<String, serde_json::Error> {
+    let mut chunk: String = String::new();
+    for (i, user) in users.iter().enumerate() {
+        match format {
+            ExportFormat::Csv => chunk.push_str(&format!(
+                "{},{},{},{},{}\r\n",
+                user.id,
+                csv_field(&user.name),
+                user.role.variant(),
+                user.added.to_rfc3339(),
+                user.updated.to_rfc3339()
+            )),
+            ExportFormat::Json => {
+                if !first || i > 0 {
+                    chunk.push(',');
+                }
+                chunk.push_str(&serde_json::to_string(&UserSummary::from(user))?);
+            },
+        }
+    }
+    Ok(chunk)
+}
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the database backup endpoint can be found.
+pub const BACKUP_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/admin/backup" };
+/// The reqwest-compatible path on which the user export endpoint can be found.
+pub const EXPORT_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/admin/export" };
+/// The reqwest-compatible path on which the user import endpoint can be found.
+pub const IMPORT_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/admin/import" };
+/// The reqwest-compatible path on which the maintenance mode toggle can be found.
+pub const MAINTENANCE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/admin/maintenance" };
+
+
+/// The body returned after backing up the database.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct BackupResponse {
+    /// The path (on the server) of the new backup.
+    pub path: PathBuf,
+}
+
+
+
+/// The formats in which users can be exported.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A CSV file with a header row, with the columns of a [`UserSummary`].
+    Csv,
+    /// A JSON array of [`UserSummary`]s.
+    #[default]
+    Json,
+}
+impl ExportFormat {
+    /// Returns the MIME type of this format.
+    #[inline]
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    /// Returns the extension of files in this format.
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+
+    /// Returns what precedes the users in this format.
+    #[inline]
+    fn header(&self) -> &'static str {
+        match self {
+            Self::Csv => "id,name,role,added,updated\r\n",
+            Self::Json => "[",
+        }
+    }
+
+    /// Returns what follows the users in this format.
+    #[inline]
+    fn footer(&self) -> &'static str {
+        match self {
+            Self::Csv => "",
+            Self::Json => "]",
+        }
+    }
+}
+
+/// The query parameters given when exporting users.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ExportQuery {
+    /// The format to export in. Defaults to JSON.
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+
+
+/// What to do with imported users that collide with existing ones.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Leave out the colliding users, but import the rest.
+    Skip,
+    /// Abort the whole import.
+    #[default]
+    Fail,
+}
+
+/// The query parameters given when importing users.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ImportQuery {
+    /// What to do with users that collide with existing ones. Defaults to failing.
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+/// A single user in the body of an import request.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ImportUser {
+    /// The identifier of the user.
+    pub id:       u64,
+    /// The name of the user.
+    pub name:     String,
+    /// The password of the user, already hashed (as a PHC string, e.g., `$argon2id$...`, or as a legacy bcrypt hash, e.g., `$2b$...`).
+    pub password: String,
+    /// The role of the user.
+    pub role:     Role,
+    /// The time the user was added. Defaults to now.
+    #[serde(default)]
+    pub added:    Option<DateTime<Utc>>,
+    /// The time the user was last changed. Defaults to when it was added.
+    #[serde(default)]
+    pub updated:  Option<DateTime<Utc>>,
+}
+
+
+
+/// The body of a request to switch maintenance mode on or off.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct MaintenanceRequest {
+    /// Whether maintenance mode should be on.
+    pub enabled: bool,
+}
+
+/// The body returned after switching maintenance mode on or off.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct MaintenanceResponse {
+    /// Whether maintenance mode is now on.
+    pub enabled: bool,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles backing up the database to a new, timestamped file in the configured backup directory.
+///
+/// Old backups beyond the configured number to keep are removed.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+///
+/// # Returns
+/// `201 CREATED` with a [`BackupResponse`] in the body that tells where the backup was written.
+///
+/// `501 NOT IMPLEMENTED` if the database backend doesn't support backups (i.e., it's not SQLite).
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we failed to write the backup.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn backup(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>) -> Response {
+    info!("Handling {} {} from '{}'", BACKUP_PATH.method, BACKUP_PATH.path, client);
+    let (dir, keep): (PathBuf, usize) = {
+        let config = state.config.read();
+        (config.backup_dir.clone(), config.backup_keep)
+    };
+
+    // Make the backup
+    match state.db.backup(dir, keep).await {
+        Ok(path) => {
+            info!("Backed up database to '{}'", path.display());
+            (StatusCode::CREATED, Json(BackupResponse { path })).into_response()
+        },
+        Err(DbError::BackupUnsupported) => {
+            debug!("Database backend doesn't support backups, returning 501 NOT IMPLEMENTED");
+            (StatusCode::NOT_IMPLEMENTED, "Backups are only supported for SQLite databases".to_string()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to back up database"), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to back up database".to_string()).into_response()
+        },
+    }
+}
+
+
+
+/// Handles exporting all users (without their password hashes) as a downloadable file.
+///
+/// The users are streamed in pages of [`EXPORT_PAGE_SIZE`], such that large tables never have to be kept in memory in their entirety.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `query`: An [`ExportQuery`] that determines the format to export in.
+///
+/// # Returns
+/// `200 OK` with the users (as [`UserSummary`]s) in the requested format in the body, ordered by identifier.
+///
+/// `400 BAD REQUEST` if the requested format is unknown.
+///
+/// # Errors
+/// This function may error if we failed to read a page of users from the database. As the response has already started by then, this
+/// aborts it instead of returning `500 INTERNAL SERVER ERROR`.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn export(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, Query(query): Query<ExportQuery>) -> Response {
+    info!("Handling {} {} from '{}'", EXPORT_PATH.method, EXPORT_PATH.path, client);
+    let format: ExportFormat = query.format;
+
+    // Stream the users page by page
+    let body: Body = Body::from_stream(stream::unfold(ExportStage::Header, move |stage| {
+        let state: ServerState = state.clone();
+        async move {
+            match stage {
+                ExportStage::Header => Some((Ok::<String, io::Error>(format.header().into()), ExportStage::Page { offset: 0 })),
+                ExportStage::Page { offset } => match state.db.list_users(offset, EXPORT_PAGE_SIZE, false).await {
+                    Ok(users) if users.is_empty() => {
+                        debug!("Exported {offset} user(s)");
+                        Some((Ok(format.footer().into()), ExportStage::Done))
+                    },
+                    Ok(users) => match export_page(format, &users, offset == 0) {
+                        Ok(chunk) => Some((Ok(chunk), ExportStage::Page { offset: offset + users.len() as u64 })),
+                        Err(err) => {
+                            error!("{}", trace!(("Failed to serialize users"), err));
+                            Some((Err(io::Error::other("Failed to serialize users")), ExportStage::Done))
+                        },
+                    },
+                    Err(err) => {
+                        error!("{}", trace!(("Failed to get users from database"), err));
+                        Some((Err(io::Error::other("Failed to get users from database")), ExportStage::Done))
+                    },
+                },
+                ExportStage::Done => None,
+            }
+        }
+    }));
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, format.content_type().to_string()), (CONTENT_DISPOSITION, format!("attachment; filename=\"users.{}\"", format.extension()))],
+        body,
+    )
+        .into_response()
+}
+
+
+
+/// Handles importing users from a dump, e.g., to bootstrap a new server.
+///
+/// All users are validated before any of them is inserted, and they are inserted in a single transaction; so either the whole import
+/// succeeds, or nothing changes.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `query`: An [`ImportQuery`] that determines what to do with users that collide with existing ones.
+/// - `body`: The [`ImportUser`]s to import.
+///
+/// # Returns
+/// `200 OK` with an [`ImportSummary`](crate::database::ImportSummary) in the body that tells how many users were inserted and skipped.
+///
+/// `400 BAD REQUEST` with an [`ApiError`] of kind `invalid_import` if any of the users was invalid (e.g., its password was not hashed), or
+/// if users were given twice.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `import_collision` if a user collided with an existing one while the `mode` was
+/// [`ImportMode::Fail`].
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn import(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Query(query): Query<ImportQuery>,
+    Json(body): Json<Vec<ImportUser>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", IMPORT_PATH.method, IMPORT_PATH.path, client);
+
+    // Check the users before touching the database
+    if let Some(res) = validate_import(&body) {
+        return res;
+    }
+    let now: DateTime<Utc> = Utc::now();
+    let users: Vec<UserInfo> = body
+        .into_iter()
+        .map(|user| {
+            let added: DateTime<Utc> = user.added.unwrap_or(now);
+            UserInfo { id: user.id, name: user.name, pass: user.password, role: user.role, added, updated: user.updated.unwrap_or(added), deleted: None }
+        })
+        .collect();
+
+    // Import them
+    match state.db.import_users(users, query.mode == ImportMode::Skip).await {
+        Ok(summary) => {
+            info!("Imported {} user(s), skipped {}", summary.inserted, summary.skipped);
+            (StatusCode::OK, Json(summary)).into_response()
+        },
+        Err(DbError::ImportCollision { id, name }) => {
+            debug!("Imported user {id} ('{name}') collides with an existing user, returning 409 CONFLICT");
+            ApiError::new(StatusCode::CONFLICT, "import_collision", format!("User {id} ('{name}') collides with an existing user; nothing was imported"))
+                .into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to import users into database"), err));
+            ApiError::internal("Failed to import users into database").into_response()
+        },
+    }
+}
+
+
+
+/// Handles switching maintenance mode on or off.
+///
+/// While it's on, all paths except for the health probes, logging in and the administrative paths are refused with
+/// `503 SERVICE UNAVAILABLE` (see [`crate::middleware::maintenance`]). Setting it to the mode it's already in is a no-op.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `body`: A [`MaintenanceRequest`] that says whether maintenance mode should be on.
+///
+/// # Returns
+/// `200 OK` with a [`MaintenanceResponse`] in the body that tells whether maintenance mode is now on.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn maintenance(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<MaintenanceRequest>,
+) -> Response {
+    info!("Handling {} {} from '{}'", MAINTENANCE_PATH.method, MAINTENANCE_PATH.path, client);
+
+    // Only log if we actually changed something
+    let was: bool = state.maintenance.swap(body.enabled, Ordering::Relaxed);
+    match (was, body.enabled) {
+        (false, true) => warn!("Server entered maintenance mode (by user {} '{}')", user.id, user.name),
+        (true, false) => info!("Server left maintenance mode (by user {} '{}')", user.id, user.name),
+        _ => debug!("Server already {} maintenance mode", if was { "in" } else { "out of" }),
+    }
+    (StatusCode::OK, Json(MaintenanceResponse { enabled: body.enabled })).into_response()
+}
@@ -0,0 +1,88 @@
+//  AUDIT.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:03:17
+//  Last edited:
+//    14 Oct 2026, 17:21:45
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for reading back the audit log of authentication events.
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Query, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::database::AuditEvent;
+use crate::spec::Path;
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The maximum number of events that can be retrieved in one go.
+pub const LIMIT_MAX: u64 = 100;
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the audit log endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::GET, path: "/v1/audit" };
+
+
+/// The query parameters given when reading the audit log.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct AuditQuery {
+    /// The maximum number of events to return. Capped at [`LIMIT_MAX`].
+    #[serde(default = "AuditQuery::default_limit")]
+    pub limit: u64,
+}
+impl AuditQuery {
+    /// Returns the default limit if none is given.
+    #[inline]
+    fn default_limit() -> u64 { LIMIT_MAX }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles reading back the most recent events in the audit log.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `query`: An [`AuditQuery`] that determines how many events to return.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`AuditEvent`]s in the body, newest first.
+///
+/// `400 BAD REQUEST` if the given `query` was invalid.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, Query(query): Query<AuditQuery>) -> Response {
+    info!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+
+    // Get the events
+    let events: Vec<AuditEvent> = match state.db.recent_events(query.limit.min(LIMIT_MAX)).await {
+        Ok(events) => events,
+        Err(err) => {
+            error!("{}", trace!(("Failed to get audit events from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get audit events from database".to_string()).into_response();
+        },
+    };
+    debug!("Returning {} audit event(s)", events.len());
+    (StatusCode::OK, Json(events)).into_response()
+}
@@ -4,49 +4,186 @@
 //  Created:
 //    09 Apr 2024, 12:18:07
 //  Last edited:
-//    09 Apr 2024, 12:56:54
+//    14 Oct 2026, 19:01:33
 //  Auto updated?
 //    Yes
 //
 //  Description:
-//!   Provides handlers for logging users in.
-//!   
-//!   Logging out is simply done by the client discarding the login token.
+//!   Provides handlers for registering users, logging them in and out,
+//!   refreshing their login tokens, enrolling them in two-factor
+//!   authentication and resetting their passwords.
 //
 
 use std::borrow::Cow;
 use std::net::SocketAddr;
 
 use axum::extract::{ConnectInfo, State};
-use axum::Json;
+use axum::{Extension, Json};
 use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::PrivateCookieJar;
+use chrono::{Duration, Utc};
 use enum_debug::EnumDebug as _;
 use error_trace::trace;
 use hyper::StatusCode;
 use log::{debug, error, info};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::auth::{check_password, check_token, create_token, LOGIN_TOKEN_NAME};
-use crate::database::UserInfo;
-use crate::spec::Path;
-use crate::state::ServerState;
+use crate::auth::{
+    check_dummy_password, check_password, check_token, check_totp, create_token, decode_token, generate_reset_token, generate_totp_secret, hash_password,
+    hash_reset_token, needs_rehash, totp_uri, validate_password, Argon2Params, LoginToken, DEFAULT_ROLE, LOGIN_TOKEN_NAME, RESET_TOKEN_VALID_TIME_MIN,
+};
+use crate::database::{AuditEventKind, Error as DbError, UserInfo};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::request_id::RequestId;
+use crate::spec::{ApiError, Path};
+use crate::state::{CookieConfig, ServerState};
 
 
 /***** SPEC *****/
-/// The reqwest-compatible path on which the version endpoint can be found.
-pub const PATH: Path = Path { method: hyper::Method::GET, path: "/v1/version" };
+/// The reqwest-compatible path on which the login endpoint can be found.
+pub const LOGIN_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/login" };
+/// The reqwest-compatible path on which the register endpoint can be found.
+pub const REGISTER_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/register" };
+/// The reqwest-compatible path on which the logout endpoint can be found.
+pub const LOGOUT_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/logout" };
+/// The reqwest-compatible path on which the refresh endpoint can be found.
+pub const REFRESH_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/refresh" };
+/// The reqwest-compatible path on which the TOTP enrollment endpoint can be found.
+pub const TOTP_ENROLL_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/totp" };
+/// The reqwest-compatible path on which the TOTP enrollment confirmation endpoint can be found.
+pub const TOTP_CONFIRM_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/totp/confirm" };
+/// The reqwest-compatible path on which the password reset request endpoint can be found.
+pub const RESET_REQUEST_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/reset/request" };
+/// The reqwest-compatible path on which the password reset endpoint can be found.
+pub const RESET_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/reset" };
+/// The reqwest-compatible path on which the password changing endpoint can be found.
+pub const CHANGE_PASSWORD_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/auth/change-password" };
 
 
 /// The request's body as given by the user.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct LoginRequest<'a> {
     /// The name of the user to login.
-    pub name: Cow<'a, str>,
+    pub name:     Cow<'a, str>,
     /// The password proving the user is who we think they are.
+    pub pass:     Cow<'a, str>,
+    /// The current TOTP code, if the user has enabled two-factor authentication.
+    #[serde(default)]
+    pub totp:     Option<Cow<'a, str>>,
+    /// Whether to issue a longer-lived token that survives browser restarts.
+    #[serde(default)]
+    pub remember: bool,
+}
+
+/// The request's body as given by a user that wants to register.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct RegisterRequest<'a> {
+    /// The name of the user to register.
+    pub name: Cow<'a, str>,
+    /// The password of the new user.
     pub pass: Cow<'a, str>,
 }
 
+/// The request's body as given by a user that wants to enroll in two-factor authentication.
+///
+/// At least one of the fields should be given to prove it's really the user.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct TotpEnrollRequest<'a> {
+    /// The current password of the user.
+    #[serde(default)]
+    pub pass: Option<Cow<'a, str>>,
+    /// A TOTP code for the current secret, if the user already enabled two-factor authentication.
+    #[serde(default)]
+    pub totp: Option<Cow<'a, str>>,
+}
+
+/// The request's body as given by a user that confirms their enrollment in two-factor authentication.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct TotpConfirmRequest<'a> {
+    /// A TOTP code for the pending secret, proving the user's authenticator generates the right codes.
+    pub code: Cow<'a, str>,
+}
+
+/// The request's body as given by a user that wants to reset their password.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ResetRequest<'a> {
+    /// The name of the user to reset the password of.
+    pub name: Cow<'a, str>,
+}
+
+/// The request's body as given by a logged-in user that changes their password.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ChangePasswordRequest<'a> {
+    /// The current password of the user, proving it's really them.
+    pub old: Cow<'a, str>,
+    /// The new password of the user.
+    pub new: Cow<'a, str>,
+}
+
+/// The request's body as given by a user that resets their password with a reset token.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ResetConfirmRequest<'a> {
+    /// The (single-use) reset token that proves the user may reset the password.
+    pub token: Cow<'a, str>,
+    /// The new password of the user.
+    pub pass:  Cow<'a, str>,
+}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Applies the configured attributes to a login cookie.
+///
+/// # Arguments
+/// - `config`: The [`CookieConfig`] with the attributes to apply.
+/// - `cookie`: The [`Cookie`] to apply them to.
+fn apply_cookie_config(config: &CookieConfig, cookie: &mut Cookie<'static>) {
+    cookie.set_same_site(config.same_site);
+    cookie.set_secure(config.secure);
+    cookie.set_http_only(config.http_only);
+    cookie.set_path(config.path.clone());
+    if let Some(domain) = &config.domain {
+        cookie.set_domain(domain.clone());
+    }
+}
+
+/// Builds the cookie that carries a login token.
+///
+/// # Arguments
+/// - `config`: The [`CookieConfig`] with the attributes of the cookie.
+/// - `token`: The serialized login token to embed.
+/// - `remember`: If [`Some`], the cookie is given a `Max-Age` of the given number of minutes such that it survives browser restarts.
+///   Otherwise, it is a session cookie.
+///
+/// # Returns
+/// A new [`Cookie`] that can be added to a [`PrivateCookieJar`].
+fn login_cookie(config: &CookieConfig, token: String, remember: Option<i64>) -> Cookie<'static> {
+    let mut cookie: Cookie<'static> = Cookie::new(LOGIN_TOKEN_NAME, token);
+    apply_cookie_config(config, &mut cookie);
+    if let Some(valid_min) = remember {
+        cookie.set_max_age(time::Duration::minutes(valid_min));
+    }
+    cookie
+}
+
+/// Builds the cookie that removes the login cookie.
+///
+/// Browsers only remove a cookie with a matching `Path` and `Domain`, so this needs the same attributes as [`login_cookie()`].
+///
+/// # Arguments
+/// - `config`: The [`CookieConfig`] with the attributes of the login cookie.
+///
+/// # Returns
+/// A new [`Cookie`] that can be removed from a [`PrivateCookieJar`].
+fn removal_cookie(config: &CookieConfig) -> Cookie<'static> {
+    let mut cookie: Cookie<'static> = Cookie::from(LOGIN_TOKEN_NAME);
+    apply_cookie_config(config, &mut cookie);
+    cookie
+}
+
 
 
 
@@ -57,77 +194,795 @@ pub struct LoginRequest<'a> {
 /// # Arguments
 /// - `state`: The shared [`ServerState`] between paths.
 /// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
 /// - `jar`: A [`PrivateCookieJar`] that we use to store cookies in.
-/// - `body`: A [`LoginRequest`] that contains the username/password to login with.
+/// - `body`: A [`LoginRequest`] that contains the username/password (and, if enabled, TOTP code) to login with.
 ///
 /// # Returns
-/// `200 OK` with the login token as a new cookie.
+/// `200 OK` with the login token as a new cookie. If the user asked to be remembered, the token (and cookie) are valid for longer.
 ///
 /// `400 BAD REQUEST` if the given `body` was invalid.
 ///
-/// `401 NOT AUTHORIZED` if the username was not found _or_ the password was invalid for that user.
+/// `401 NOT AUTHORIZED` with an [`ApiError`] if the username was not found or the password was invalid for that user (kind
+/// `invalid_credentials`; both cases take about as long and give the same response, such that they don't reveal which usernames exist), _or_ if the user has enabled two-factor authentication and the TOTP code was missing (kind `missing_totp`) or
+/// invalid (kind `invalid_totp`).
 ///
 /// # Errors
-/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to hash the given password or fail to contact the backend database.
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to hash the given password
+/// or fail to contact the backend database.
 #[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
 pub async fn login(
     State(state): State<ServerState>,
     ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
     jar: PrivateCookieJar,
     Json(body): Json<LoginRequest<'static>>,
-) -> (StatusCode, PrivateCookieJar, String) {
-    info!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+) -> Result<(StatusCode, PrivateCookieJar), ApiError> {
+    info!("[{rid}] Handling {} {} from '{}'", LOGIN_PATH.method, LOGIN_PATH.path, client);
 
     // Check if the user is already logged-in with a valid token
     if let Some(token) = jar.get(LOGIN_TOKEN_NAME) {
         // Ensure it's still valid!
-        debug!("Client presents us with login token {token:?}, checking validity");
-        match check_token(&state.db, token.value()) {
+        debug!("[{rid}] Client presents us with login token {token:?}, checking validity");
+        match check_token(&state.db, &state.key, token.value()).await {
             // It is, nothing to do
             Ok(Ok(token)) => {
-                debug!("Client '{}' login token is valid for user {} (role: {}), nothing to do", client, token.id, token.role.variant());
-                return (StatusCode::OK, jar, String::new());
+                debug!("[{rid}] Client '{}' login token is valid for user {} (role: {}), nothing to do", client, token.id, token.role.variant());
+                return Ok((StatusCode::OK, jar));
             },
             // It's invalid. Continue to insert.
             Ok(Err(err)) => {
-                debug!("{}", trace!(("Client '{client}' login token is not valid, logging user in"), err));
+                debug!("[{rid}] {}", trace!(("Client '{client}' login token is not valid, logging user in"), err));
             },
             // An error occurred
             Err(err) => {
-                error!("{}", trace!(("Failed to check token {:?} validity", token.value()), err));
-                return (StatusCode::INTERNAL_SERVER_ERROR, jar, String::new());
+                error!("[{rid}] {}", trace!(("Failed to check token {:?} validity", token.value()), err));
+                return Err(ApiError::internal("Failed to check login token"));
             },
         }
     }
 
     // Attempt to find this user in the database
-    debug!("Retrieving user '{}' from database", body.name);
-    let user: UserInfo = match state.db.get_user_by_name(body.name.as_ref()) {
+    debug!("[{rid}] Retrieving user '{}' from database", body.name);
+    let user: UserInfo = match state.db.get_user_by_name(body.name.as_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => {
-            debug!("User '{}' not found, returning 401 UNAUTHORIZED", body.name);
-            return (StatusCode::UNAUTHORIZED, jar, String::new());
+            debug!("[{rid}] User '{}' not found, returning 401 UNAUTHORIZED", body.name);
+            // NOTE: We still check the password (against a dummy), such that this takes as long as a wrong password for an existing user
+            let hash: Option<String> = state.dummy_hash.read().clone();
+            if let Some(hash) = hash {
+                if let Err(err) = check_dummy_password(&body.pass, &hash) {
+                    error!("[{rid}] {}", trace!(("Failed to check dummy password"), err));
+                }
+            }
+            if let Err(err) = state.db.log_event(AuditEventKind::LoginUnknownUser, None, client.ip()).await {
+                error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+            }
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid username or password"));
         },
         Err(err) => {
-            error!("{}", trace!(("Failed to get user info for user '{}' from database", body.name), err));
-            return (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to get user info for user '{}' from database", body.name));
+            error!("[{rid}] {}", trace!(("Failed to get user info for user '{}' from database", body.name), err));
+            return Err(ApiError::internal(format!("Failed to get user info for user '{}' from database", body.name)));
         },
     };
 
     // Check the passwords
-    debug!("Doing password gate-check...");
-    if !check_password(&body.pass, &user.pass) {
-        debug!("User '{}' password incorrect, returning 401 UNAUTHORIZED", body.name);
-        return (StatusCode::UNAUTHORIZED, jar, String::new());
+    debug!("[{rid}] Doing password gate-check...");
+    match check_password(&body.pass, &user.pass) {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("[{rid}] User '{}' password incorrect, returning 401 UNAUTHORIZED", body.name);
+            if let Err(err) = state.db.log_event(AuditEventKind::LoginBadPassword, Some(user.id), client.ip()).await {
+                error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+            }
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid username or password"));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to check password of user '{}'", body.name), err));
+            return Err(ApiError::internal(format!("Failed to check password of user '{}'", body.name)));
+        },
+    }
+
+    // Check the TOTP code, if the user enabled two-factor authentication
+    match state.db.get_totp_secret(user.id).await {
+        Ok(Some(secret)) => {
+            debug!("[{rid}] User '{}' has two-factor authentication enabled, doing TOTP gate-check...", body.name);
+            let code: &str = match &body.totp {
+                Some(code) => code.as_ref(),
+                None => {
+                    debug!("[{rid}] User '{}' did not provide TOTP code, returning 401 UNAUTHORIZED", body.name);
+                    if let Err(err) = state.db.log_event(AuditEventKind::LoginBadTotp, Some(user.id), client.ip()).await {
+                        error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+                    }
+                    return Err(ApiError::new(StatusCode::UNAUTHORIZED, "missing_totp", "Missing TOTP code"));
+                },
+            };
+            match check_totp(&secret, code) {
+                Ok(true) => {},
+                Ok(false) => {
+                    debug!("[{rid}] User '{}' TOTP code incorrect, returning 401 UNAUTHORIZED", body.name);
+                    if let Err(err) = state.db.log_event(AuditEventKind::LoginBadTotp, Some(user.id), client.ip()).await {
+                        error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+                    }
+                    return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_totp", "Invalid TOTP code"));
+                },
+                Err(err) => {
+                    error!("[{rid}] {}", trace!(("Failed to check TOTP code for user '{}'", body.name), err));
+                    return Err(ApiError::internal(format!("Failed to check TOTP code for user '{}'", body.name)));
+                },
+            }
+        },
+        Ok(None) => {},
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to get TOTP secret for user '{}' from database", body.name), err));
+            return Err(ApiError::internal(format!("Failed to get TOTP secret for user '{}' from database", body.name)));
+        },
+    }
+
+    // Upgrade legacy bcrypt hashes and hashes with outdated parameters now that we know the plaintext, such that all passwords eventually
+    // end up as Argon2 with the current parameters
+    // NOTE: Failing to do so doesn't stop the user from logging in; we'll just try again next time
+    let params: Argon2Params = state.config.read().argon2;
+    if needs_rehash(&user.pass, &params) {
+        debug!("[{rid}] User '{}' has outdated password hash, re-hashing with {params:?}...", body.name);
+        match hash_password(&body.pass, &params) {
+            Ok(hash) => match state.db.update_password(user.id, hash).await {
+                Ok(_) => info!("[{rid}] Upgraded password hash of user {}", user.id),
+                Err(err) => error!("[{rid}] {}", trace!(("Failed to update password hash of user {} in database", user.id), err)),
+            },
+            Err(err) => error!("[{rid}] {}", trace!(("Failed to re-hash password of user {}", user.id), err)),
+        }
     }
 
     // Alrighty that's it, generate a new token and return that
-    debug!("User '{}' password correct, generating token", body.name);
-    match create_token(user.id, user.role) {
-        Ok(token) => (StatusCode::OK, jar.add(Cookie::new(LOGIN_TOKEN_NAME, token)), String::new()),
+    debug!("[{rid}] User '{}' password correct, generating token (remember: {})", body.name, body.remember);
+    let (valid_min, cookie): (i64, CookieConfig) = {
+        let config = state.config.read();
+        (if body.remember { config.remember_valid_min } else { config.token_valid_min }, config.cookie.clone())
+    };
+    match create_token(&state.key, user.id, user.role, valid_min) {
+        Ok(token) => {
+            if let Err(err) = state.db.log_event(AuditEventKind::LoginSuccess, Some(user.id), client.ip()).await {
+                error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+            }
+            Ok((StatusCode::OK, jar.add(login_cookie(&cookie, token, if body.remember { Some(valid_min) } else { None }))))
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to get generate login token for user '{}'", body.name), err));
+            Err(ApiError::internal(format!("Failed to generate login token for user '{}'", body.name)))
+        },
+    }
+}
+
+
+
+/// Handles logging users out.
+///
+/// This is done by revoking the login token server-side and expiring the cookie, such that it doesn't linger at the client side. Note that
+/// this is idempotent, i.e., clients that aren't logged in can log out as much as they want.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `jar`: A [`PrivateCookieJar`] that we use to remove the login cookie from.
+///
+/// # Returns
+/// `200 OK` with the login token cookie removed.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn logout(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    jar: PrivateCookieJar,
+) -> (StatusCode, PrivateCookieJar, String) {
+    info!("[{rid}] Handling {} {} from '{}'", LOGOUT_PATH.method, LOGOUT_PATH.path, client);
+
+    // Revoke the token if there is any (and it's still meaningful to do so)
+    match jar.get(LOGIN_TOKEN_NAME) {
+        Some(token) => match decode_token(&state.key, token.value()) {
+            Ok(token) => {
+                debug!("[{rid}] Revoking token '{}' of user {}", token.jti, token.id);
+                if let Err(err) = state.db.revoke_token(&token.jti, token.expires()).await {
+                    error!("[{rid}] {}", trace!(("Failed to revoke token '{}'", token.jti), err));
+                    return (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to revoke '{LOGIN_TOKEN_NAME}' cookie"));
+                }
+                if let Err(err) = state.db.log_event(AuditEventKind::Logout, Some(token.id), client.ip()).await {
+                    error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+                }
+            },
+            Err(err) => debug!("[{rid}] {}", trace!(("Client '{client}' login token is not valid; not revoking"), err)),
+        },
+        None => debug!("[{rid}] Client '{client}' did not provide any token; nothing to log out"),
+    }
+
+    // Then remove the cookie
+    let cookie: Cookie<'static> = removal_cookie(&state.config.read().cookie);
+    (StatusCode::OK, jar.remove(cookie), String::new())
+}
+
+
+
+/// Handles refreshing login tokens.
+///
+/// This re-issues a fresh token for users that present a token that is still valid, allowing for sliding sessions without extending the
+/// lifetime of any individual token. The new token is valid for as long as the old one was, such that "remembered" sessions stay remembered.
+/// The old token is revoked, such that a leaked token cannot be kept alive by refreshing it alongside its owner.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `jar`: A [`PrivateCookieJar`] that contains the current login token and that we use to store the new one in.
+///
+/// # Returns
+/// `200 OK` with the new login token as a cookie (replacing the old one).
+///
+/// `401 NOT AUTHORIZED` if no token was given, or if the given token is invalid or expired.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database or fail to generate a new token.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn refresh(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    jar: PrivateCookieJar,
+) -> (StatusCode, PrivateCookieJar, String) {
+    info!("[{rid}] Handling {} {} from '{}'", REFRESH_PATH.method, REFRESH_PATH.path, client);
+
+    // Get the current token
+    let token: Cookie = match jar.get(LOGIN_TOKEN_NAME) {
+        Some(token) => token,
+        None => {
+            debug!("[{rid}] Client '{client}' did not provide any token, returning 401 UNAUTHORIZED");
+            return (StatusCode::UNAUTHORIZED, jar, format!("No '{LOGIN_TOKEN_NAME}' cookie given"));
+        },
+    };
+
+    // Find out how long it was valid
+    let old: LoginToken = match decode_token(&state.key, token.value()) {
+        Ok(token) => token,
+        Err(err) => {
+            debug!("[{rid}] {}", trace!(("Client '{client}' login token is not valid, returning 401 UNAUTHORIZED"), err));
+            return (StatusCode::UNAUTHORIZED, jar, format!("Invalid '{LOGIN_TOKEN_NAME}' cookie given"));
+        },
+    };
+
+    // Ensure it's still valid
+    let user: UserInfo = match check_token(&state.db, &state.key, token.value()).await {
+        Ok(Ok(user)) => user,
+        Ok(Err(err)) => {
+            debug!("[{rid}] {}", trace!(("Client '{client}' login token is not valid, returning 401 UNAUTHORIZED"), err));
+            return (StatusCode::UNAUTHORIZED, jar, format!("Invalid '{LOGIN_TOKEN_NAME}' cookie given"));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to check token {:?} validity", token.value()), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to check '{LOGIN_TOKEN_NAME}' cookie"));
+        },
+    };
+
+    // Revoke the old one, such that it can't be used (or refreshed) alongside the new one
+    debug!("[{rid}] Client '{}' login token is valid for user {}, revoking token '{}'", client, user.id, old.jti);
+    if let Err(err) = state.db.revoke_token(&old.jti, old.expires()).await {
+        error!("[{rid}] {}", trace!(("Failed to revoke token '{}'", old.jti), err));
+        return (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to revoke '{LOGIN_TOKEN_NAME}' cookie"));
+    }
+
+    // Re-issue
+    debug!("[{rid}] Re-issuing token for user {}", user.id);
+    let valid_min: i64 = old.valid_minutes;
+    let (remember, cookie): (Option<i64>, CookieConfig) = {
+        let config = state.config.read();
+        (if valid_min > config.token_valid_min { Some(valid_min) } else { None }, config.cookie.clone())
+    };
+    match create_token(&state.key, user.id, user.role, valid_min) {
+        Ok(token) => (StatusCode::OK, jar.add(login_cookie(&cookie, token, remember)), String::new()),
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to generate login token for user {}", user.id), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to generate login token for user {}", user.id))
+        },
+    }
+}
+
+
+
+/// Handles registering new users.
+///
+/// New users are always given the [`DEFAULT_ROLE`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `body`: A [`RegisterRequest`] that contains the username/password of the new user.
+///
+/// # Returns
+/// `201 CREATED` if the user was successfully added.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid, any of its fields were empty or the password is not strong enough.
+///
+/// `409 CONFLICT` if a user with the given name already exists.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to hash the given password or fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn register(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    Json(body): Json<RegisterRequest<'static>>,
+) -> (StatusCode, String) {
+    info!("[{rid}] Handling {} {} from '{}'", REGISTER_PATH.method, REGISTER_PATH.path, client);
+
+    // Assert the fields make sense
+    if body.name.is_empty() {
+        debug!("[{rid}] Client '{client}' provided an empty username, returning 400 BAD REQUEST");
+        return (StatusCode::BAD_REQUEST, "Username cannot be empty".into());
+    }
+    if body.pass.is_empty() {
+        debug!("[{rid}] Client '{client}' provided an empty password, returning 400 BAD REQUEST");
+        return (StatusCode::BAD_REQUEST, "Password cannot be empty".into());
+    }
+    if let Err(err) = validate_password(&body.pass, state.config.read().password_min_len) {
+        debug!("[{rid}] {}", trace!(("Client '{client}' provided a password that is not strong enough, returning 400 BAD REQUEST"), err));
+        return (StatusCode::BAD_REQUEST, err.to_string());
+    }
+
+    // Ensure the user does not yet exist
+    debug!("[{rid}] Checking if user '{}' already exists", body.name);
+    match state.db.get_user_by_name(body.name.as_ref()).await {
+        Ok(Some(_)) => {
+            debug!("[{rid}] User '{}' already exists, returning 409 CONFLICT", body.name);
+            return (StatusCode::CONFLICT, format!("User '{}' already exists", body.name));
+        },
+        Ok(None) => {},
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to get user info for user '{}' from database", body.name), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get user info for user '{}' from database", body.name));
+        },
+    }
+
+    // Hash the password
+    // NOTE: We copy the parameters out first, such that we don't hold the config lock while hashing
+    debug!("[{rid}] Hashing password of new user '{}'...", body.name);
+    let params: Argon2Params = state.config.read().argon2;
+    let hash: String = match hash_password(&body.pass, &params) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to hash password of new user '{}'", body.name), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to hash password of new user '{}'", body.name));
+        },
+    };
+
+    // Alrighty that's it, insert the user
+    match state.db.create_user(body.name.as_ref(), hash, DEFAULT_ROLE).await {
+        Ok(id) => {
+            debug!("[{rid}] Registered new user '{}' as user {}", body.name, id);
+            (StatusCode::CREATED, String::new())
+        },
+        // NOTE: Someone may have registered the same name since we checked
+        Err(DbError::DuplicateName { .. }) => {
+            debug!("[{rid}] User '{}' already exists, returning 409 CONFLICT", body.name);
+            (StatusCode::CONFLICT, format!("User '{}' already exists", body.name))
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to add new user '{}' to database", body.name), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to add new user '{}' to database", body.name))
+        },
+    }
+}
+
+
+
+/// Handles enrolling users in two-factor authentication.
+///
+/// This generates a new TOTP secret for the logged-in user and stores it as _pending_. It only takes effect once the user confirmed it with
+/// a code generated for it (see [`confirm_totp()`]), such that users cannot lock themselves out by enrolling without an authenticator.
+/// Calling this again replaces the pending secret; the current secret (if any) remains in effect until the new one is confirmed.
+///
+/// To keep someone who got hold of a session from (re-)enrolling its user, the request must prove it's really them by giving the current
+/// password or, if the user already enabled two-factor authentication, a current TOTP code.
+///
+/// Note that this path must be run behind the [`auth`](crate::middleware::auth::handle())-middleware, as it relies on the injected
+/// [`UserInfo`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `user`: The [`UserInfo`] of the logged-in user, as injected by the auth middleware.
+/// - `body`: A [`TotpEnrollRequest`] with the proof that it's really the user.
+///
+/// # Returns
+/// `200 OK` with an `otpauth://`-URI in the body that can be given to authenticator apps.
+///
+/// `401 NOT AUTHORIZED` with an [`ApiError`] of kind `invalid_credentials` if neither a correct password nor a correct TOTP code was given.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to generate the URI or fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn enroll_totp(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<TotpEnrollRequest<'static>>,
+) -> Result<(StatusCode, String), ApiError> {
+    info!("[{rid}] Handling {} {} from '{}'", TOTP_ENROLL_PATH.method, TOTP_ENROLL_PATH.path, client);
+
+    // Check that it's really the user, either by password...
+    let mut proven: bool = false;
+    if let Some(pass) = &body.pass {
+        match check_password(pass, &user.pass) {
+            Ok(correct) => proven = correct,
+            Err(err) => {
+                error!("[{rid}] {}", trace!(("Failed to check password of user {}", user.id), err));
+                return Err(ApiError::internal(format!("Failed to check password of user {}", user.id)));
+            },
+        }
+    }
+    // ...or by a code for the current secret
+    if !proven {
+        if let Some(code) = &body.totp {
+            let secret: Option<String> = match state.db.get_totp_secret(user.id).await {
+                Ok(secret) => secret,
+                Err(err) => {
+                    error!("[{rid}] {}", trace!(("Failed to get TOTP secret for user {} from database", user.id), err));
+                    return Err(ApiError::internal(format!("Failed to get TOTP secret for user {} from database", user.id)));
+                },
+            };
+            if let Some(secret) = secret {
+                match check_totp(&secret, code) {
+                    Ok(correct) => proven = correct,
+                    Err(err) => {
+                        error!("[{rid}] {}", trace!(("Failed to check TOTP code for user {}", user.id), err));
+                        return Err(ApiError::internal(format!("Failed to check TOTP code for user {}", user.id)));
+                    },
+                }
+            }
+        }
+    }
+    if !proven {
+        debug!("[{rid}] User {} did not prove their identity, returning 401 UNAUTHORIZED", user.id);
+        if let Err(err) = state.db.log_event(AuditEventKind::LoginBadPassword, Some(user.id), client.ip()).await {
+            error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+        }
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid password or TOTP code"));
+    }
+
+    // Generate the secret and the matching URI
+    debug!("[{rid}] Generating TOTP secret for user {}...", user.id);
+    let secret: String = generate_totp_secret();
+    let uri: String = match totp_uri(&secret, state.name, &user.name) {
+        Ok(uri) => uri,
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to generate otpauth URI for user {}", user.id), err));
+            return Err(ApiError::internal(format!("Failed to generate otpauth URI for user {}", user.id)));
+        },
+    };
+
+    // Store it until it's confirmed
+    match state.db.set_pending_totp_secret(user.id, &secret).await {
+        Ok(_) => {
+            debug!("[{rid}] Started enrolling user {} in two-factor authentication", user.id);
+            Ok((StatusCode::OK, uri))
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to store pending TOTP secret for user {}", user.id), err));
+            Err(ApiError::internal(format!("Failed to store TOTP secret for user {}", user.id)))
+        },
+    }
+}
+
+/// Handles users confirming their enrollment in two-factor authentication.
+///
+/// This enables the secret generated by [`enroll_totp()`], after checking that the user can generate codes for it. From then on, logging
+/// in requires a TOTP code.
+///
+/// Note that this path must be run behind the [`auth`](crate::middleware::auth::handle())-middleware, as it relies on the injected
+/// [`UserInfo`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `user`: The [`UserInfo`] of the logged-in user, as injected by the auth middleware.
+/// - `body`: A [`TotpConfirmRequest`] with a code generated for the pending secret.
+///
+/// # Returns
+/// `200 OK` if two-factor authentication is now enabled with the new secret.
+///
+/// `401 NOT AUTHORIZED` with an [`ApiError`] of kind `invalid_totp` if the code does not match the pending secret.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `no_pending_totp` if the user didn't start enrolling (or already confirmed it).
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn confirm_totp(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<TotpConfirmRequest<'static>>,
+) -> Result<StatusCode, ApiError> {
+    info!("[{rid}] Handling {} {} from '{}'", TOTP_CONFIRM_PATH.method, TOTP_CONFIRM_PATH.path, client);
+
+    // Get the secret to confirm
+    let secret: String = match state.db.get_pending_totp_secret(user.id).await {
+        Ok(Some(secret)) => secret,
+        Ok(None) => {
+            debug!("[{rid}] User {} has no pending TOTP secret, returning 409 CONFLICT", user.id);
+            return Err(ApiError::new(StatusCode::CONFLICT, "no_pending_totp", "Not enrolling in two-factor authentication"));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to get pending TOTP secret for user {} from database", user.id), err));
+            return Err(ApiError::internal(format!("Failed to get pending TOTP secret for user {} from database", user.id)));
+        },
+    };
+
+    // Check the code against it
+    match check_totp(&secret, &body.code) {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("[{rid}] User {} TOTP code incorrect, returning 401 UNAUTHORIZED", user.id);
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_totp", "Invalid TOTP code"));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to check TOTP code for user {}", user.id), err));
+            return Err(ApiError::internal(format!("Failed to check TOTP code for user {}", user.id)));
+        },
+    }
+
+    // OK, enable it
+    match state.db.set_totp_secret(user.id, &secret).await {
+        Ok(_) => {
+            debug!("[{rid}] Enrolled user {} in two-factor authentication", user.id);
+            Ok(StatusCode::OK)
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to store TOTP secret for user {}", user.id), err));
+            Err(ApiError::internal(format!("Failed to store TOTP secret for user {}", user.id)))
+        },
+    }
+}
+
+
+
+/// Handles users requesting a password reset.
+///
+/// This generates a single-use reset token that is valid for [`RESET_TOKEN_VALID_TIME_MIN`] minutes. For now, the token is not mailed to
+/// the user but logged instead, such that the server administrator can pass it on.
+///
+/// Note that this path always returns `200 OK`, regardless of whether the user exists, to avoid leaking which users exist.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `body`: A [`ResetRequest`] that contains the name of the user to reset the password of.
+///
+/// # Returns
+/// `200 OK` if the request was accepted.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn request_reset(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    Json(body): Json<ResetRequest<'static>>,
+) -> (StatusCode, String) {
+    info!("[{rid}] Handling {} {} from '{}'", RESET_REQUEST_PATH.method, RESET_REQUEST_PATH.path, client);
+
+    // Attempt to find this user in the database
+    debug!("[{rid}] Retrieving user '{}' from database", body.name);
+    let user: UserInfo = match state.db.get_user_by_name(body.name.as_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            debug!("[{rid}] User '{}' not found, not generating reset token", body.name);
+            return (StatusCode::OK, String::new());
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to get user info for user '{}' from database", body.name), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get user info for user '{}' from database", body.name));
+        },
+    };
+
+    // Generate a token and store its hash
+    let token: String = generate_reset_token();
+    if let Err(err) = state
+        .db
+        .create_password_reset(user.id, hash_reset_token(&token), Utc::now() + Duration::minutes(RESET_TOKEN_VALID_TIME_MIN))
+        .await
+    {
+        error!("[{rid}] {}", trace!(("Failed to store password reset token for user '{}'", body.name), err));
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to store password reset token for user '{}'", body.name));
+    }
+
+    // Pass it on
+    // TODO: Mail this to the user instead.
+    info!("[{rid}] Password reset token for user '{}' (valid for {} minutes): {}", user.name, RESET_TOKEN_VALID_TIME_MIN, token);
+    (StatusCode::OK, String::new())
+}
+
+/// Handles users resetting their password with a reset token.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `body`: A [`ResetConfirmRequest`] that contains the reset token and the new password.
+///
+/// # Returns
+/// `200 OK` if the password was reset.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid or the new password is not strong enough.
+///
+/// `401 NOT AUTHORIZED` if the given token is unknown, already used or expired.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to hash the given password or fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn reset(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    Json(body): Json<ResetConfirmRequest<'static>>,
+) -> (StatusCode, String) {
+    info!("[{rid}] Handling {} {} from '{}'", RESET_PATH.method, RESET_PATH.path, client);
+
+    // Check the new password
+    if let Err(err) = validate_password(&body.pass, state.config.read().password_min_len) {
+        debug!("[{rid}] {}", trace!(("Client '{client}' provided a password that is not strong enough, returning 400 BAD REQUEST"), err));
+        return (StatusCode::BAD_REQUEST, err.to_string());
+    }
+
+    // Hash it
+    // NOTE: We copy the parameters out first, such that we don't hold the config lock while hashing
+    let params: Argon2Params = state.config.read().argon2;
+    let hash: String = match hash_password(&body.pass, &params) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to hash new password"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash new password".into());
+        },
+    };
+
+    // Update the password if the token checks out
+    match state.db.consume_password_reset(hash_reset_token(&body.token), hash).await {
+        Ok(Some(id)) => {
+            debug!("[{rid}] Reset password of user {id}");
+            (StatusCode::OK, String::new())
+        },
+        Ok(None) => {
+            debug!("[{rid}] Client '{client}' provided an unknown or expired reset token, returning 401 UNAUTHORIZED");
+            (StatusCode::UNAUTHORIZED, "Invalid reset token given".into())
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to reset password"), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to reset password".into())
+        },
+    }
+}
+
+
+/// Handles logged-in users changing their own password.
+///
+/// On success, the login token used to do the request is revoked and replaced by a fresh one, such that a stolen token can't be used
+/// anymore. Note that other login tokens of the user remain valid until they expire or are logged out.
+///
+/// Note that this path must be run behind the [`auth`](crate::middleware::auth::handle())-middleware, as it relies on the injected
+/// [`UserInfo`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `user`: The [`UserInfo`] of the logged-in user, as injected by the auth middleware.
+/// - `jar`: A [`PrivateCookieJar`] with the current login token, which we replace.
+/// - `body`: A [`ChangePasswordRequest`] with the old and new passwords.
+///
+/// # Returns
+/// `200 OK` with a fresh login token as a new cookie.
+///
+/// `400 BAD REQUEST` with an [`ApiError`] of kind `weak_password` if the new password does not adhere to the password policy.
+///
+/// `401 NOT AUTHORIZED` with an [`ApiError`] of kind `invalid_credentials` if the old password was incorrect.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to hash the new password
+/// or fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn change_password(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    AuthUser(user): AuthUser,
+    jar: PrivateCookieJar,
+    Json(body): Json<ChangePasswordRequest<'static>>,
+) -> Result<(StatusCode, PrivateCookieJar), ApiError> {
+    info!("[{rid}] Handling {} {} from '{}'", CHANGE_PASSWORD_PATH.method, CHANGE_PASSWORD_PATH.path, client);
+
+    // Check the old password
+    match check_password(&body.old, &user.pass) {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("[{rid}] User {} old password incorrect, returning 401 UNAUTHORIZED", user.id);
+            if let Err(err) = state.db.log_event(AuditEventKind::LoginBadPassword, Some(user.id), client.ip()).await {
+                error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+            }
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid password"));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to check password of user {}", user.id), err));
+            return Err(ApiError::internal(format!("Failed to check password of user {}", user.id)));
+        },
+    }
+
+    // Check the new password
+    if let Err(err) = validate_password(&body.new, state.config.read().password_min_len) {
+        debug!("[{rid}] {}", trace!(("User {} provided a password that is not strong enough, returning 400 BAD REQUEST", user.id), err));
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "weak_password", err.to_string()));
+    }
+
+    // Hash it and store it
+    // NOTE: We copy the parameters out first, such that we don't hold the config lock while hashing
+    let params: Argon2Params = state.config.read().argon2;
+    let hash: String = match hash_password(&body.new, &params) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to hash new password"), err));
+            return Err(ApiError::internal("Failed to hash new password"));
+        },
+    };
+    match state.db.update_password(user.id, hash).await {
+        Ok(true) => {},
+        Ok(false) => {
+            // NOTE: Only happens if the user was deleted while we were busy
+            error!("[{rid}] User {} disappeared while changing their password", user.id);
+            return Err(ApiError::internal(format!("Failed to update password of user {}", user.id)));
+        },
+        Err(err) => {
+            error!("[{rid}] {}", trace!(("Failed to update password of user {} in database", user.id), err));
+            return Err(ApiError::internal(format!("Failed to update password of user {} in database", user.id)));
+        },
+    }
+    debug!("[{rid}] Changed password of user {}", user.id);
+    if let Err(err) = state.db.log_event(AuditEventKind::PasswordChanged, Some(user.id), client.ip()).await {
+        error!("[{rid}] {}", trace!(("Failed to log audit event"), err));
+    }
+
+    // Revoke the token used to get here...
+    if let Some(token) = jar.get(LOGIN_TOKEN_NAME) {
+        match decode_token(&state.key, token.value()) {
+            Ok(token) => {
+                if let Err(err) = state.db.revoke_token(&token.jti, token.expires()).await {
+                    error!("[{rid}] {}", trace!(("Failed to revoke token '{}'", token.jti), err));
+                    return Err(ApiError::internal(format!("Failed to revoke '{LOGIN_TOKEN_NAME}' cookie")));
+                }
+            },
+            Err(err) => debug!("[{rid}] {}", trace!(("Client '{client}' login token is not valid; not revoking"), err)),
+        }
+    }
+
+    // ...and give the user a fresh one
+    let (valid_min, cookie): (i64, CookieConfig) = {
+        let config = state.config.read();
+        (config.token_valid_min, config.cookie.clone())
+    };
+    match create_token(&state.key, user.id, user.role, valid_min) {
+        Ok(token) => Ok((StatusCode::OK, jar.add(login_cookie(&cookie, token, None)))),
         Err(err) => {
-            error!("{}", trace!(("Failed to get generate login token for user '{}'", body.name), err));
-            return (StatusCode::INTERNAL_SERVER_ERROR, jar, format!("Failed to get generate login token for user '{}'", body.name));
+            error!("[{rid}] {}", trace!(("Failed to generate login token for user {}", user.id), err));
+            Err(ApiError::internal(format!("Failed to generate login token for user {}", user.id)))
         },
     }
 }
@@ -0,0 +1,935 @@
+//  CAMPAIGNS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:12:58
+//  Last edited:
+//    14 Oct 2026, 18:59:18
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for creating, reading and deleting campaigns, for
+//!   managing who plays in them (either directly or through invite codes),
+//!   for chatting in them and for reading back the rolls made in them.
+//
+
+use std::borrow::Cow;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Path as PathParam, Query, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Role;
+use crate::database::{Campaign, CampaignMember, ChatMessage, Roll};
+use crate::events::Event;
+use crate::middleware::auth::AuthUser;
+use crate::spec::{ApiError, Page, Pagination, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The maximum length (in characters) of campaign names.
+pub const NAME_MAX_LEN: usize = 64;
+
+/// The maximum length (in characters) of chat messages.
+pub const MESSAGE_MAX_LEN: usize = 4000;
+/// The number of rolls returned by the roll history endpoint if no limit is given.
+pub const ROLLS_LIMIT_DEFAULT: u64 = 50;
+/// The maximum number of rolls that can be retrieved from the roll history endpoint in one go.
+pub const ROLLS_LIMIT_MAX: u64 = 100;
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the campaign creation endpoint can be found.
+pub const CREATE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns" };
+/// The reqwest-compatible path on which the campaign listing endpoint can be found.
+pub const LIST_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns" };
+/// The reqwest-compatible path on which the campaign retrieval endpoint can be found.
+pub const GET_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id" };
+/// The reqwest-compatible path on which the campaign deletion endpoint can be found.
+pub const DELETE_PATH: Path = Path { method: hyper::Method::DELETE, path: "/v1/campaigns/:id" };
+/// The reqwest-compatible path on which the member listing endpoint can be found.
+pub const MEMBERS_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/members" };
+/// The reqwest-compatible path on which the member adding endpoint can be found.
+pub const ADD_MEMBER_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns/:id/members" };
+/// The reqwest-compatible path on which the member removal endpoint can be found.
+pub const REMOVE_MEMBER_PATH: Path = Path { method: hyper::Method::DELETE, path: "/v1/campaigns/:id/members/:user" };
+/// The reqwest-compatible path on which the invite code retrieval endpoint can be found.
+pub const INVITE_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/invite" };
+/// The reqwest-compatible path on which the invite code rotation endpoint can be found.
+pub const ROTATE_INVITE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns/:id/invite" };
+/// The reqwest-compatible path on which the campaign joining endpoint can be found.
+pub const JOIN_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns/join" };
+/// The reqwest-compatible path on which the message posting endpoint can be found.
+pub const POST_MESSAGE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns/:id/messages" };
+/// The reqwest-compatible path on which the message history endpoint can be found.
+pub const MESSAGES_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/messages" };
+/// The reqwest-compatible path on which the roll history endpoint can be found.
+pub const ROLLS_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/rolls" };
+
+
+/// The request's body as given by a user that wants to create a campaign.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "CreateCampaignRequest")]
+pub struct CreateRequest<'a> {
+    /// The name of the new campaign.
+    pub name: Cow<'a, str>,
+}
+
+/// The request's body as given by a dungeon master that wants to add a player to their campaign.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct AddMemberRequest {
+    /// The identifier of the user to add.
+    pub user: u64,
+}
+
+/// The request's body as given by a user that wants to join a campaign.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct JoinRequest<'a> {
+    /// The invite code of the campaign to join. Case-insensitive.
+    pub code: Cow<'a, str>,
+}
+
+/// The response's body when asking for the invite code of a campaign.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct InviteResponse {
+    /// The code with which players can join the campaign.
+    pub code: String,
+}
+
+/// The request's body as given by a user that wants to post a chat message.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct PostMessageRequest<'a> {
+    /// The contents of the message.
+    pub body: Cow<'a, str>,
+}
+
+/// The query parameters given when reading the roll history of a campaign.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct RollsQuery {
+    /// The maximum number of rolls to return. Capped at [`ROLLS_LIMIT_MAX`].
+    #[serde(default = "RollsQuery::default_limit")]
+    pub limit: u64,
+}
+impl RollsQuery {
+    /// Returns the default limit if none is given.
+    #[inline]
+    fn default_limit() -> u64 { ROLLS_LIMIT_DEFAULT }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles creating new campaigns.
+///
+/// The user creating the campaign becomes its dungeon master. This path should only be reachable by users with at least the
+/// [`Role::DungeonMaster`] role.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `body`: A [`CreateRequest`] that describes the new campaign.
+///
+/// # Returns
+/// `201 CREATED` with the new [`Campaign`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid or the name was empty or too long.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn create(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<CreateRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", CREATE_PATH.method, CREATE_PATH.path, client);
+
+    // Check the name
+    if body.name.is_empty() || body.name.chars().count() > NAME_MAX_LEN {
+        debug!("Campaign name '{}' is empty or too long, returning 400 BAD REQUEST", body.name);
+        return (StatusCode::BAD_REQUEST, format!("Campaign name must be between 1 and {NAME_MAX_LEN} characters")).into_response();
+    }
+
+    // Create it
+    match state.db.create_campaign(body.name.as_ref(), user.id).await {
+        Ok(Some(campaign)) => {
+            debug!("Created campaign {} '{}' for user {}", campaign.id, campaign.name, user.id);
+            (StatusCode::CREATED, Json(campaign)).into_response()
+        },
+        Ok(None) => {
+            error!("User {} disappeared while creating campaign '{}'", user.id, body.name);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create campaign '{}'", body.name)).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to create campaign '{}' in database", body.name), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create campaign '{}'", body.name)).into_response()
+        },
+    }
+}
+
+
+
+/// Handles listing the campaigns of the logged-in user.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`Campaign`]s that the user is the dungeon master or a member of.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn list(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, AuthUser(user): AuthUser) -> Response {
+    info!("Handling {} {} from '{}'", LIST_PATH.method, LIST_PATH.path, client);
+
+    match state.db.list_campaigns_for_user(user.id).await {
+        Ok(campaigns) => {
+            debug!("Returning {} campaign(s) of user {}", campaigns.len(), user.id);
+            (StatusCode::OK, Json(campaigns)).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to list campaigns of user {} in database", user.id), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to list campaigns".to_string()).into_response()
+        },
+    }
+}
+
+
+
+/// Handles retrieving a single campaign.
+///
+/// Only the campaign's members (i.e., its dungeon master and the players that joined it) and root can see it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to retrieve.
+///
+/// # Returns
+/// `200 OK` with the [`Campaign`] in the body.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist or the user is not allowed to see it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn get(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", GET_PATH.method, GET_PATH.path, client);
+
+    // Check if the user may see it
+    if user.role < Role::Root {
+        match state.db.is_member(id, user.id).await {
+            Ok(true) => {},
+            Ok(false) => {
+                debug!("Campaign {id} not found or not visible to user {}, returning 404 NOT FOUND", user.id);
+                return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+                return ApiError::internal(format!("Failed to get campaign {id}")).into_response();
+            },
+        }
+    }
+
+    // Get it
+    let campaign: Campaign = match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => campaign,
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to get campaign {id}")).into_response();
+        },
+    };
+    (StatusCode::OK, Json(campaign)).into_response()
+}
+
+
+
+/// Handles deleting a campaign.
+///
+/// Only the campaign's dungeon master and root can delete it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to delete.
+///
+/// # Returns
+/// `200 OK` if the campaign was deleted.
+///
+/// `403 FORBIDDEN` if the user is not allowed to delete the campaign.
+///
+/// `404 NOT FOUND` if the campaign does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn delete(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", DELETE_PATH.method, DELETE_PATH.path, client);
+
+    // Check if the user may delete it
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return (StatusCode::FORBIDDEN, String::new()).into_response();
+            }
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete campaign {id}")).into_response();
+        },
+    }
+
+    // Delete it
+    match state.db.delete_campaign(id).await {
+        Ok(true) => (StatusCode::OK, String::new()).into_response(),
+        Ok(false) => {
+            debug!("Campaign {id} was deleted concurrently, returning 404 NOT FOUND");
+            (StatusCode::NOT_FOUND, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to delete campaign {id} from database"), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles listing the players that joined a campaign.
+///
+/// Only members of the campaign (i.e., its dungeon master and the players that joined it) and root can see them.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to list the members of.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`CampaignMember`]s in the body, in the order they joined.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn members(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", MEMBERS_PATH.method, MEMBERS_PATH.path, client);
+
+    // Check if the user may see them
+    if user.role < Role::Root {
+        match state.db.is_member(id, user.id).await {
+            Ok(true) => {},
+            Ok(false) => {
+                debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+                return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+                return ApiError::internal(format!("Failed to get members of campaign {id}")).into_response();
+            },
+        }
+    }
+
+    // Get the members
+    let members: Vec<CampaignMember> = match state.db.list_members(id).await {
+        Ok(members) => members,
+        Err(err) => {
+            error!("{}", trace!(("Failed to get members of campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to get members of campaign {id}")).into_response();
+        },
+    };
+    debug!("Returning {} member(s) of campaign {id}", members.len());
+    (StatusCode::OK, Json(members)).into_response()
+}
+
+
+
+/// Handles adding a player to a campaign.
+///
+/// Only the campaign's dungeon master and root can add players.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to add the player to.
+/// - `body`: An [`AddMemberRequest`] that describes who to add.
+///
+/// # Returns
+/// `201 CREATED` if the player was added.
+///
+/// `200 OK` if the player already was a member, in which case nothing changes.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to add players to the campaign.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist, or of kind `user_not_found` if the
+/// player does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn add_member(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<AddMemberRequest>,
+) -> Response {
+    info!("Handling {} {} from '{}'", ADD_MEMBER_PATH.method, ADD_MEMBER_PATH.path, client);
+
+    // Check if the user may add players
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return ApiError::new(StatusCode::FORBIDDEN, "not_campaign_dm", format!("Only the dungeon master of campaign {id} may do this"))
+                    .into_response();
+            }
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to add member to campaign {id}")).into_response();
+        },
+    }
+
+    // Check the player exists
+    match state.db.get_user_by_id(body.user).await {
+        Ok(Some(_)) => {},
+        Ok(None) => {
+            debug!("User {} not found, returning 404 NOT FOUND", body.user);
+            return ApiError::new(StatusCode::NOT_FOUND, "user_not_found", format!("User {} not found", body.user)).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get user {} from database", body.user), err));
+            return ApiError::internal(format!("Failed to add member to campaign {id}")).into_response();
+        },
+    }
+
+    // Add them
+    match state.db.add_member(id, body.user).await {
+        Ok(true) => {
+            debug!("Added user {} to campaign {id}", body.user);
+            (StatusCode::CREATED, String::new()).into_response()
+        },
+        Ok(false) => {
+            debug!("User {} already is a member of campaign {id}, returning 200 OK", body.user);
+            (StatusCode::OK, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to add user {} to campaign {id} in database", body.user), err));
+            ApiError::internal(format!("Failed to add member to campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles removing a player from a campaign.
+///
+/// The campaign's dungeon master and root can remove anyone, whereas other players can only remove themselves (i.e., leave).
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to remove the player from.
+/// - `member`: The identifier of the player to remove.
+///
+/// # Returns
+/// `200 OK` if the player was removed.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to remove the player.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist, or of kind `not_a_member` if the
+/// player is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn remove_member(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam((id, member)): PathParam<(u64, u64)>,
+) -> Response {
+    info!("Handling {} {} from '{}'", REMOVE_MEMBER_PATH.method, REMOVE_MEMBER_PATH.path, client);
+
+    // Check if the user may remove the player
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if member != user.id && campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return ApiError::new(StatusCode::FORBIDDEN, "not_campaign_dm", format!("Only the dungeon master of campaign {id} may do this"))
+                    .into_response();
+            }
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to remove member from campaign {id}")).into_response();
+        },
+    }
+
+    // Remove them
+    match state.db.remove_member(id, member).await {
+        Ok(true) => {
+            debug!("Removed user {member} from campaign {id}");
+            (StatusCode::OK, String::new()).into_response()
+        },
+        Ok(false) => {
+            debug!("User {member} is not a member of campaign {id}, returning 404 NOT FOUND");
+            ApiError::new(StatusCode::NOT_FOUND, "not_a_member", format!("User {member} is not a member of campaign {id}")).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to remove user {member} from campaign {id} in database"), err));
+            ApiError::internal(format!("Failed to remove member from campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles retrieving the invite code of a campaign.
+///
+/// Only the campaign's dungeon master and root can see it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to retrieve the invite code of.
+///
+/// # Returns
+/// `200 OK` with an [`InviteResponse`] in the body.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to see the invite code.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn invite(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", INVITE_PATH.method, INVITE_PATH.path, client);
+
+    // Check if the user may see it
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return ApiError::new(StatusCode::FORBIDDEN, "not_campaign_dm", format!("Only the dungeon master of campaign {id} may do this"))
+                    .into_response();
+            }
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to get invite code of campaign {id}")).into_response();
+        },
+    }
+
+    // Get it
+    match state.db.get_invite_code(id).await {
+        Ok(Some(code)) => (StatusCode::OK, Json(InviteResponse { code })).into_response(),
+        Ok(None) => {
+            debug!("Campaign {id} was deleted concurrently, returning 404 NOT FOUND");
+            ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get invite code of campaign {id} from database"), err));
+            ApiError::internal(format!("Failed to get invite code of campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles replacing the invite code of a campaign, such that the old one can no longer be used to join.
+///
+/// Only the campaign's dungeon master and root can rotate it. Players that already joined remain members.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to rotate the invite code of.
+///
+/// # Returns
+/// `200 OK` with an [`InviteResponse`] with the new code in the body.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to rotate the invite code.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn rotate_invite(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", ROTATE_INVITE_PATH.method, ROTATE_INVITE_PATH.path, client);
+
+    // Check if the user may rotate it
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return ApiError::new(StatusCode::FORBIDDEN, "not_campaign_dm", format!("Only the dungeon master of campaign {id} may do this"))
+                    .into_response();
+            }
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            return ApiError::internal(format!("Failed to rotate invite code of campaign {id}")).into_response();
+        },
+    }
+
+    // Rotate it
+    match state.db.rotate_invite_code(id).await {
+        Ok(Some(code)) => {
+            debug!("Rotated invite code of campaign {id}");
+            (StatusCode::OK, Json(InviteResponse { code })).into_response()
+        },
+        Ok(None) => {
+            debug!("Campaign {id} was deleted concurrently, returning 404 NOT FOUND");
+            ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to rotate invite code of campaign {id} in database"), err));
+            ApiError::internal(format!("Failed to rotate invite code of campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles joining a campaign with its invite code.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `body`: A [`JoinRequest`] with the invite code.
+///
+/// # Returns
+/// `200 OK` with the joined [`Campaign`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `invite_not_found` if no campaign has the given invite code.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `already_member` if the user already is a member of the campaign (including if they are
+/// its dungeon master).
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn join(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<JoinRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", JOIN_PATH.method, JOIN_PATH.path, client);
+
+    // Find the campaign
+    let campaign: Campaign = match state.db.get_campaign_by_invite_code(body.code.as_ref()).await {
+        Ok(Some(campaign)) => campaign,
+        Ok(None) => {
+            debug!("No campaign with invite code '{}', returning 404 NOT FOUND", body.code);
+            return ApiError::new(StatusCode::NOT_FOUND, "invite_not_found", "Unknown invite code").into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign with invite code '{}' from database", body.code), err));
+            return ApiError::internal("Failed to join campaign").into_response();
+        },
+    };
+
+    // Check if the user is already in it
+    match state.db.is_member(campaign.id, user.id).await {
+        Ok(false) => {},
+        Ok(true) => {
+            debug!("User {} already is a member of campaign {}, returning 409 CONFLICT", user.id, campaign.id);
+            return ApiError::new(StatusCode::CONFLICT, "already_member", format!("Already a member of campaign {}", campaign.id)).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {}", user.id, campaign.id), err));
+            return ApiError::internal(format!("Failed to join campaign {}", campaign.id)).into_response();
+        },
+    }
+
+    // Join it
+    match state.db.add_member(campaign.id, user.id).await {
+        Ok(true) => {
+            debug!("User {} joined campaign {}", user.id, campaign.id);
+            (StatusCode::OK, Json(campaign)).into_response()
+        },
+        Ok(false) => {
+            debug!("User {} joined campaign {} concurrently, returning 409 CONFLICT", user.id, campaign.id);
+            ApiError::new(StatusCode::CONFLICT, "already_member", format!("Already a member of campaign {}", campaign.id)).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to add user {} to campaign {} in database", user.id, campaign.id), err));
+            ApiError::internal(format!("Failed to join campaign {}", campaign.id)).into_response()
+        },
+    }
+}
+
+
+
+/// Handles reading back the most recent rolls made in a campaign.
+///
+/// Only members of the campaign (i.e., its dungeon master and the players that joined it) can see them.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to retrieve the rolls of.
+/// - `query`: A [`RollsQuery`] that determines how many rolls to return.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`Roll`]s in the body, newest first.
+///
+/// `400 BAD REQUEST` if the given `query` was invalid.
+///
+/// `404 NOT FOUND` if the campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn rolls(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Query(query): Query<RollsQuery>,
+) -> Response {
+    info!("Handling {} {} from '{}'", ROLLS_PATH.method, ROLLS_PATH.path, client);
+
+    // Check if the user may see them
+    match state.db.is_member(id, user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get rolls of campaign {id}")).into_response();
+        },
+    }
+
+    // Get the rolls
+    let rolls: Vec<Roll> = match state.db.recent_rolls(id, query.limit.min(ROLLS_LIMIT_MAX)).await {
+        Ok(rolls) => rolls,
+        Err(err) => {
+            error!("{}", trace!(("Failed to get rolls of campaign {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get rolls of campaign {id}")).into_response();
+        },
+    };
+    debug!("Returning {} roll(s) of campaign {id}", rolls.len());
+    (StatusCode::OK, Json(rolls)).into_response()
+}
+
+
+
+/// Handles posting a chat message in a campaign.
+///
+/// The message is also sent live to everyone connected to the campaign (see [`ws::handle()`](super::ws::handle())).
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to post in.
+/// - `body`: A [`PostMessageRequest`] with the message.
+///
+/// # Returns
+/// `201 CREATED` with the new [`ChatMessage`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid or the message was empty.
+///
+/// `404 NOT FOUND` if the campaign does not exist or the user is not a member of it.
+///
+/// `413 PAYLOAD TOO LARGE` if the message is longer than [`MESSAGE_MAX_LEN`] characters.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn post_message(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<PostMessageRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", POST_MESSAGE_PATH.method, POST_MESSAGE_PATH.path, client);
+
+    // Check the message
+    if body.body.is_empty() {
+        debug!("Message is empty, returning 400 BAD REQUEST");
+        return (StatusCode::BAD_REQUEST, "Message cannot be empty".to_string()).into_response();
+    }
+    if body.body.chars().count() > MESSAGE_MAX_LEN {
+        debug!("Message is too long, returning 413 PAYLOAD TOO LARGE");
+        return (StatusCode::PAYLOAD_TOO_LARGE, format!("Message cannot be longer than {MESSAGE_MAX_LEN} characters")).into_response();
+    }
+
+    // Check if the user may post
+    match state.db.is_member(id, user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to post message in campaign {id}")).into_response();
+        },
+    }
+
+    // Post it, then let the others know
+    let message: ChatMessage = match state.db.post_message(id, user.id, body.body.as_ref()).await {
+        Ok(message) => message,
+        Err(err) => {
+            error!("{}", trace!(("Failed to post message of user {} in campaign {id} to database", user.id), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to post message in campaign {id}")).into_response();
+        },
+    };
+    state.publish(id, Event::Chat { name: user.name.clone(), message: message.clone() });
+    (StatusCode::CREATED, Json(message)).into_response()
+}
+
+
+
+/// Handles reading back the chat messages posted in a campaign.
+///
+/// Only members of the campaign (i.e., its dungeon master and the players that joined it) can see them.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to retrieve the messages of.
+/// - `pagination`: A [`Pagination`] that determines which page of messages to return. Its cursor is the identifier of the oldest message
+///   of the previous page.
+///
+/// # Returns
+/// `200 OK` with a [`Page`] of [`ChatMessage`]s in the body, newest first.
+///
+/// `400 BAD REQUEST` if the given `query` was invalid.
+///
+/// `404 NOT FOUND` if the campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn messages(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    pagination: Pagination,
+) -> Response {
+    info!("Handling {} {} from '{}'", MESSAGES_PATH.method, MESSAGES_PATH.path, client);
+
+    // Check if the user may see them
+    match state.db.is_member(id, user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get messages of campaign {id}")).into_response();
+        },
+    }
+
+    // Get the messages
+    let messages: Vec<ChatMessage> = match state.db.list_messages(id, pagination.cursor, pagination.limit).await {
+        Ok(messages) => messages,
+        Err(err) => {
+            error!("{}", trace!(("Failed to get messages of campaign {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get messages of campaign {id}")).into_response();
+        },
+    };
+    debug!("Returning {} message(s) of campaign {id}", messages.len());
+    (StatusCode::OK, Json(Page::new(messages, &pagination, |message| message.id))).into_response()
+}
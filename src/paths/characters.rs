@@ -0,0 +1,845 @@
+//  CHARACTERS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:40:11
+//  Last edited:
+//    14 Oct 2026, 19:03:48
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for managing the characters that players play in
+//!   campaigns.
+//!   
+//!   Players may only edit their own characters, but the dungeon master of
+//!   a campaign may see all characters in it. Characters that a user may not
+//!   see are reported as `404 NOT FOUND`, so their existence isn't leaked.
+//!   
+//!   During play, both the owner and the dungeon master may spend and
+//!   restore a character's resources (e.g., spell slots) and change its
+//!   hit points.
+//
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Path as PathParam, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Role;
+use crate::database::{
+    validate_resources, AuditEventKind, Campaign, Character, CharacterOverview, CharacterStats, CharacterUpdate, Database, Error, Resource, Resources, UserInfo,
+};
+use crate::events::Event;
+use crate::middleware::auth::AuthUser;
+use crate::spec::{ApiError, Page, Pagination, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The maximum length (in characters) of character names.
+pub const NAME_MAX_LEN: usize = 64;
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the character creation endpoint can be found.
+pub const CREATE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/characters" };
+/// The reqwest-compatible path on which the character listing endpoint can be found.
+pub const LIST_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/characters" };
+/// The reqwest-compatible path on which the endpoint listing the logged-in user's own characters can be found.
+pub const OWN_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/me/characters" };
+/// The reqwest-compatible path on which the character retrieval endpoint can be found.
+pub const GET_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/characters/:id" };
+/// The reqwest-compatible path on which the character update endpoint can be found.
+pub const UPDATE_PATH: Path = Path { method: hyper::Method::PUT, path: "/v1/characters/:id" };
+/// The reqwest-compatible path on which the character deletion endpoint can be found.
+pub const DELETE_PATH: Path = Path { method: hyper::Method::DELETE, path: "/v1/characters/:id" };
+/// The reqwest-compatible path on which the character resource endpoint can be found.
+pub const RESOURCES_PATH: Path = Path { method: hyper::Method::PATCH, path: "/v1/characters/:id/resources" };
+/// The reqwest-compatible path on which the character hit point endpoint can be found.
+pub const HP_PATH: Path = Path { method: hyper::Method::PATCH, path: "/v1/characters/:id/hp" };
+
+
+/// The request's body as given by a user that wants to create a character.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "CreateCharacterRequest")]
+pub struct CreateRequest<'a> {
+    /// The identifier of the campaign in which the character plays.
+    pub campaign:  u64,
+    /// The name of the new character.
+    pub name:      Cow<'a, str>,
+    /// The stats of the new character.
+    pub stats:     CharacterStats,
+    /// The consumable resources (e.g., spell slots or hit dice) of the new character.
+    #[serde(default)]
+    pub resources: Resources,
+}
+
+/// The request's body as given by a user that wants to update their character.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "UpdateCharacterRequest")]
+pub struct UpdateRequest<'a> {
+    /// The new name of the character.
+    pub name:      Cow<'a, str>,
+    /// The new stats of the character.
+    pub stats:     CharacterStats,
+    /// The new consumable resources of the character. Leaves the current ones untouched if omitted.
+    #[serde(default)]
+    pub resources: Option<Resources>,
+}
+
+/// The request's body as given by a user that wants to spend or restore the resources of a character.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+#[schemars(rename = "CharacterResourcesRequest")]
+pub enum ResourcesRequest {
+    /// Spends some of a resource.
+    Use {
+        /// The name of the resource to spend.
+        name:   String,
+        /// How much of it to spend.
+        #[serde(default = "default_amount")]
+        amount: u32,
+    },
+    /// Restores some of a resource.
+    Restore {
+        /// The name of the resource to restore.
+        name:   String,
+        /// How much of it to restore. Restores it to its maximum if omitted.
+        #[serde(default)]
+        amount: Option<u32>,
+    },
+    /// Restores all resources to their maximum.
+    LongRest,
+}
+
+/// The request's body as given by a user that wants to damage or heal a character.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "CharacterHpRequest")]
+pub struct HpRequest {
+    /// The change in hit points. Negative values are damage, positive values are healing.
+    #[serde(default)]
+    pub delta: i64,
+    /// If given, replaces the character's temporary hit points before the `delta` is applied.
+    #[serde(default)]
+    pub temp:  Option<u32>,
+}
+
+/// The amount of a resource spent if the request doesn't say.
+#[inline]
+fn default_amount() -> u32 { 1 }
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Explains why a [`ResourcesRequest`] could not be applied to a character.
+#[derive(Debug)]
+enum ResourcesRejection {
+    /// The character has no resource with the given name.
+    NotFound { name: String },
+    /// The character has too little left of the resource.
+    Insufficient { name: String, current: u32, amount: u32 },
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Checks whether a user may see a character.
+///
+/// This is the case if they own it, are the dungeon master of its campaign, or are root.
+///
+/// # Arguments
+/// - `db`: The [`Database`] to look up the character's campaign in.
+/// - `user`: The [`UserInfo`] of the user to check.
+/// - `character`: The [`Character`] to check.
+///
+/// # Returns
+/// True if the `user` may see the `character`, or false otherwise.
+///
+/// # Errors
+/// This function errors if we failed to communicate with the database.
+async fn may_see(db: &Database, user: &UserInfo, character: &Character) -> Result<bool, Error> {
+    if character.owner == user.id || user.role >= Role::Root {
+        return Ok(true);
+    }
+    let campaign: Option<Campaign> = db.get_campaign(character.campaign).await?;
+    Ok(campaign.map(|campaign| campaign.dm == user.id).unwrap_or(false))
+}
+
+/// Checks whether a user may change a character during play (e.g., spend its resources).
+///
+/// This is the case if they own it or are the dungeon master of its campaign. Unlike [`may_see()`], being root is not enough.
+///
+/// # Arguments
+/// - `db`: The [`Database`] to look up the character's campaign in.
+/// - `user`: The [`UserInfo`] of the user to check.
+/// - `character`: The [`Character`] to check.
+///
+/// # Returns
+/// True if the `user` may change the `character`, or false otherwise.
+///
+/// # Errors
+/// This function errors if we failed to communicate with the database.
+async fn may_play(db: &Database, user: &UserInfo, character: &Character) -> Result<bool, Error> {
+    if character.owner == user.id {
+        return Ok(true);
+    }
+    let campaign: Option<Campaign> = db.get_campaign(character.campaign).await?;
+    Ok(campaign.map(|campaign| campaign.dm == user.id).unwrap_or(false))
+}
+
+/// Applies a [`ResourcesRequest`] to the resources of a character.
+///
+/// # Arguments
+/// - `resources`: The [`Resources`] to update in-place.
+/// - `request`: The [`ResourcesRequest`] to apply.
+///
+/// # Errors
+/// This function errors if the request names an unknown resource or would spend more of it than is left. In that case, the `resources`
+/// are left untouched.
+fn apply_resources(resources: &mut Resources, request: ResourcesRequest) -> Result<(), ResourcesRejection> {
+    match request {
+        ResourcesRequest::Use { name, amount } => {
+            let resource: &mut Resource = match resources.get_mut(&name) {
+                Some(resource) => resource,
+                None => return Err(ResourcesRejection::NotFound { name }),
+            };
+            if resource.current < amount {
+                return Err(ResourcesRejection::Insufficient { current: resource.current, name, amount });
+            }
+            resource.current -= amount;
+        },
+        ResourcesRequest::Restore { name, amount } => {
+            let resource: &mut Resource = match resources.get_mut(&name) {
+                Some(resource) => resource,
+                None => return Err(ResourcesRejection::NotFound { name }),
+            };
+            resource.current = match amount {
+                Some(amount) => resource.current.saturating_add(amount).min(resource.max),
+                None => resource.max,
+            };
+        },
+        ResourcesRequest::LongRest => {
+            for resource in resources.values_mut() {
+                resource.current = resource.max;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Checks whether a name, stats and resources are valid for a character.
+///
+/// # Arguments
+/// - `name`: The name of the character.
+/// - `stats`: The [`CharacterStats`] of the character.
+/// - `resources`: The [`Resources`] of the character.
+///
+/// # Returns
+/// [`None`] if they are, or else a `400 BAD REQUEST` [`Response`] explaining why not.
+fn validate(name: &str, stats: &CharacterStats, resources: &Resources) -> Option<Response> {
+    if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+        debug!("Character name '{name}' is empty or too long, returning 400 BAD REQUEST");
+        return Some((StatusCode::BAD_REQUEST, format!("Character name must be between 1 and {NAME_MAX_LEN} characters")).into_response());
+    }
+    if let Err(err) = stats.validate() {
+        debug!("{}", trace!(("Character '{name}' has invalid stats, returning 400 BAD REQUEST"), err));
+        return Some((StatusCode::BAD_REQUEST, err.to_string()).into_response());
+    }
+    if let Err(err) = validate_resources(resources) {
+        debug!("{}", trace!(("Character '{name}' has invalid resources, returning 400 BAD REQUEST"), err));
+        return Some((StatusCode::BAD_REQUEST, err.to_string()).into_response());
+    }
+    None
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles creating new characters.
+///
+/// The user creating the character becomes its owner. They must be a member of the campaign the character plays in.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `body`: A [`CreateRequest`] that describes the new character.
+///
+/// # Returns
+/// `201 CREATED` with the new [`Character`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid, the name was empty or too long, any of the ability scores was out of range, or
+/// any of the resources was invalid.
+///
+/// `404 NOT FOUND` if the given campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn create(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<CreateRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", CREATE_PATH.method, CREATE_PATH.path, client);
+
+    // Check the input
+    if let Some(res) = validate(&body.name, &body.stats, &body.resources) {
+        return res;
+    }
+
+    // Check if the user may play in the campaign
+    match state.db.is_member(body.campaign, user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("User {} is not a member of campaign {}, returning 404 NOT FOUND", user.id, body.campaign);
+            return (StatusCode::NOT_FOUND, format!("Campaign {} not found", body.campaign)).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {}", user.id, body.campaign), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create character '{}'", body.name)).into_response();
+        },
+    }
+
+    // Create it
+    match state.db.create_character(user.id, body.campaign, body.name.as_ref(), &body.stats, &body.resources).await {
+        Ok(Some(character)) => {
+            debug!("Created character {} '{}' for user {} in campaign {}", character.id, character.name, user.id, character.campaign);
+            (StatusCode::CREATED, Json(character)).into_response()
+        },
+        Ok(None) => {
+            debug!("Campaign {} not found, returning 404 NOT FOUND", body.campaign);
+            (StatusCode::NOT_FOUND, format!("Campaign {} not found", body.campaign)).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to create character '{}' in database", body.name), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create character '{}'", body.name)).into_response()
+        },
+    }
+}
+
+
+
+/// Handles listing the characters visible to the logged-in user.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`Character`]s that the user owns or that play in campaigns the user is the dungeon master of.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn list(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, AuthUser(user): AuthUser) -> Response {
+    info!("Handling {} {} from '{}'", LIST_PATH.method, LIST_PATH.path, client);
+
+    match state.db.list_characters_for_user(user.id).await {
+        Ok(characters) => {
+            debug!("Returning {} character(s) visible to user {}", characters.len(), user.id);
+            (StatusCode::OK, Json(characters)).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to list characters of user {} in database", user.id), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to list characters".to_string()).into_response()
+        },
+    }
+}
+
+
+
+/// Handles listing the characters that the logged-in user owns, across all their campaigns.
+///
+/// Characters are ordered by when they were last changed, which is why the `cursor` of the pagination is an offset rather than an
+/// identifier.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `pagination`: The [`Pagination`] determining which page of characters to return.
+///
+/// # Returns
+/// `200 OK` with a [`Page`] of [`CharacterOverview`]s in the body, most recently changed first.
+///
+/// `400 BAD REQUEST` if the given query was invalid.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn own(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, AuthUser(user): AuthUser, pagination: Pagination) -> Response {
+    info!("Handling {} {} from '{}'", OWN_PATH.method, OWN_PATH.path, client);
+
+    let offset: u64 = pagination.cursor.unwrap_or(0);
+    match state.db.list_own_characters(user.id, offset, pagination.limit).await {
+        Ok(characters) => {
+            debug!("Returning {} character(s) owned by user {}", characters.len(), user.id);
+            let next: u64 = offset + characters.len() as u64;
+            (StatusCode::OK, Json(Page::new(characters, &pagination, |_| next))).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to list characters owned by user {} in database", user.id), err));
+            ApiError::internal("Failed to list characters").into_response()
+        },
+    }
+}
+
+
+
+/// Handles retrieving a single character.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the character to retrieve.
+///
+/// # Returns
+/// `200 OK` with the [`Character`] in the body.
+///
+/// `404 NOT FOUND` if the character does not exist or the user is not allowed to see it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn get(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", GET_PATH.method, GET_PATH.path, client);
+
+    // Get the character
+    let character: Character = match state.db.get_character(id).await {
+        Ok(Some(character)) => character,
+        Ok(None) => {
+            debug!("Character {id} not found, returning 404 NOT FOUND");
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get character {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get character {id}")).into_response();
+        },
+    };
+
+    // Check if the user may see it
+    match may_see(&state.db, &user, &character).await {
+        Ok(true) => (StatusCode::OK, Json(character)).into_response(),
+        Ok(false) => {
+            debug!("Character {id} not visible to user {}, returning 404 NOT FOUND", user.id);
+            (StatusCode::NOT_FOUND, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} may see character {id}", user.id), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get character {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles updating a character's name, stats and resources.
+///
+/// Only the owner of a character may update it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the character to update.
+/// - `body`: An [`UpdateRequest`] that describes the new name, stats and (optionally) resources.
+///
+/// # Returns
+/// `200 OK` with the updated [`Character`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid, the name was empty or too long, any of the ability scores was out of range, or
+/// any of the resources was invalid.
+///
+/// `403 FORBIDDEN` if the user may see the character but does not own it.
+///
+/// `404 NOT FOUND` if the character does not exist or the user is not allowed to see it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn update(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<UpdateRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", UPDATE_PATH.method, UPDATE_PATH.path, client);
+
+    // Get the character
+    let character: Character = match state.db.get_character(id).await {
+        Ok(Some(character)) => character,
+        Ok(None) => {
+            debug!("Character {id} not found, returning 404 NOT FOUND");
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get character {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update character {id}")).into_response();
+        },
+    };
+
+    // Check if the user may edit it
+    if character.owner != user.id {
+        match may_see(&state.db, &user, &character).await {
+            Ok(true) => {
+                debug!("User {} does not own character {id}, returning 403 FORBIDDEN", user.id);
+                return (StatusCode::FORBIDDEN, String::new()).into_response();
+            },
+            Ok(false) => {
+                debug!("Character {id} not visible to user {}, returning 404 NOT FOUND", user.id);
+                return (StatusCode::NOT_FOUND, String::new()).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} may see character {id}", user.id), err));
+                return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update character {id}")).into_response();
+            },
+        }
+    }
+
+    // Check the input
+    let resources: Resources = body.resources.unwrap_or_else(|| character.resources.clone());
+    if let Some(res) = validate(&body.name, &body.stats, &resources) {
+        return res;
+    }
+
+    // Update it
+    match state.db.update_character(id, body.name.as_ref(), &body.stats, &resources).await {
+        Ok(true) => (StatusCode::OK, Json(Character { name: body.name.into_owned(), stats: body.stats, resources, ..character })).into_response(),
+        Ok(false) => {
+            debug!("Character {id} was deleted concurrently, returning 404 NOT FOUND");
+            (StatusCode::NOT_FOUND, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to update character {id} in database"), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update character {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles deleting a character.
+///
+/// Only the owner of a character may delete it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the character to delete.
+///
+/// # Returns
+/// `200 OK` if the character was deleted.
+///
+/// `403 FORBIDDEN` if the user may see the character but does not own it.
+///
+/// `404 NOT FOUND` if the character does not exist or the user is not allowed to see it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn delete(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", DELETE_PATH.method, DELETE_PATH.path, client);
+
+    // Get the character
+    let character: Character = match state.db.get_character(id).await {
+        Ok(Some(character)) => character,
+        Ok(None) => {
+            debug!("Character {id} not found, returning 404 NOT FOUND");
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get character {id} from database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete character {id}")).into_response();
+        },
+    };
+
+    // Check if the user may delete it
+    if character.owner != user.id {
+        match may_see(&state.db, &user, &character).await {
+            Ok(true) => {
+                debug!("User {} does not own character {id}, returning 403 FORBIDDEN", user.id);
+                return (StatusCode::FORBIDDEN, String::new()).into_response();
+            },
+            Ok(false) => {
+                debug!("Character {id} not visible to user {}, returning 404 NOT FOUND", user.id);
+                return (StatusCode::NOT_FOUND, String::new()).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} may see character {id}", user.id), err));
+                return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete character {id}")).into_response();
+            },
+        }
+    }
+
+    // Delete it
+    match state.db.delete_character(id).await {
+        Ok(true) => (StatusCode::OK, String::new()).into_response(),
+        Ok(false) => {
+            debug!("Character {id} was deleted concurrently, returning 404 NOT FOUND");
+            (StatusCode::NOT_FOUND, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to delete character {id} from database"), err));
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete character {id}")).into_response()
+        },
+    }
+}
+
+
+
+
+/// Handles spending and restoring the resources (e.g., spell slots) of a character.
+///
+/// Both the owner of a character and the dungeon master of its campaign may do so. The change is applied atomically, and pushed to
+/// everyone connected to the campaign as an [`Event::Resources`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the character to change.
+/// - `body`: A [`ResourcesRequest`] that describes what to spend or restore.
+///
+/// # Returns
+/// `200 OK` with the updated [`Character`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_character_player` if the user may see the character but neither owns it nor is the
+/// dungeon master of its campaign.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `character_not_found` if the character does not exist or the user is not allowed to see
+/// it, or of kind `resource_not_found` if the character has no resource with the given name.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `insufficient_resource` if the character has too little left of the resource.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn resources(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<ResourcesRequest>,
+) -> Response {
+    info!("Handling {} {} from '{}'", RESOURCES_PATH.method, RESOURCES_PATH.path, client);
+
+    // Get the character
+    let character: Character = match state.db.get_character(id).await {
+        Ok(Some(character)) => character,
+        Ok(None) => {
+            debug!("Character {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get character {id} from database"), err));
+            return ApiError::internal(format!("Failed to update resources of character {id}")).into_response();
+        },
+    };
+
+    // Check if the user may change it
+    match may_play(&state.db, &user, &character).await {
+        Ok(true) => {},
+        Ok(false) if user.role >= Role::Root => {
+            debug!("User {} neither owns character {id} nor runs its campaign, returning 403 FORBIDDEN", user.id);
+            return ApiError::new(
+                StatusCode::FORBIDDEN,
+                "not_character_player",
+                format!("Only the owner of character {id} and the dungeon master of its campaign may do this"),
+            )
+            .into_response();
+        },
+        Ok(false) => {
+            debug!("Character {id} not visible to user {}, returning 404 NOT FOUND", user.id);
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} may change character {id}", user.id), err));
+            return ApiError::internal(format!("Failed to update resources of character {id}")).into_response();
+        },
+    }
+
+    // Apply the change
+    let character: Character = match state.db.modify_character(id, move |character| apply_resources(&mut character.resources, body)).await {
+        Ok(CharacterUpdate::Updated(character)) => character,
+        Ok(CharacterUpdate::NotFound) => {
+            debug!("Character {id} was deleted concurrently, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Ok(CharacterUpdate::Rejected(ResourcesRejection::NotFound { name })) => {
+            debug!("Character {id} has no resource '{name}', returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "resource_not_found", format!("Character {id} has no resource '{name}'")).into_response();
+        },
+        Ok(CharacterUpdate::Rejected(ResourcesRejection::Insufficient { name, current, amount })) => {
+            debug!("Character {id} has {current} of resource '{name}' left, cannot use {amount}; returning 409 CONFLICT");
+            return ApiError::new(
+                StatusCode::CONFLICT,
+                "insufficient_resource",
+                format!("Character {id} has only {current} of resource '{name}' left, cannot use {amount}"),
+            )
+            .into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to update resources of character {id} in database"), err));
+            return ApiError::internal(format!("Failed to update resources of character {id}")).into_response();
+        },
+    };
+    state.publish(character.campaign, Event::Resources { character: id, resources: character.resources.clone() });
+    (StatusCode::OK, Json(character)).into_response()
+}
+
+
+
+
+/// Handles damaging and healing a character.
+///
+/// Both the owner of a character and the dungeon master of its campaign may do so. Damage is absorbed by temporary hit points first,
+/// after which the current hit points are clamped to between 0 and the maximum. A character that reaches 0 is marked as downed. The
+/// change is applied atomically, recorded in the audit log and pushed to everyone connected to the campaign as an [`Event::Hp`].
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the character to change.
+/// - `body`: An [`HpRequest`] that describes the change.
+///
+/// # Returns
+/// `200 OK` with the updated [`Character`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_character_player` if the user may see the character but neither owns it nor is the
+/// dungeon master of its campaign.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `character_not_found` if the character does not exist or the user is not allowed to see
+/// it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn hp(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<HpRequest>,
+) -> Response {
+    info!("Handling {} {} from '{}'", HP_PATH.method, HP_PATH.path, client);
+
+    // Get the character
+    let character: Character = match state.db.get_character(id).await {
+        Ok(Some(character)) => character,
+        Ok(None) => {
+            debug!("Character {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get character {id} from database"), err));
+            return ApiError::internal(format!("Failed to update hit points of character {id}")).into_response();
+        },
+    };
+
+    // Check if the user may change it
+    match may_play(&state.db, &user, &character).await {
+        Ok(true) => {},
+        Ok(false) if user.role >= Role::Root => {
+            debug!("User {} neither owns character {id} nor runs its campaign, returning 403 FORBIDDEN", user.id);
+            return ApiError::new(
+                StatusCode::FORBIDDEN,
+                "not_character_player",
+                format!("Only the owner of character {id} and the dungeon master of its campaign may do this"),
+            )
+            .into_response();
+        },
+        Ok(false) => {
+            debug!("Character {id} not visible to user {}, returning 404 NOT FOUND", user.id);
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} may change character {id}", user.id), err));
+            return ApiError::internal(format!("Failed to update hit points of character {id}")).into_response();
+        },
+    }
+
+    // Apply the change
+    let character: Character = match state
+        .db
+        .modify_character(id, move |character| -> Result<(), Infallible> {
+            if let Some(temp) = body.temp {
+                character.stats.temp_hp = temp;
+            }
+            character.stats.apply_hp(body.delta);
+            Ok(())
+        })
+        .await
+    {
+        Ok(CharacterUpdate::Updated(character)) => character,
+        Ok(CharacterUpdate::NotFound) => {
+            debug!("Character {id} was deleted concurrently, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "character_not_found", format!("Character {id} not found")).into_response();
+        },
+        Ok(CharacterUpdate::Rejected(never)) => match never {},
+        Err(err) => {
+            error!("{}", trace!(("Failed to update hit points of character {id} in database"), err));
+            return ApiError::internal(format!("Failed to update hit points of character {id}")).into_response();
+        },
+    };
+    info!(
+        "User {} changed hit points of character {id} by {} (now {}/{} + {} temporary{})",
+        user.id,
+        body.delta,
+        character.stats.hp_left(),
+        character.stats.hp,
+        character.stats.temp_hp,
+        if character.stats.downed { ", downed" } else { "" }
+    );
+    if let Err(err) = state.db.log_event(AuditEventKind::HpChanged, Some(user.id), client.ip()).await {
+        error!("{}", trace!(("Failed to log audit event"), err));
+    }
+    state.publish(character.campaign, Event::Hp {
+        character: id,
+        delta:     body.delta,
+        current:   character.stats.hp_left(),
+        temp:      character.stats.temp_hp,
+        max:       character.stats.hp,
+        downed:    character.stats.downed,
+    });
+    (StatusCode::OK, Json(character)).into_response()
+}
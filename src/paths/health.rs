@@ -0,0 +1,86 @@
+//  HEALTH.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 20:58:42
+//  Last edited:
+//    14 Oct 2026, 17:38:52
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines unauthenticated health-check endpoints for use by
+//!   orchestrators (e.g., liveness and readiness probes).
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, State};
+use axum::response::Json;
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::spec::Path;
+use crate::state::ServerState;
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the liveness endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::GET, path: "/healthz" };
+/// The reqwest-compatible path on which the readiness endpoint can be found.
+pub const READY_PATH: Path = Path { method: hyper::Method::GET, path: "/readyz" };
+
+
+/// The response returned by the health endpoints.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct HealthResponse<'a> {
+    /// Either `ok` or `unavailable`.
+    pub status: &'a str,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles `GET /healthz` to report that the server is alive.
+///
+/// Since probes hit this often, this only logs at debug level.
+///
+/// # Arguments
+/// - `client`: The address of the client we're working with.
+///
+/// # Returns
+/// `200 OK` with a [`HealthResponse`] in the body.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(ConnectInfo(client): ConnectInfo<SocketAddr>) -> (StatusCode, Json<HealthResponse<'static>>) {
+    debug!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+    (StatusCode::OK, Json(HealthResponse { status: "ok" }))
+}
+
+/// Handles `GET /readyz` to report whether the server can serve requests, i.e., whether it can reach its database.
+///
+/// Since probes hit this often, this only logs at debug level.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+///
+/// # Returns
+/// `200 OK` with a [`HealthResponse`] in the body.
+///
+/// `503 SERVICE UNAVAILABLE` with a [`HealthResponse`] in the body if the database could not be reached.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn ready(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>) -> (StatusCode, Json<HealthResponse<'static>>) {
+    debug!("Handling {} {} from '{}'", READY_PATH.method, READY_PATH.path, client);
+    match state.db.ping().await {
+        Ok(_) => (StatusCode::OK, Json(HealthResponse { status: "ok" })),
+        Err(err) => {
+            error!("{}", trace!(("Failed to reach database"), err));
+            (StatusCode::SERVICE_UNAVAILABLE, Json(HealthResponse { status: "unavailable" }))
+        },
+    }
+}
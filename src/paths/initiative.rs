@@ -0,0 +1,350 @@
+//  INITIATIVE.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:52:33
+//  Last edited:
+//    14 Oct 2026, 18:59:34
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for tracking the turn order of combat in a campaign.
+//!   
+//!   All changes are sent live to everyone connected to the campaign (see
+//!   [`ws::handle()`](super::ws::handle())).
+//
+
+use std::borrow::Cow;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Path as PathParam, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Role;
+use crate::database::{InitiativeEntry, UserInfo};
+use crate::events::Event;
+use crate::middleware::auth::AuthUser;
+use crate::spec::{ApiError, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The maximum length (in characters) of the names of entities in the turn order.
+pub const NAME_MAX_LEN: usize = 64;
+/// The maximum number of entities in the turn order.
+pub const ENTRIES_MAX: usize = 64;
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the turn order retrieval endpoint can be found.
+pub const GET_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/initiative" };
+/// The reqwest-compatible path on which the turn order setting endpoint can be found.
+pub const SET_PATH: Path = Path { method: hyper::Method::PUT, path: "/v1/campaigns/:id/initiative" };
+/// The reqwest-compatible path on which the turn advancing endpoint can be found.
+pub const NEXT_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/campaigns/:id/initiative/next" };
+/// The reqwest-compatible path on which the turn order clearing endpoint can be found.
+pub const CLEAR_PATH: Path = Path { method: hyper::Method::DELETE, path: "/v1/campaigns/:id/initiative" };
+
+
+/// The request's body as given by a dungeon master that wants to start combat.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "SetInitiativeRequest")]
+pub struct SetRequest<'a> {
+    /// The entities in the combat. Will be ordered by their initiative, highest first; ties keep the order in which they are given.
+    pub entries: Vec<SetEntry<'a>>,
+}
+
+/// A single entity in a [`SetRequest`].
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "SetInitiativeEntry")]
+pub struct SetEntry<'a> {
+    /// The name of the entity.
+    pub name:  Cow<'a, str>,
+    /// The initiative the entity rolled.
+    pub value: i32,
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Checks whether a user may change the turn order of a campaign, i.e., is its dungeon master or root.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] to look up the campaign in.
+/// - `user`: The [`UserInfo`] of the user to check.
+/// - `id`: The identifier of the campaign to check.
+/// - `action`: What the user was trying to do, for in the error message.
+///
+/// # Returns
+/// [`None`] if the user may, or else the [`Response`] to send back (`403 FORBIDDEN`, `404 NOT FOUND` or `500 INTERNAL SERVER ERROR`
+/// with an [`ApiError`] of kind `not_campaign_dm`, `campaign_not_found` or `internal`, respectively).
+async fn check_dm(state: &ServerState, user: &UserInfo, id: u64, action: &str) -> Option<Response> {
+    match state.db.get_campaign(id).await {
+        Ok(Some(campaign)) => {
+            if campaign.dm != user.id && user.role < Role::Root {
+                debug!("User {} is not the DM of campaign {id}, returning 403 FORBIDDEN", user.id);
+                return Some(
+                    ApiError::new(StatusCode::FORBIDDEN, "not_campaign_dm", format!("Only the dungeon master of campaign {id} may {action}")).into_response(),
+                );
+            }
+            None
+        },
+        Ok(None) => {
+            debug!("Campaign {id} not found, returning 404 NOT FOUND");
+            Some(ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response())
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to get campaign {id} from database"), err));
+            Some(ApiError::internal(format!("Failed to {action} of campaign {id}")).into_response())
+        },
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles reading the turn order of a campaign's combat.
+///
+/// Only members of the campaign (i.e., its dungeon master and the players that joined it) and root can see it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to retrieve the turn order of.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`InitiativeEntry`]s in the body, in turn order. Is empty if the campaign is not in combat.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn get(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", GET_PATH.method, GET_PATH.path, client);
+
+    // Check if the user may see it
+    if user.role < Role::Root {
+        match state.db.is_member(id, user.id).await {
+            Ok(true) => {},
+            Ok(false) => {
+                debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+                return ApiError::new(StatusCode::NOT_FOUND, "campaign_not_found", format!("Campaign {id} not found")).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+                return ApiError::internal(format!("Failed to get initiative of campaign {id}")).into_response();
+            },
+        }
+    }
+
+    // Get it
+    match state.db.get_initiative(id).await {
+        Ok(entries) => (StatusCode::OK, Json(entries)).into_response(),
+        Err(err) => {
+            error!("{}", trace!(("Failed to get initiative of campaign {id} from database"), err));
+            ApiError::internal(format!("Failed to get initiative of campaign {id}")).into_response()
+        },
+    }
+}
+
+
+
+/// Handles setting the turn order of a campaign's combat, replacing any existing one.
+///
+/// The entity with the highest initiative gets the first turn. Only the campaign's dungeon master and root can set it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to set the turn order of.
+/// - `body`: A [`SetRequest`] with the entities in the combat.
+///
+/// # Returns
+/// `200 OK` with a JSON array of the new [`InitiativeEntry`]s in the body, in turn order.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid, or with an [`ApiError`] of kind `invalid_initiative` if it had no or more than
+/// [`ENTRIES_MAX`] entities or any name was empty or too long.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to change the turn order.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn set(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<SetRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", SET_PATH.method, SET_PATH.path, client);
+
+    // Check the input
+    if body.entries.is_empty() || body.entries.len() > ENTRIES_MAX {
+        debug!("Given {} initiative entries, returning 400 BAD REQUEST", body.entries.len());
+        return ApiError::new(StatusCode::BAD_REQUEST, "invalid_initiative", format!("Initiative must have between 1 and {ENTRIES_MAX} entries"))
+            .into_response();
+    }
+    if let Some(entry) = body.entries.iter().find(|entry| entry.name.is_empty() || entry.name.chars().count() > NAME_MAX_LEN) {
+        debug!("Initiative entry name '{}' is empty or too long, returning 400 BAD REQUEST", entry.name);
+        return ApiError::new(StatusCode::BAD_REQUEST, "invalid_initiative", format!("Initiative entry names must be between 1 and {NAME_MAX_LEN} characters"))
+            .into_response();
+    }
+
+    // Check if the user may set it
+    if let Some(res) = check_dm(&state, &user, id, "set initiative").await {
+        return res;
+    }
+
+    // Put the entities in order
+    // NOTE: `sort_by()` is stable, so ties keep the order given by the DM
+    let mut entries: Vec<InitiativeEntry> = body
+        .entries
+        .into_iter()
+        .map(|entry| InitiativeEntry { name: entry.name.into_owned(), value: entry.value, current: false })
+        .collect();
+    entries.sort_by(|lhs, rhs| rhs.value.cmp(&lhs.value));
+    entries[0].current = true;
+
+    // Store them, then let the others know
+    if let Err(err) = state.db.set_initiative(id, &entries).await {
+        error!("{}", trace!(("Failed to set initiative of campaign {id} in database"), err));
+        return ApiError::internal(format!("Failed to set initiative of campaign {id}")).into_response();
+    }
+    debug!("Set initiative of campaign {id} ({} entries)", entries.len());
+    state.publish(id, Event::Initiative { entries: entries.clone() });
+    (StatusCode::OK, Json(entries)).into_response()
+}
+
+
+
+/// Handles advancing the turn order of a campaign's combat to the next entity.
+///
+/// After the last entity, the turn wraps around to the first. Only the campaign's dungeon master and root can advance it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to advance the turn order of.
+///
+/// # Returns
+/// `200 OK` with a JSON array of the updated [`InitiativeEntry`]s in the body, in turn order.
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to change the turn order.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `not_in_combat` if the campaign is not in combat.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn next(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", NEXT_PATH.method, NEXT_PATH.path, client);
+
+    // Check if the user may advance it
+    if let Some(res) = check_dm(&state, &user, id, "advance initiative").await {
+        return res;
+    }
+
+    // Advance it, then let the others know
+    let entries: Vec<InitiativeEntry> = match state.db.advance_initiative(id).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("{}", trace!(("Failed to advance initiative of campaign {id} in database"), err));
+            return ApiError::internal(format!("Failed to advance initiative of campaign {id}")).into_response();
+        },
+    };
+    if entries.is_empty() {
+        debug!("Campaign {id} is not in combat, returning 409 CONFLICT");
+        return ApiError::new(StatusCode::CONFLICT, "not_in_combat", format!("Campaign {id} is not in combat")).into_response();
+    }
+    state.publish(id, Event::Initiative { entries: entries.clone() });
+    (StatusCode::OK, Json(entries)).into_response()
+}
+
+
+
+/// Handles clearing the turn order of a campaign's combat, i.e., ending it.
+///
+/// Only the campaign's dungeon master and root can clear it.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to clear the turn order of.
+///
+/// # Returns
+/// `200 OK` if the turn order was cleared (or the campaign was not in combat to begin with).
+///
+/// `403 FORBIDDEN` with an [`ApiError`] of kind `not_campaign_dm` if the user is not allowed to change the turn order.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `campaign_not_found` if the campaign does not exist.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR` and an [`ApiError`] of kind `internal`) if we fail to contact the backend
+/// database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn clear(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", CLEAR_PATH.method, CLEAR_PATH.path, client);
+
+    // Check if the user may clear it
+    if let Some(res) = check_dm(&state, &user, id, "clear initiative").await {
+        return res;
+    }
+
+    // Clear it, then let the others know
+    match state.db.clear_initiative(id).await {
+        Ok(true) => {
+            debug!("Cleared initiative of campaign {id}");
+            state.publish(id, Event::Initiative { entries: Vec::new() });
+            (StatusCode::OK, String::new()).into_response()
+        },
+        Ok(false) => {
+            debug!("Campaign {id} was not in combat");
+            (StatusCode::OK, String::new()).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to clear initiative of campaign {id} in database"), err));
+            ApiError::internal(format!("Failed to clear initiative of campaign {id}")).into_response()
+        },
+    }
+}
@@ -0,0 +1,52 @@
+//  METRICS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 21:26:50
+//  Last edited:
+//    14 Oct 2026, 17:32:20
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the endpoint that exposes the server's metrics in the
+//!   Prometheus text format.
+//!   
+//!   Note that this endpoint is unauthenticated, so it should be firewalled
+//!   off from the public internet in production.
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, State};
+use hyper::StatusCode;
+use log::debug;
+use metrics_exporter_prometheus::PrometheusHandle;
+
+use crate::spec::Path;
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the metrics endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::GET, path: "/metrics" };
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles `GET /metrics` to render all recorded metrics for Prometheus to scrape.
+///
+/// Since scrapers hit this often, this only logs at debug level.
+///
+/// # Arguments
+/// - `handle`: The [`PrometheusHandle`] of the installed metrics recorder.
+/// - `client`: The address of the client we're working with.
+///
+/// # Returns
+/// `200 OK` with the metrics in the Prometheus text format in the body.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(State(handle): State<PrometheusHandle>, ConnectInfo(client): ConnectInfo<SocketAddr>) -> (StatusCode, String) {
+    debug!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+    (StatusCode::OK, handle.render())
+}
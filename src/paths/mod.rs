@@ -4,7 +4,7 @@
 //  Created:
 //    08 Apr 2024, 11:44:19
 //  Last edited:
-//    09 Apr 2024, 12:18:35
+//    14 Oct 2026, 17:59:49
 //  Auto updated?
 //    Yes
 //
@@ -13,5 +13,16 @@
 //
 
 // Define the submodules defining the paths
+pub mod admin;
+pub mod audit;
 pub mod auth;
+pub mod campaigns;
+pub mod characters;
+pub mod health;
+pub mod initiative;
+pub mod metrics;
+pub mod openapi;
+pub mod roll;
+pub mod users;
 pub mod version;
+pub mod ws;
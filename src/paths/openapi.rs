@@ -0,0 +1,564 @@
+//  OPENAPI.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:40:00
+//  Last edited:
+//    14 Oct 2026, 18:53:33
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines an endpoint that serves an OpenAPI 3 document describing the
+//!   API of the server.
+//!   
+//!   The document is generated from the [`ENDPOINTS`] registry, which
+//!   collects the [`Path`] of every endpoint together with the serde types
+//!   that it accepts and returns.
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, State};
+use axum::response::Json;
+use axum::Extension;
+use hyper::StatusCode;
+use log::info;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Map, Value};
+
+use crate::auth::LOGIN_TOKEN_NAME;
+use crate::database::{AuditEvent, Campaign, CampaignMember, Character, CharacterOverview, ChatMessage, ImportSummary, InitiativeEntry, Roll, UserSummary};
+use crate::dice::RollResult;
+use crate::middleware::request_id::RequestId;
+use crate::paths::{admin, audit, auth, campaigns, characters, health, initiative, roll, users, version, ws};
+use crate::spec::{schema, Endpoint, Page, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The name of the security scheme that represents being logged-in.
+const SECURITY_SCHEME: &'static str = "login_token";
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the OpenAPI endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::GET, path: "/openapi.json" };
+
+
+/// The registry of all documented endpoints of the API.
+///
+/// Be sure to add new endpoints to this list when mounting them.
+pub const ENDPOINTS: &[Endpoint] = &[
+    // Probes
+    Endpoint {
+        path:     &health::PATH,
+        summary:  "Reports that the server is alive",
+        auth:     false,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<health::HealthResponse>),
+    },
+    Endpoint {
+        path:     &health::READY_PATH,
+        summary:  "Reports whether the server can reach its database",
+        auth:     false,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<health::HealthResponse>),
+    },
+    Endpoint {
+        path:     &version::PATH,
+        summary:  "Returns the name and version of the server",
+        auth:     false,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<version::VersionResponse>),
+    },
+    // Auth
+    Endpoint {
+        path:     &auth::LOGIN_PATH,
+        summary:  "Logs a user in, setting the login cookie",
+        auth:     false,
+        request:  Some(schema::<auth::LoginRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::LOGOUT_PATH,
+        summary:  "Logs a user out, revoking the login cookie",
+        auth:     false,
+        request:  None,
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::REFRESH_PATH,
+        summary:  "Exchanges a valid login cookie for a fresh one",
+        auth:     false,
+        request:  None,
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::REGISTER_PATH,
+        summary:  "Registers a new user",
+        auth:     false,
+        request:  Some(schema::<auth::RegisterRequest>),
+        status:   StatusCode::CREATED,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::TOTP_ENROLL_PATH,
+        summary:  "Starts enrolling the user in two-factor authentication, returning the otpauth URL of the pending secret",
+        auth:     true,
+        request:  Some(schema::<auth::TotpEnrollRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::TOTP_CONFIRM_PATH,
+        summary:  "Enables the pending two-factor authentication secret with a code generated for it",
+        auth:     true,
+        request:  Some(schema::<auth::TotpConfirmRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::RESET_REQUEST_PATH,
+        summary:  "Requests a password reset token for a user",
+        auth:     false,
+        request:  Some(schema::<auth::ResetRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::CHANGE_PASSWORD_PATH,
+        summary:  "Changes the password of the user, replacing the login cookie",
+        auth:     true,
+        request:  Some(schema::<auth::ChangePasswordRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &auth::RESET_PATH,
+        summary:  "Resets a user's password using a reset token",
+        auth:     false,
+        request:  Some(schema::<auth::ResetConfirmRequest>),
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &users::WHOAMI_PATH,
+        summary:  "Returns the logged-in user",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<UserSummary>),
+    },
+    Endpoint {
+        path:     &users::ROLES_PATH,
+        summary:  "Lists the roles that users can have",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<users::RoleInfo>>),
+    },
+    // Campaigns
+    Endpoint {
+        path:     &campaigns::CREATE_PATH,
+        summary:  "Creates a new campaign with the user as its dungeon master",
+        auth:     true,
+        request:  Some(schema::<campaigns::CreateRequest>),
+        status:   StatusCode::CREATED,
+        response: Some(schema::<Campaign>),
+    },
+    Endpoint {
+        path:     &campaigns::LIST_PATH,
+        summary:  "Lists the campaigns of the user",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<Campaign>>),
+    },
+    Endpoint {
+        path:     &campaigns::GET_PATH,
+        summary:  "Returns a single campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Campaign>),
+    },
+    Endpoint { path: &campaigns::DELETE_PATH, summary: "Deletes a campaign", auth: true, request: None, status: StatusCode::OK, response: None },
+    Endpoint {
+        path:     &campaigns::MEMBERS_PATH,
+        summary:  "Lists the players that joined a campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<CampaignMember>>),
+    },
+    Endpoint {
+        path:     &campaigns::ADD_MEMBER_PATH,
+        summary:  "Adds a player to a campaign",
+        auth:     true,
+        request:  Some(schema::<campaigns::AddMemberRequest>),
+        status:   StatusCode::CREATED,
+        response: None,
+    },
+    Endpoint {
+        path:     &campaigns::REMOVE_MEMBER_PATH,
+        summary:  "Removes a player from a campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &campaigns::INVITE_PATH,
+        summary:  "Returns the invite code of a campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<campaigns::InviteResponse>),
+    },
+    Endpoint {
+        path:     &campaigns::ROTATE_INVITE_PATH,
+        summary:  "Replaces the invite code of a campaign with a fresh one",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<campaigns::InviteResponse>),
+    },
+    Endpoint {
+        path:     &campaigns::JOIN_PATH,
+        summary:  "Joins a campaign using its invite code",
+        auth:     true,
+        request:  Some(schema::<campaigns::JoinRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<Campaign>),
+    },
+    Endpoint {
+        path:     &initiative::GET_PATH,
+        summary:  "Returns the turn order of a campaign's combat",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<InitiativeEntry>>),
+    },
+    Endpoint {
+        path:     &initiative::SET_PATH,
+        summary:  "Starts combat in a campaign with the given turn order",
+        auth:     true,
+        request:  Some(schema::<initiative::SetRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<InitiativeEntry>>),
+    },
+    Endpoint {
+        path:     &initiative::NEXT_PATH,
+        summary:  "Moves the turn to the next entity in a campaign's combat",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<InitiativeEntry>>),
+    },
+    Endpoint {
+        path:     &initiative::CLEAR_PATH,
+        summary:  "Ends combat in a campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &campaigns::POST_MESSAGE_PATH,
+        summary:  "Posts a chat message in a campaign",
+        auth:     true,
+        request:  Some(schema::<campaigns::PostMessageRequest>),
+        status:   StatusCode::CREATED,
+        response: Some(schema::<ChatMessage>),
+    },
+    Endpoint {
+        path:     &campaigns::MESSAGES_PATH,
+        summary:  "Lists the chat messages of a campaign, newest first",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Page<ChatMessage>>),
+    },
+    Endpoint {
+        path:     &campaigns::ROLLS_PATH,
+        summary:  "Lists the recent rolls made in a campaign",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<Roll>>),
+    },
+    Endpoint {
+        path:     &ws::PATH,
+        summary:  "Upgrades to a WebSocket on which the events of a campaign are streamed",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::SWITCHING_PROTOCOLS,
+        response: None,
+    },
+    // Characters
+    Endpoint {
+        path:     &characters::CREATE_PATH,
+        summary:  "Creates a new character for the user",
+        auth:     true,
+        request:  Some(schema::<characters::CreateRequest>),
+        status:   StatusCode::CREATED,
+        response: Some(schema::<Character>),
+    },
+    Endpoint {
+        path:     &characters::LIST_PATH,
+        summary:  "Lists the characters the user owns or runs",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<Character>>),
+    },
+    Endpoint {
+        path:     &characters::OWN_PATH,
+        summary:  "Lists the characters the user owns across all campaigns",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Page<CharacterOverview>>),
+    },
+    Endpoint {
+        path:     &characters::GET_PATH,
+        summary:  "Returns a single character",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Character>),
+    },
+    Endpoint {
+        path:     &characters::UPDATE_PATH,
+        summary:  "Updates a character of the user",
+        auth:     true,
+        request:  Some(schema::<characters::UpdateRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<Character>),
+    },
+    Endpoint {
+        path:     &characters::DELETE_PATH,
+        summary:  "Deletes a character of the user",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: None,
+    },
+    Endpoint {
+        path:     &characters::HP_PATH,
+        summary:  "Damages or heals a character",
+        auth:     true,
+        request:  Some(schema::<characters::HpRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<Character>),
+    },
+    Endpoint {
+        path:     &characters::RESOURCES_PATH,
+        summary:  "Spends or restores the resources of a character",
+        auth:     true,
+        request:  Some(schema::<characters::ResourcesRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<Character>),
+    },
+    // Dice
+    Endpoint {
+        path:     &roll::PATH,
+        summary:  "Rolls some dice",
+        auth:     true,
+        request:  Some(schema::<roll::RollRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<RollResult>),
+    },
+    // Administration
+    Endpoint {
+        path:     &admin::BACKUP_PATH,
+        summary:  "Backs up the database (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::CREATED,
+        response: Some(schema::<admin::BackupResponse>),
+    },
+    Endpoint {
+        path:     &admin::EXPORT_PATH,
+        summary:  "Exports all users as JSON or CSV (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<UserSummary>>),
+    },
+    Endpoint {
+        path:     &admin::IMPORT_PATH,
+        summary:  "Imports users with already-hashed passwords (root only)",
+        auth:     true,
+        request:  Some(schema::<Vec<admin::ImportUser>>),
+        status:   StatusCode::OK,
+        response: Some(schema::<ImportSummary>),
+    },
+    Endpoint {
+        path:     &admin::MAINTENANCE_PATH,
+        summary:  "Switches maintenance mode on or off (root only)",
+        auth:     true,
+        request:  Some(schema::<admin::MaintenanceRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<admin::MaintenanceResponse>),
+    },
+    Endpoint {
+        path:     &audit::PATH,
+        summary:  "Lists the audit log (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<AuditEvent>>),
+    },
+    Endpoint {
+        path:     &users::LIST_PATH,
+        summary:  "Lists all users (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<UserSummary>>),
+    },
+    Endpoint {
+        path:     &users::SEARCH_PATH,
+        summary:  "Searches users by name prefix (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<Vec<UserSummary>>),
+    },
+    Endpoint {
+        path:     &users::ROLE_PATH,
+        summary:  "Changes the role of a user (root only)",
+        auth:     true,
+        request:  Some(schema::<users::RoleRequest>),
+        status:   StatusCode::OK,
+        response: Some(schema::<UserSummary>),
+    },
+    Endpoint {
+        path:     &users::RESTORE_PATH,
+        summary:  "Restores a deleted user (root only)",
+        auth:     true,
+        request:  None,
+        status:   StatusCode::OK,
+        response: Some(schema::<UserSummary>),
+    },
+];
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Translates an axum path (e.g., `/v1/campaigns/:id`) to an OpenAPI path (e.g., `/v1/campaigns/{id}`).
+///
+/// # Arguments
+/// - `path`: The axum path to translate.
+///
+/// # Returns
+/// A tuple of the OpenAPI path and the names of its parameters.
+fn translate_path(path: &str) -> (String, Vec<&str>) {
+    let mut params: Vec<&str> = Vec::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => {
+                params.push(param);
+                format!("{{{param}}}")
+            },
+            None => segment.into(),
+        })
+        .collect();
+    (segments.join("/"), params)
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Generates the OpenAPI document of the server from [`ENDPOINTS`].
+///
+/// # Arguments
+/// - `name`: The name of the server to put in the document.
+/// - `version`: The version of the server to put in the document.
+///
+/// # Returns
+/// The OpenAPI 3 document, as JSON.
+pub fn document(name: &str, version: &str) -> Value {
+    let mut gen: SchemaGenerator = SchemaSettings::openapi3().into_generator();
+    let mut paths: Map<String, Value> = Map::new();
+    for endpoint in ENDPOINTS {
+        let (path, params): (String, Vec<&str>) = translate_path(endpoint.path.path);
+
+        // Describe the operation
+        let mut operation: Map<String, Value> = Map::new();
+        operation.insert("summary".into(), json!(endpoint.summary));
+        if !params.is_empty() {
+            // NOTE: All our path parameters are identifiers
+            let params: Vec<Value> = params
+                .into_iter()
+                .map(|param| json!({ "name": param, "in": "path", "required": true, "schema": { "type": "integer", "format": "uint64", "minimum": 0 } }))
+                .collect();
+            operation.insert("parameters".into(), Value::Array(params));
+        }
+        if let Some(request) = endpoint.request {
+            operation.insert("requestBody".into(), json!({ "required": true, "content": { "application/json": { "schema": request(&mut gen) } } }));
+        }
+        let mut response: Map<String, Value> = Map::new();
+        response.insert("description".into(), json!(endpoint.status.canonical_reason().unwrap_or("Success")));
+        if let Some(schema) = endpoint.response {
+            response.insert("content".into(), json!({ "application/json": { "schema": schema(&mut gen) } }));
+        }
+        operation.insert("responses".into(), json!({ endpoint.status.as_str(): response }));
+        if endpoint.auth {
+            operation.insert("security".into(), json!([{ SECURITY_SCHEME: [] }]));
+        }
+
+        // Add it to the path (which may have multiple methods)
+        if let Value::Object(methods) = paths.entry(path).or_insert_with(|| Value::Object(Map::new())) {
+            methods.insert(endpoint.path.method.as_str().to_lowercase(), Value::Object(operation));
+        }
+    }
+
+    // Wrap it in the document
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": name, "version": version },
+        "paths": paths,
+        "components": {
+            "schemas": gen.definitions(),
+            "securitySchemes": { SECURITY_SCHEME: { "type": "apiKey", "in": "cookie", "name": LOGIN_TOKEN_NAME } },
+        },
+    })
+}
+
+
+
+/// Handles `GET /openapi.json` to return a machine-readable description of the API.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+///
+/// # Returns
+/// `200 OK` with the OpenAPI 3 document in the body.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+) -> (StatusCode, Json<Value>) {
+    info!("[{rid}] Handling {} {} from '{}'", PATH.method, PATH.path, client);
+    (StatusCode::OK, Json(document(state.name, &state.version.to_string())))
+}
@@ -0,0 +1,136 @@
+//  ROLL.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 19:31:04
+//  Last edited:
+//    14 Oct 2026, 18:29:51
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the path that rolls dice for users server-side.
+//
+
+use std::borrow::Cow;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::dice::{DiceRoller, Mode, Notation, RollResult};
+use crate::events::Event;
+use crate::middleware::auth::AuthUser;
+use crate::spec::Path;
+use crate::state::ServerState;
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the roll endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::POST, path: "/v1/roll" };
+
+
+/// The request's body as given by a user that wants to roll.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct RollRequest<'a> {
+    /// The dice to roll, in standard notation (e.g., `1d20+2d4-1`).
+    pub notation: Cow<'a, str>,
+    /// If true, rolls every d20 with advantage (i.e., twice, keeping the highest).
+    #[serde(default)]
+    pub adv:      bool,
+    /// If true, rolls every d20 with disadvantage (i.e., twice, keeping the lowest).
+    #[serde(default)]
+    pub dis:      bool,
+    /// The campaign in which to roll, if any. The roll shows up in that campaign's history.
+    #[serde(default)]
+    pub campaign: Option<u64>,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles rolling dice for a user.
+///
+/// Every roll is recorded in the database. Rolls made in a campaign are also sent live to everyone connected to it (see
+/// [`ws::handle()`](super::ws::handle())).
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `body`: A [`RollRequest`] that describes what to roll.
+///
+/// # Returns
+/// `200 OK` with a [`RollResult`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid, the notation was malformed or rolled too many dice, or both `adv` and `dis` were
+/// given.
+///
+/// `404 NOT FOUND` if the given campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<RollRequest<'static>>,
+) -> Response {
+    info!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+
+    // Parse the input
+    let mode: Mode = match (body.adv, body.dis) {
+        (false, false) => Mode::Normal,
+        (true, false) => Mode::Advantage,
+        (false, true) => Mode::Disadvantage,
+        (true, true) => {
+            debug!("Both advantage and disadvantage given, returning 400 BAD REQUEST");
+            return (StatusCode::BAD_REQUEST, "Cannot roll with both advantage and disadvantage".to_string()).into_response();
+        },
+    };
+    let notation: Notation = match body.notation.parse() {
+        Ok(notation) => notation,
+        Err(err) => {
+            debug!("{}", trace!(("Invalid dice notation {:?}, returning 400 BAD REQUEST", body.notation), err));
+            return (StatusCode::BAD_REQUEST, err.to_string()).into_response();
+        },
+    };
+
+    if let Some(campaign) = body.campaign {
+        match state.db.is_member(campaign, user.id).await {
+            Ok(true) => {},
+            Ok(false) => {
+                debug!("User {} is not a member of campaign {campaign}, returning 404 NOT FOUND", user.id);
+                return (StatusCode::NOT_FOUND, format!("Campaign {campaign} not found")).into_response();
+            },
+            Err(err) => {
+                error!("{}", trace!(("Failed to check if user {} is a member of campaign {campaign}", user.id), err));
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to roll".to_string()).into_response();
+            },
+        }
+    }
+
+    // Roll!
+    let res: RollResult = DiceRoller::new().roll(&notation, mode);
+    debug!("Rolled {} for user {}: {}", res.notation, user.id, res.total);
+
+    // Record it
+    if let Err(err) = state.db.record_roll(user.id, body.campaign, &res).await {
+        error!("{}", trace!(("Failed to record roll of user {} in database", user.id), err));
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to roll".to_string()).into_response();
+    }
+
+    // Let the other players know
+    if let Some(campaign) = body.campaign {
+        state.publish(campaign, Event::Roll { user: user.id, name: user.name.clone(), result: res.clone() });
+    }
+    (StatusCode::OK, Json(res)).into_response()
+}
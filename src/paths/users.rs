@@ -0,0 +1,344 @@
+//  USERS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 17:52:40
+//  Last edited:
+//    14 Oct 2026, 18:33:32
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines paths for managing the users known to the server.
+//
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Path as PathParam, Query, State};
+use axum::response::{IntoResponse as _, Json, Response};
+use enum_debug::EnumDebug as _;
+use error_trace::trace;
+use hyper::header::HeaderName;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Role;
+use crate::database::{AuditEventKind, RoleUpdate, UserInfo, UserSummary};
+use crate::middleware::auth::AuthUser;
+use crate::spec::{ApiError, Path};
+use crate::state::ServerState;
+
+
+/***** CONSTANTS *****/
+/// The maximum number of users that can be listed in one go.
+pub const LIST_LIMIT_MAX: u64 = 100;
+
+/// The maximum number of users that a search returns.
+pub const SEARCH_LIMIT_MAX: u64 = 25;
+
+/// The name of the header that carries the total number of users when listing them.
+pub const TOTAL_COUNT_HEADER: &'static str = "x-total-count";
+
+
+
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the user listing endpoint can be found.
+pub const LIST_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/users" };
+/// The reqwest-compatible path on which the user search endpoint can be found.
+pub const SEARCH_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/users/search" };
+/// The reqwest-compatible path on which the endpoint returning the logged-in user can be found.
+pub const WHOAMI_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/whoami" };
+/// The reqwest-compatible path on which the role changing endpoint can be found.
+pub const ROLE_PATH: Path = Path { method: hyper::Method::PATCH, path: "/v1/users/:id/role" };
+/// The reqwest-compatible path on which the endpoint restoring deleted users can be found.
+pub const RESTORE_PATH: Path = Path { method: hyper::Method::POST, path: "/v1/users/:id/restore" };
+/// The reqwest-compatible path on which the endpoint listing the known roles can be found.
+pub const ROLES_PATH: Path = Path { method: hyper::Method::GET, path: "/v1/roles" };
+
+
+/// Describes a single role as returned by the role listing endpoint.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct RoleInfo {
+    /// The role itself, as it is given to other endpoints (e.g., `DungeonMaster`).
+    pub role:  Role,
+    /// The human-readable name of the role (e.g., `Dungeon Master`).
+    pub name:  String,
+    /// The numeric value of the role. Higher values are more powerful.
+    pub value: u8,
+}
+
+/// The query parameters given when listing users.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ListQuery {
+    /// The number of users to skip.
+    #[serde(default)]
+    pub offset:          u64,
+    /// The maximum number of users to return. Capped at [`LIST_LIMIT_MAX`].
+    #[serde(default = "ListQuery::default_limit")]
+    pub limit:           u64,
+    /// Whether to include users that were deleted.
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+impl ListQuery {
+    /// Returns the default limit if none is given.
+    #[inline]
+    fn default_limit() -> u64 { LIST_LIMIT_MAX }
+}
+
+/// The query parameters given when searching for users.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SearchQuery {
+    /// The prefix of the names of the users to find.
+    pub q:     String,
+    /// The maximum number of users to return. Capped at [`SEARCH_LIMIT_MAX`].
+    #[serde(default = "SearchQuery::default_limit")]
+    pub limit: u64,
+}
+impl SearchQuery {
+    /// Returns the default limit if none is given.
+    #[inline]
+    fn default_limit() -> u64 { SEARCH_LIMIT_MAX }
+}
+
+/// The request's body as given by the root when changing the role of a user.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct RoleRequest {
+    /// The new role of the user.
+    pub role: Role,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles listing the users known to the server.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `query`: A [`ListQuery`] that determines which page of users to return, and whether deleted users are included.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`UserSummary`]s in the body, ordered by identifier, and the total number of users in the
+/// [`TOTAL_COUNT_HEADER`] header.
+///
+/// `400 BAD REQUEST` if the given `query` was invalid.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn list(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, Query(query): Query<ListQuery>) -> Response {
+    info!("Handling {} {} from '{}'", LIST_PATH.method, LIST_PATH.path, client);
+
+    // Get the total first
+    let total: u64 = match state.db.count_users(query.include_deleted).await {
+        Ok(total) => total,
+        Err(err) => {
+            error!("{}", trace!(("Failed to count users in database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to count users in database".to_string()).into_response();
+        },
+    };
+
+    // Then get the page
+    let limit: u64 = query.limit.min(LIST_LIMIT_MAX);
+    let users: Vec<UserInfo> = match state.db.list_users(query.offset, limit, query.include_deleted).await {
+        Ok(users) => users,
+        Err(err) => {
+            error!("{}", trace!(("Failed to list users in database"), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to list users in database".to_string()).into_response();
+        },
+    };
+    debug!("Returning {} user(s) out of {total}", users.len());
+
+    // Return them without their passwords
+    let users: Vec<UserSummary> = users.into_iter().map(UserSummary::from).collect();
+    (StatusCode::OK, [(HeaderName::from_static(TOTAL_COUNT_HEADER), total.to_string())], Json(users)).into_response()
+}
+
+/// Handles searching for users by (a prefix of) their name.
+///
+/// The prefix is matched literally and case-insensitively. Deleted users are never found.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `query`: A [`SearchQuery`] with the prefix to search for.
+///
+/// # Returns
+/// `200 OK` with a JSON array of at most [`SEARCH_LIMIT_MAX`] [`UserSummary`]s in the body, ordered by name.
+///
+/// `400 BAD REQUEST` (with an [`ApiError`] of kind `empty_query` if the prefix is empty) if the given `query` was invalid.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn search(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>, Query(query): Query<SearchQuery>) -> Response {
+    info!("Handling {} {} from '{}'", SEARCH_PATH.method, SEARCH_PATH.path, client);
+
+    // Check the input
+    if query.q.is_empty() {
+        debug!("Empty search query, returning 400 BAD REQUEST");
+        return ApiError::new(StatusCode::BAD_REQUEST, "empty_query", "Search query must not be empty").into_response();
+    }
+
+    // Run the search
+    let users: Vec<UserInfo> = match state.db.search_users(&query.q, query.limit.min(SEARCH_LIMIT_MAX)).await {
+        Ok(users) => users,
+        Err(err) => {
+            error!("{}", trace!(("Failed to search users with prefix {:?} in database", query.q), err));
+            return ApiError::internal("Failed to search users in database").into_response();
+        },
+    };
+    debug!("Found {} user(s) with prefix {:?}", users.len(), query.q);
+
+    // Return them without their passwords
+    let users: Vec<UserSummary> = users.into_iter().map(UserSummary::from).collect();
+    (StatusCode::OK, Json(users)).into_response()
+}
+
+/// Handles changing the role of a user, e.g., to promote a player to a dungeon master.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`UserInfo`] of the (root) user doing the change, as injected by the auth middleware.
+/// - `id`: The identifier of the user to change the role of.
+/// - `body`: A [`RoleRequest`] with the new role.
+///
+/// # Returns
+/// `200 OK` with the updated [`UserSummary`] in the body.
+///
+/// `400 BAD REQUEST` if the given `body` was invalid.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `user_not_found` if there is no such user.
+///
+/// `409 CONFLICT` with an [`ApiError`] of kind `last_root` if this would demote the last remaining root.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn update_role(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    Json(body): Json<RoleRequest>,
+) -> Response {
+    info!("Handling {} {} from '{}'", ROLE_PATH.method, ROLE_PATH.path, client);
+
+    // Update the role
+    let target: UserInfo = match state.db.update_role(id, body.role).await {
+        Ok(RoleUpdate::Updated(target)) => target,
+        Ok(RoleUpdate::NotFound) => {
+            debug!("User {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "user_not_found", format!("No user with ID {id}")).into_response();
+        },
+        Ok(RoleUpdate::LastRoot) => {
+            debug!("User {id} is the last root, returning 409 CONFLICT");
+            return ApiError::new(StatusCode::CONFLICT, "last_root", "Cannot demote the last remaining root").into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to update role of user {id} in database"), err));
+            return ApiError::internal(format!("Failed to update role of user {id} in database")).into_response();
+        },
+    };
+    info!("User {} changed role of user {id} to {}", user.id, body.role.variant());
+    if let Err(err) = state.db.log_event(AuditEventKind::RoleChanged, Some(id), client.ip()).await {
+        error!("{}", trace!(("Failed to log audit event"), err));
+    }
+
+    // Return the new user without their password
+    (StatusCode::OK, Json(UserSummary::from(target))).into_response()
+}
+
+/// Handles restoring a user that was deleted, such that it can login again.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`UserInfo`] of the (root) user doing the restore, as injected by the auth middleware.
+/// - `id`: The identifier of the user to restore.
+///
+/// # Returns
+/// `200 OK` with the restored [`UserSummary`] in the body.
+///
+/// `404 NOT FOUND` with an [`ApiError`] of kind `user_not_found` if there is no such deleted user.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn restore(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+) -> Response {
+    info!("Handling {} {} from '{}'", RESTORE_PATH.method, RESTORE_PATH.path, client);
+
+    // Restore the user
+    match state.db.restore_user(id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("Deleted user {id} not found, returning 404 NOT FOUND");
+            return ApiError::new(StatusCode::NOT_FOUND, "user_not_found", format!("No deleted user with ID {id}")).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to restore user {id} in database"), err));
+            return ApiError::internal(format!("Failed to restore user {id} in database")).into_response();
+        },
+    }
+    info!("User {} restored user {id}", user.id);
+    if let Err(err) = state.db.log_event(AuditEventKind::UserRestored, Some(id), client.ip()).await {
+        error!("{}", trace!(("Failed to log audit event"), err));
+    }
+
+    // Return the restored user without their password
+    match state.db.get_user_by_id(id).await {
+        Ok(Some(target)) => (StatusCode::OK, Json(UserSummary::from(target))).into_response(),
+        Ok(None) => {
+            debug!("User {id} disappeared after restoring, returning 404 NOT FOUND");
+            ApiError::new(StatusCode::NOT_FOUND, "user_not_found", format!("No user with ID {id}")).into_response()
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to retrieve user {id} from database"), err));
+            ApiError::internal(format!("Failed to retrieve user {id} from database")).into_response()
+        },
+    }
+}
+
+/// Handles returning the logged-in user, such that clients can resolve who they are from their (opaque) login cookie.
+///
+/// Note that this path must be run behind the [`auth`](crate::middleware::auth::handle())-middleware, which returns `401 NOT AUTHORIZED`
+/// if the user isn't logged in.
+///
+/// # Arguments
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`UserInfo`] of the logged-in user, as injected by the auth middleware.
+///
+/// # Returns
+/// `200 OK` with the [`UserSummary`] of the logged-in user in the body.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn whoami(ConnectInfo(client): ConnectInfo<SocketAddr>, AuthUser(user): AuthUser) -> (StatusCode, Json<UserSummary>) {
+    info!("Handling {} {} from '{}'", WHOAMI_PATH.method, WHOAMI_PATH.path, client);
+    (StatusCode::OK, Json(UserSummary::from(user)))
+}
+
+/// Handles listing the roles that users can have, such that clients don't have to hard-code them.
+///
+/// # Arguments
+/// - `client`: The address of the client we're working with.
+///
+/// # Returns
+/// `200 OK` with a JSON array of [`RoleInfo`]s in the body, from least to most powerful.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn roles(ConnectInfo(client): ConnectInfo<SocketAddr>) -> (StatusCode, Json<Vec<RoleInfo>>) {
+    info!("Handling {} {} from '{}'", ROLES_PATH.method, ROLES_PATH.path, client);
+    let roles: Vec<RoleInfo> = Role::all().iter().map(|role| RoleInfo { role: *role, name: role.display_name().into(), value: u8::from(*role) }).collect();
+    (StatusCode::OK, Json(roles))
+}
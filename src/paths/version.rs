@@ -4,41 +4,87 @@
 //  Created:
 //    08 Apr 2024, 17:36:28
 //  Last edited:
-//    09 Apr 2024, 12:49:53
+//    14 Oct 2026, 18:30:27
 //  Auto updated?
 //    Yes
 //
 //  Description:
 //!   Defines a simple, no-nonsense `version` endpoint that can be used to
-//!   extract some information.
+//!   extract some information, including whether a client is still
+//!   compatible with this server.
 //
 
 use std::borrow::Cow;
 use std::net::SocketAddr;
 
-use axum::extract::{ConnectInfo, State};
+use axum::extract::{ConnectInfo, Query, State};
 use axum::response::Json;
+use axum::Extension;
 use hyper::StatusCode;
-use log::info;
+use log::{debug, info};
+use schemars::JsonSchema;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
-use crate::spec::Path;
+use crate::database::UserSummary;
+use crate::middleware::auth::MaybeAuthUser;
+use crate::middleware::request_id::RequestId;
+use crate::spec::{ApiError, Path};
 use crate::state::ServerState;
 
 
+/***** CONSTANTS *****/
+/// The hash of the git commit that the server was built from, or `unknown` if it wasn't known at build time.
+pub const GIT_COMMIT: &str = env!("DND_GIT_COMMIT");
+/// The time (in RFC 3339) at which the build metadata of the server was captured.
+pub const BUILD_TIMESTAMP: &str = env!("DND_BUILD_TIMESTAMP");
+
+
+
+
+
 /***** SPEC *****/
 /// The reqwest-compatible path on which the version endpoint can be found.
 pub const PATH: Path = Path { method: hyper::Method::GET, path: "/v1/version" };
 
 
-/// The response returned by the version endpoint.
+/// The query parameters given when asking for the version.
 #[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VersionQuery {
+    /// The version of the client asking, if it wants to know whether it's compatible.
+    #[serde(default)]
+    pub client:   Option<String>,
+    /// Whether to include the [`BuildInfo`] in the response.
+    #[serde(default)]
+    pub detailed: bool,
+}
+
+/// Metadata about the build of the server, returned by the version endpoint if asked for.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct BuildInfo<'a> {
+    /// The hash of the git commit that the server was built from, or `unknown`.
+    pub commit:    Cow<'a, str>,
+    /// The time (in RFC 3339) at which the build metadata was captured.
+    pub timestamp: Cow<'a, str>,
+}
+
+/// The response returned by the version endpoint.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct VersionResponse<'a> {
     /// The name of the server executable.
-    pub name:    Cow<'a, str>,
+    pub name:       Cow<'a, str>,
     /// The semantic version of the server.
-    pub version: Version,
+    pub version:    Version,
+    /// Whether the client version given in the query is at least the minimum version supported by the server. Omitted if no client
+    /// version was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatible: Option<bool>,
+    /// Metadata about the build of the server. Omitted unless asked for with `detailed=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build:      Option<BuildInfo<'a>>,
+    /// The user that is logged in. Omitted if the client isn't logged in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user:       Option<UserSummary>,
 }
 
 
@@ -51,11 +97,43 @@ pub struct VersionResponse<'a> {
 /// # Arguments
 /// - `state`: The shared [`ServerState`] between paths.
 /// - `client`: The address of the client we're working with.
+/// - `rid`: The [`RequestId`] of this request, which is included in all log lines.
+/// - `user`: The [`MaybeAuthUser`] with the info of the logged-in user, if any.
+/// - `query`: A [`VersionQuery`] with the version of the client, if any, and whether to include the build metadata.
 ///
 /// # Returns
-/// `200 OK` with a [`VersionResponse`] in the body.
+/// `200 OK` with a [`VersionResponse`] in the body. If the query has `detailed=true`, this includes the git commit and build timestamp. If
+/// the client is logged in, it includes a summary of their user as well.
+///
+/// `400 BAD REQUEST` if the given client version is not a valid semantic version.
 #[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
-pub async fn handle(State(state): State<ServerState>, ConnectInfo(client): ConnectInfo<SocketAddr>) -> (StatusCode, Json<VersionResponse<'static>>) {
-    info!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
-    (StatusCode::OK, Json::from(VersionResponse { name: Cow::Borrowed(state.name), version: state.version.clone() }))
+pub async fn handle(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Extension(rid): Extension<RequestId>,
+    MaybeAuthUser(user): MaybeAuthUser,
+    Query(query): Query<VersionQuery>,
+) -> Result<(StatusCode, Json<VersionResponse<'static>>), ApiError> {
+    info!("[{rid}] Handling {} {} from '{}'", PATH.method, PATH.path, client);
+
+    // Check the client's version, if any
+    let compatible: Option<bool> = match query.client {
+        Some(raw) => match Version::parse(&raw) {
+            Ok(version) => Some(version >= state.config.read().min_client_version),
+            Err(err) => {
+                debug!("[{rid}] Invalid client version {raw:?} ({err}), returning 400 BAD REQUEST");
+                return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_version", format!("Invalid client version {raw:?}: {err}")));
+            },
+        },
+        None => None,
+    };
+
+    // Add the build metadata, if asked for
+    let build: Option<BuildInfo> =
+        if query.detailed { Some(BuildInfo { commit: Cow::Borrowed(GIT_COMMIT), timestamp: Cow::Borrowed(BUILD_TIMESTAMP) }) } else { None };
+
+    Ok((
+        StatusCode::OK,
+        Json::from(VersionResponse { name: Cow::Borrowed(state.name), version: state.version.clone(), compatible, build, user: user.map(UserSummary::from) }),
+    ))
 }
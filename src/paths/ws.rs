@@ -0,0 +1,176 @@
+//  WS.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 20:21:16
+//  Last edited:
+//    14 Oct 2026, 18:29:51
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the WebSocket path on which clients receive live [`Event`]s
+//!   for a campaign, and on which they can chat.
+//
+
+use std::borrow::Cow;
+use std::net::SocketAddr;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Path as PathParam, State};
+use axum::response::{IntoResponse as _, Response};
+use error_trace::trace;
+use hyper::StatusCode;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use crate::database::{ChatMessage, UserInfo};
+use crate::events::Event;
+use crate::middleware::auth::AuthUser;
+use crate::paths::campaigns::MESSAGE_MAX_LEN;
+use crate::spec::Path;
+use crate::state::ServerState;
+
+
+/***** SPEC *****/
+/// The reqwest-compatible path on which the WebSocket endpoint can be found.
+pub const PATH: Path = Path { method: hyper::Method::GET, path: "/v1/campaigns/:id/ws" };
+
+
+/// The messages that clients may send over the WebSocket.
+///
+/// Like [`Event`]s, these are serialized as JSON objects with a `kind` field that determines the variant.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClientMessage<'a> {
+    /// Posts a chat message in the campaign, like [`campaigns::post_message()`](super::campaigns::post_message()) does.
+    Chat {
+        /// The message to send.
+        message: Cow<'a, str>,
+    },
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Runs an upgraded WebSocket connection until the client disconnects.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `campaign`: The identifier of the campaign for which to relay events.
+/// - `socket`: The [`WebSocket`] to the client.
+async fn run(state: ServerState, client: SocketAddr, user: UserInfo, campaign: u64, mut socket: WebSocket) {
+    debug!("Client '{client}' (user {}) connected to events of campaign {campaign}", user.id);
+    let mut events: Receiver<Event> = state.subscribe(campaign);
+    loop {
+        tokio::select! {
+            // Handle messages from the client
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Chat { message }) => {
+                        if message.is_empty() || message.chars().count() > MESSAGE_MAX_LEN {
+                            debug!("Client '{client}' sent empty or too long chat message; ignoring");
+                            continue;
+                        }
+                        let message: ChatMessage = match state.db.post_message(campaign, user.id, message.as_ref()).await {
+                            Ok(message) => message,
+                            Err(err) => {
+                                error!("{}", trace!(("Failed to post message of user {} in campaign {campaign} to database", user.id), err));
+                                continue;
+                            },
+                        };
+                        state.publish(campaign, Event::Chat { name: user.name.clone(), message });
+                    },
+                    Err(err) => debug!("{}", trace!(("Client '{client}' sent invalid message; ignoring"), err)),
+                },
+                Some(Ok(Message::Close(_))) | None => break,
+                // NOTE: Pings are answered by axum already, and we don't do anything with binary messages
+                Some(Ok(_)) => {},
+                Some(Err(err)) => {
+                    debug!("{}", trace!(("Failed to receive message from client '{client}'"), err));
+                    break;
+                },
+            },
+
+            // Relay events to the client
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let text: String = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(err) => {
+                            error!("{}", trace!(("Failed to serialize event"), err));
+                            continue;
+                        },
+                    };
+                    if let Err(err) = socket.send(Message::Text(text)).await {
+                        debug!("{}", trace!(("Failed to send event to client '{client}'"), err));
+                        break;
+                    }
+                },
+                Err(RecvError::Lagged(n)) => debug!("Client '{client}' missed {n} event(s) of campaign {campaign}"),
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    // Clean up the channel if we were the last one
+    debug!("Client '{client}' (user {}) disconnected from events of campaign {campaign}", user.id);
+    drop(events);
+    state.unsubscribe(campaign);
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Handles upgrading a connection to a WebSocket on which the client receives live [`Event`]s for a campaign.
+///
+/// Only members of the campaign (i.e., its dungeon master and the players that joined it) can connect.
+///
+/// # Arguments
+/// - `state`: The shared [`ServerState`] between paths.
+/// - `client`: The address of the client we're working with.
+/// - `user`: The [`AuthUser`] with the info of the logged-in user.
+/// - `id`: The identifier of the campaign to receive events for.
+/// - `ws`: The [`WebSocketUpgrade`] with which to upgrade the connection.
+///
+/// # Returns
+/// `101 SWITCHING PROTOCOLS` if the connection was upgraded.
+///
+/// `404 NOT FOUND` if the campaign does not exist or the user is not a member of it.
+///
+/// # Errors
+/// This function may error (with `500 INTERNAL SERVER ERROR`) if we fail to contact the backend database.
+#[cfg_attr(feature = "axum-debug", axum_macros::debug_handler)]
+pub async fn handle(
+    State(state): State<ServerState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    AuthUser(user): AuthUser,
+    PathParam(id): PathParam<u64>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    info!("Handling {} {} from '{}'", PATH.method, PATH.path, client);
+
+    // Check if the user may connect
+    match state.db.is_member(id, user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!("User {} is not a member of campaign {id}, returning 404 NOT FOUND", user.id);
+            return (StatusCode::NOT_FOUND, String::new()).into_response();
+        },
+        Err(err) => {
+            error!("{}", trace!(("Failed to check if user {} is a member of campaign {id}", user.id), err));
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to connect to campaign {id}")).into_response();
+        },
+    }
+
+    // Upgrade
+    ws.on_upgrade(move |socket| run(state, client, user, id, socket))
+}
@@ -0,0 +1,307 @@
+//  ROUTER.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:03:51
+//  Last edited:
+//    14 Oct 2026, 19:03:09
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Assembles the handlers in [`crate::paths`], the [`crate::middleware`]
+//!   and the client files into the [`Router`] that serves the server.
+//!   
+//!   This is shared between the binary and anything else that wants to
+//!   embed the server, such as other binaries or tests.
+//!   
+//!   The administrative paths (user management, the audit log, backups and
+//!   exports) can be hosted separately from the rest with
+//!   [`build_public_router()`] and [`build_admin_router()`], e.g., on a
+//!   port that is firewalled off from the public internet. They still
+//!   require a logged-in root user, which is why the admin router serves
+//!   the `/v1/auth` paths as well. Note that this only reduces who can
+//!   _reach_ the admin paths; since cookies aren't bound to a port, the
+//!   login cookie obtained on the public listener is also accepted on the
+//!   admin one (if on the same host), and vice versa.
+//
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::routing::{delete, get, patch, post};
+use axum::Router;
+use log::debug;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::services::{ServeDir, ServeFile};
+use tower_http::timeout::TimeoutLayer;
+
+use crate::auth::Role;
+use crate::middleware::auth::RouterExt as _;
+use crate::paths::openapi::ENDPOINTS;
+use crate::spec::{Endpoint, Path as SpecPath};
+use crate::state::ServerState;
+use crate::{middleware, paths};
+
+
+/***** CONSTANTS *****/
+/// The paths mounted by [`admin_routes()`]. Be sure to keep this in sync with it.
+const ADMIN_PATHS: &[&SpecPath] = &[
+    &paths::admin::BACKUP_PATH,
+    &paths::admin::EXPORT_PATH,
+    &paths::admin::IMPORT_PATH,
+    &paths::admin::MAINTENANCE_PATH,
+    &paths::audit::PATH,
+    &paths::users::LIST_PATH,
+    &paths::users::SEARCH_PATH,
+    &paths::users::RESTORE_PATH,
+    &paths::users::ROLE_PATH,
+];
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Checks whether the given path is one of the administrative ones (see [`ADMIN_PATHS`]).
+///
+/// # Arguments
+/// - `path`: The [`SpecPath`] to check.
+///
+/// # Returns
+/// True if it is mounted by [`admin_routes()`], or false otherwise.
+#[inline]
+fn is_admin_path(path: &SpecPath) -> bool { ADMIN_PATHS.iter().any(|admin| admin.method == path.method && admin.path == path.path) }
+
+/// Collects the endpoints of the [`ENDPOINTS`]-registry that are mounted on a router, such that [`middleware::method`] doesn't know about
+/// any others.
+///
+/// # Arguments
+/// - `mounted`: A closure that decides whether the given [`SpecPath`] is mounted.
+///
+/// # Returns
+/// The mounted [`Endpoint`]s, ready to be given as state to [`middleware::method::handle()`].
+fn mounted_endpoints(mounted: impl Fn(&SpecPath) -> bool) -> Arc<[&'static Endpoint]> { ENDPOINTS.iter().filter(|endpoint| mounted(endpoint.path)).collect() }
+
+/// Returns the request limits with which to build a router.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] to read the limits from.
+///
+/// # Returns
+/// A tuple of the maximum request body size (in bytes) and the request timeout.
+#[inline]
+fn request_limits(state: &ServerState) -> (usize, Duration) {
+    let config = state.config.read();
+    (config.max_body_size, config.request_timeout)
+}
+
+/// Builds the (unnested) [`Router`] with the `/v1/auth` paths, which are served on both the public and the admin listener.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+///
+/// # Returns
+/// A new [`Router`] that should be nested under `/v1`.
+fn auth_routes(state: &ServerState) -> Router {
+    Router::new()
+        .route("/auth/login", post(paths::auth::login))
+        .route("/auth/logout", post(paths::auth::logout))
+        .route("/auth/refresh", post(paths::auth::refresh))
+        .route("/auth/register", post(paths::auth::register))
+        .route("/auth/reset", post(paths::auth::reset))
+        .route("/auth/reset/request", post(paths::auth::request_reset))
+        .merge(
+            Router::new()
+                .route("/auth/change-password", post(paths::auth::change_password))
+                .route("/auth/totp", post(paths::auth::enroll_totp))
+                .route("/auth/totp/confirm", post(paths::auth::confirm_totp))
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle)),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(state.limiter.clone(), middleware::ratelimit::handle))
+        .with_state(state.clone())
+}
+
+/// Builds the (unnested) [`Router`] with the administrative `/v1` paths, which all require a logged-in root user.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+///
+/// # Returns
+/// A new [`Router`] that should be nested under `/v1`.
+fn admin_routes(state: &ServerState) -> Router {
+    Router::new()
+        .route("/admin/backup", post(paths::admin::backup))
+        .route("/admin/export", get(paths::admin::export))
+        .route("/admin/import", post(paths::admin::import))
+        .route("/admin/maintenance", post(paths::admin::maintenance))
+        .route("/audit", get(paths::audit::handle))
+        .route("/users", get(paths::users::list))
+        .route("/users/search", get(paths::users::search))
+        .route("/users/:id/restore", post(paths::users::restore))
+        .route("/users/:id/role", patch(paths::users::update_role))
+        .require_role(Role::Root)
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone())
+}
+
+/// Builds the [`Router`] that serves all paths of the server, optionally leaving out the administrative ones.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+/// - `client_path`: The directory with the client files.
+/// - `admin`: Whether to include the administrative paths.
+///
+/// # Returns
+/// A new [`Router`] that is ready to be served.
+fn build_routes(state: ServerState, client_path: &Path, admin: bool) -> Router {
+    let (max_body_size, request_timeout): (usize, Duration) = request_limits(&state);
+
+    // Build the API paths
+    debug!("Building axum API paths...");
+    let auth: Router = auth_routes(&state);
+    let campaigns: Router = Router::new()
+        .route("/campaigns", post(paths::campaigns::create).route_layer(axum::middleware::from_fn(middleware::auth::require_role(Role::DungeonMaster))))
+        .route("/campaigns", get(paths::campaigns::list))
+        .route("/campaigns/:id", get(paths::campaigns::get).delete(paths::campaigns::delete))
+        .route("/campaigns/join", post(paths::campaigns::join))
+        .route("/campaigns/:id/invite", get(paths::campaigns::invite).post(paths::campaigns::rotate_invite))
+        .route("/campaigns/:id/initiative", get(paths::initiative::get).put(paths::initiative::set).delete(paths::initiative::clear))
+        .route("/campaigns/:id/initiative/next", post(paths::initiative::next))
+        .route("/campaigns/:id/members", get(paths::campaigns::members).post(paths::campaigns::add_member))
+        .route("/campaigns/:id/members/:user", delete(paths::campaigns::remove_member))
+        .route("/campaigns/:id/messages", get(paths::campaigns::messages).post(paths::campaigns::post_message))
+        .route("/campaigns/:id/rolls", get(paths::campaigns::rolls))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone());
+    let characters: Router = Router::new()
+        .route("/characters", get(paths::characters::list).post(paths::characters::create))
+        .route("/characters/:id", get(paths::characters::get).put(paths::characters::update).delete(paths::characters::delete))
+        .route("/characters/:id/hp", patch(paths::characters::hp))
+        .route("/characters/:id/resources", patch(paths::characters::resources))
+        .route("/me/characters", get(paths::characters::own))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone());
+    let roll: Router = Router::new()
+        .route("/roll", post(paths::roll::handle))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone());
+    let roles: Router = Router::new()
+        .route("/roles", get(paths::users::roles))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone());
+    let whoami: Router = Router::new()
+        .route("/whoami", get(paths::users::whoami))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .with_state(state.clone());
+    let health: Router = Router::new().route("/healthz", get(paths::health::handle)).route("/readyz", get(paths::health::ready)).with_state(state.clone());
+    let version: Router = Router::new().route("/version", get(paths::version::handle)).with_state(state.clone());
+    // NOTE: The WebSocket is kept separate, as it's long-lived and so shouldn't be subject to the request timeout
+    let ws: Router = Router::new()
+        .route("/v1/campaigns/:id/ws", get(paths::ws::handle))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth::handle))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::maintenance::handle))
+        .with_state(state.clone());
+    let openapi: Router = Router::new().route("/openapi.json", get(paths::openapi::handle)).with_state(state.clone());
+    // NOTE: We merge the versioned routers first and then nest them once, as nesting multiple routers at the same path doesn't combine them.
+    //       The version endpoint is merged in after checking the client's version, as that's how outdated clients find out they are.
+    //       Maintenance mode is checked before merging in the admin paths, such that root users can still switch it off again.
+    let mut v1: Router = Router::new()
+        .merge(auth)
+        .merge(campaigns)
+        .merge(characters)
+        .merge(roles)
+        .merge(roll)
+        .merge(whoami)
+        .layer(axum::middleware::from_fn_with_state(state.clone(), middleware::maintenance::handle));
+    if admin {
+        v1 = v1.merge(admin_routes(&state));
+    }
+    let v1: Router = v1.layer(axum::middleware::from_fn_with_state(state.clone(), middleware::client_version::handle)).merge(version);
+    let api: Router = Router::new()
+        .merge(health)
+        .merge(openapi)
+        .nest("/v1", v1)
+        .layer(RequestBodyLimitLayer::new(max_body_size))
+        .layer(TimeoutLayer::new(request_timeout))
+        .merge(ws);
+
+    // Build the file server paths
+    debug!("Building axum file paths...");
+    // NOTE: Unknown paths serve the index, such that the client can do its own routing
+    let files: ServeDir<ServeFile> = ServeDir::new(client_path).fallback(ServeFile::new(client_path.join("index.html")));
+    let files: Router = Router::new()
+        .fallback_service(files)
+        .layer(axum::middleware::from_fn(middleware::static_cache::handle))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), middleware::login_redirect::handle))
+        .with_state(state.clone());
+
+    // Join them; the file server is the fallback, such that the API paths always take precedence
+    // NOTE: Known paths with the wrong method are refused before they can fall through to it
+    let endpoints: Arc<[&'static Endpoint]> = mounted_endpoints(|path| admin || !is_admin_path(path));
+    Router::new().merge(api).merge(files).layer(axum::middleware::from_fn_with_state(endpoints, middleware::method::handle))
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Builds the [`Router`] that serves all paths of the server: the probes, the OpenAPI document, the `/v1` paths, the WebSocket and,
+/// as a fallback for everything else, the client files.
+///
+/// The request limits (see [`ServerConfig::max_body_size`](crate::state::ServerConfig::max_body_size) and
+/// [`ServerConfig::request_timeout`](crate::state::ServerConfig::request_timeout)) are read from the `state` once, so changing them
+/// afterwards has no effect on the returned router.
+///
+/// Note that this doesn't include anything that is specific to how the binary is hosted, such as CORS, compression, metrics or tracking
+/// in-flight requests.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+/// - `client_path`: The directory with the client files. Unknown paths serve its `index.html`, such that the client can do its own routing.
+///
+/// # Returns
+/// A new [`Router`] that is ready to be served.
+#[inline]
+pub fn build_router(state: ServerState, client_path: &Path) -> Router { build_routes(state, client_path, true) }
+
+/// Builds the [`Router`] that serves all paths of the server except for the administrative ones.
+///
+/// Use this together with [`build_admin_router()`] to host those on a separate listener. Otherwise behaves like [`build_router()`].
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+/// - `client_path`: The directory with the client files.
+///
+/// # Returns
+/// A new [`Router`] that is ready to be served.
+#[inline]
+pub fn build_public_router(state: ServerState, client_path: &Path) -> Router { build_routes(state, client_path, false) }
+
+/// Builds the [`Router`] that serves the administrative paths of the server (i.e., those left out by [`build_public_router()`]).
+///
+/// The `/v1/auth` paths are served as well, such that root users can log in on it directly. It is subject to the same request limits as
+/// [`build_router()`], but doesn't serve any client files.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] shared between all paths.
+///
+/// # Returns
+/// A new [`Router`] that is ready to be served.
+pub fn build_admin_router(state: ServerState) -> Router {
+    let (max_body_size, request_timeout): (usize, Duration) = request_limits(&state);
+    let endpoints: Arc<[&'static Endpoint]> = mounted_endpoints(|path| path.path.starts_with("/v1/auth/") || is_admin_path(path));
+    // NOTE: The auth paths are included such that root users can log in (and out) on this listener directly, i.e., without having to
+    //       reach the public one first
+    let v1: Router = auth_routes(&state)
+        .layer(axum::middleware::from_fn_with_state(state.clone(), middleware::maintenance::handle))
+        .merge(admin_routes(&state))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), middleware::client_version::handle));
+    Router::new()
+        .nest("/v1", v1)
+        .layer(RequestBodyLimitLayer::new(max_body_size))
+        .layer(TimeoutLayer::new(request_timeout))
+        .layer(axum::middleware::from_fn_with_state(endpoints, middleware::method::handle))
+}
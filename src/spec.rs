@@ -4,15 +4,41 @@
 //  Created:
 //    09 Apr 2024, 12:15:18
 //  Last edited:
-//    09 Apr 2024, 12:16:22
+//    14 Oct 2026, 18:22:50
 //  Auto updated?
 //    Yes
 //
 //  Description:
 //!   Defines some definitions for the spec of the server.
+//!   
+//!   Besides the [`Path`] of every endpoint, this defines an [`Endpoint`]
+//!   that describes it in enough detail to generate an OpenAPI document
+//!   from (see [`crate::paths::openapi`]), and the [`ApiError`] that
+//!   endpoints return when something goes wrong.
+//!   
+//!   Finally, it defines the [`Pagination`] and [`Page`] with which listing
+//!   endpoints are paginated.
 //
 
-use hyper::Method;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Json, Response};
+use hyper::{Method, StatusCode};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+
+/***** CONSTANTS *****/
+/// The number of items returned by paginated endpoints if no limit is given.
+pub const PAGE_LIMIT_DEFAULT: u64 = 50;
+/// The maximum number of items that paginated endpoints return in one go.
+pub const PAGE_LIMIT_MAX: u64 = 100;
+
+
+
 
 
 /***** LIBRARY *****/
@@ -23,3 +49,163 @@ pub struct Path {
     /// The path on which the method can be found.
     pub path:   &'static str,
 }
+impl Path {
+    /// Joins the given base URL with this path.
+    ///
+    /// Exactly one slash ends up between them, regardless of whether the `base` ends with one or the path starts with one.
+    ///
+    /// # Arguments
+    /// - `base`: The base URL of the server (e.g., `https://dnd.example.com`).
+    ///
+    /// # Returns
+    /// The full URL of this path (e.g., `https://dnd.example.com/v1/version`).
+    #[inline]
+    pub fn url(&self, base: &str) -> String { format!("{}/{}", base.trim_end_matches('/'), self.path.trim_start_matches('/')) }
+
+    /// Starts a request to this path with the right method.
+    ///
+    /// # Arguments
+    /// - `client`: The [`reqwest::Client`] to build the request with.
+    /// - `base`: The base URL of the server (see [`Path::url()`]).
+    ///
+    /// # Returns
+    /// A [`reqwest::RequestBuilder`] with the method and URL already filled in.
+    #[cfg(feature = "client")]
+    #[inline]
+    pub fn request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder { client.request(self.method.clone(), self.url(base)) }
+}
+
+
+/// Generates the (referencing) [`Schema`] of some type, registering its definition in the given [`SchemaGenerator`].
+pub type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// Returns the [`SchemaFn`] of the given type.
+///
+/// Use as `schema::<T>` when defining an [`Endpoint`].
+///
+/// # Arguments
+/// - `gen`: The [`SchemaGenerator`] to register the definition of `T` in.
+///
+/// # Returns
+/// A [`Schema`] referencing the definition of `T`.
+#[inline]
+pub fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema { gen.subschema_for::<T>() }
+
+
+
+/// Describes an endpoint of the API for documentation purposes.
+pub struct Endpoint {
+    /// The [`Path`] on which the endpoint can be found.
+    pub path:     &'static Path,
+    /// A short, human-readable summary of what the endpoint does.
+    pub summary:  &'static str,
+    /// Whether the endpoint requires the user to be logged-in.
+    pub auth:     bool,
+    /// The schema of the JSON body accepted by the endpoint, if any.
+    pub request:  Option<SchemaFn>,
+    /// The status code returned by the endpoint on success.
+    pub status:   StatusCode,
+    /// The schema of the JSON body returned by the endpoint on success, if any.
+    pub response: Option<SchemaFn>,
+}
+
+
+/// Defines the JSON body returned by endpoints when something goes wrong.
+///
+/// Clients should switch on the `kind`, which is machine-stable, and treat the `message` as a human-readable explanation that may change.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ApiError {
+    /// The HTTP status code of the response.
+    pub code:    u16,
+    /// A machine-stable, `snake_case` identifier of what went wrong (e.g., `invalid_credentials`).
+    pub kind:    String,
+    /// A human-readable explanation of what went wrong.
+    pub message: String,
+}
+impl ApiError {
+    /// Constructor for the ApiError.
+    ///
+    /// # Arguments
+    /// - `status`: The [`StatusCode`] to return.
+    /// - `kind`: A machine-stable, `snake_case` identifier of what went wrong.
+    /// - `message`: A human-readable explanation of what went wrong.
+    ///
+    /// # Returns
+    /// A new ApiError.
+    #[inline]
+    pub fn new(status: StatusCode, kind: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { code: status.as_u16(), kind: kind.into(), message: message.into() }
+    }
+
+    /// Constructor for an ApiError that reports an internal server error.
+    ///
+    /// # Arguments
+    /// - `message`: A human-readable explanation of what went wrong.
+    ///
+    /// # Returns
+    /// A new ApiError with status `500 INTERNAL SERVER ERROR` and kind `internal`.
+    #[inline]
+    pub fn internal(message: impl Into<String>) -> Self { Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", message) }
+}
+impl IntoResponse for ApiError {
+    #[inline]
+    fn into_response(self) -> Response {
+        let status: StatusCode = StatusCode::from_u16(self.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(self)).into_response()
+    }
+}
+
+
+
+/// The query parameters given to paginated endpoints.
+///
+/// As an extractor, this reads them from the request's query and caps the `limit` at [`PAGE_LIMIT_MAX`]. Invalid queries are rejected
+/// with `400 BAD REQUEST`.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Pagination {
+    /// If given, the `next_cursor` of the previous [`Page`], to get the page after it.
+    #[serde(default)]
+    pub cursor: Option<u64>,
+    /// The maximum number of items to return. Capped at [`PAGE_LIMIT_MAX`].
+    #[serde(default = "Pagination::default_limit")]
+    pub limit:  u64,
+}
+impl Pagination {
+    /// Returns the default limit if none is given.
+    #[inline]
+    fn default_limit() -> u64 { PAGE_LIMIT_DEFAULT }
+}
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Pagination {
+    type Rejection = <Query<Self> as FromRequestParts<S>>::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(mut pagination): Query<Self> = Query::from_request_parts(parts, state).await?;
+        pagination.limit = pagination.limit.min(PAGE_LIMIT_MAX);
+        Ok(pagination)
+    }
+}
+
+/// Defines the JSON body returned by paginated endpoints.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Page<T> {
+    /// The items on this page.
+    pub items:       Vec<T>,
+    /// The cursor to give (see [`Pagination::cursor`]) to get the next page, or [`None`] if this is the last one.
+    pub next_cursor: Option<u64>,
+}
+impl<T> Page<T> {
+    /// Constructor for the Page.
+    ///
+    /// # Arguments
+    /// - `items`: The items on this page, as retrieved with the given `pagination`.
+    /// - `pagination`: The [`Pagination`] with which the items were retrieved.
+    /// - `cursor`: A closure that returns the cursor pointing past a given item. It is called on the last item only.
+    ///
+    /// # Returns
+    /// A new Page that has a `next_cursor` if it is full (i.e., there may be more items).
+    pub fn new(items: Vec<T>, pagination: &Pagination, cursor: impl FnOnce(&T) -> u64) -> Self {
+        let next_cursor: Option<u64> = if items.len() as u64 >= pagination.limit { items.last().map(cursor) } else { None };
+        Self { items, next_cursor }
+    }
+}
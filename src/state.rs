@@ -4,22 +4,125 @@
 //  Created:
 //    08 Apr 2024, 11:55:37
 //  Last edited:
-//    09 Apr 2024, 12:49:21
+//    14 Oct 2026, 19:01:33
 //  Auto updated?
 //    Yes
 //
 //  Description:
 //!   Defines the shared [`ServerState`] between all path handlers.
+//!   
+//!   This includes the per-campaign channels on which live [`Event`]s are
+//!   fanned out to connected clients.
 //
 
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::FromRef;
-use axum_extra::extract::cookie::Key;
+use axum_extra::extract::cookie::{Key, SameSite};
+use dashmap::DashMap;
+use error_trace::trace;
+use log::{debug, error};
+use parking_lot::RwLock;
 use semver::Version;
+use tokio::sync::broadcast;
+
+use crate::auth::{dummy_password_hash, Argon2Params, PASSWORD_MIN_LEN, REMEMBER_VALID_TIME_MIN, TOKEN_VALID_TIME_MIN};
+use crate::database::{Database, BACKUP_KEEP};
+use crate::events::Event;
+use crate::middleware::ratelimit::{RateLimiter, AUTH_RATE_LIMIT, AUTH_RATE_WINDOW_SECS};
+
+
+/***** CONSTANTS *****/
+/// The number of [`Event`]s that a campaign's channel buffers before slow clients start missing them.
+pub const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Defines the attributes with which the login cookie is set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CookieConfig {
+    /// The `SameSite`-attribute, which determines whether the cookie is sent along with cross-site requests.
+    pub same_site: SameSite,
+    /// Whether to set the `Secure`-attribute, which makes browsers only send the cookie over HTTPS.
+    pub secure:    bool,
+    /// Whether to set the `HttpOnly`-attribute, which hides the cookie from scripts.
+    pub http_only: bool,
+    /// The `Path`-attribute, i.e., the path prefix of the requests that the cookie is sent along with.
+    pub path:      String,
+    /// The `Domain`-attribute, if any. If omitted, the cookie is only sent to the exact host that set it.
+    pub domain:    Option<String>,
+}
+impl Default for CookieConfig {
+    #[inline]
+    fn default() -> Self { Self { same_site: SameSite::Lax, secure: false, http_only: true, path: "/".into(), domain: None } }
+}
+
+
+
+/// Defines the tunable parameters of the server.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// The time (in minutes) that login tokens are valid.
+    pub token_valid_min:        i64,
+    /// The time (in minutes) that login tokens are valid if the user asked to be remembered.
+    pub remember_valid_min:     i64,
+    /// The minimum length (in characters) of user passwords.
+    pub password_min_len:       usize,
+    /// The parameters with which to hash new passwords.
+    pub argon2:                 Argon2Params,
+    /// The maximum number of requests a single IP address may do to the `/v1/auth` paths per `auth_rate_window`.
+    pub auth_rate_limit:        u32,
+    /// The window in which a single IP address may do at most `auth_rate_limit` requests to the `/v1/auth` paths.
+    pub auth_rate_window:       Duration,
+    /// The oldest version of the client that is still compatible with this server.
+    pub min_client_version:     Version,
+    /// Whether clients must give their version on API requests, instead of being assumed to be compatible if they don't.
+    pub require_client_version: bool,
+    /// The directory to which database backups are written.
+    pub backup_dir:             PathBuf,
+    /// The number of database backups to keep in the `backup_dir`.
+    pub backup_keep:            usize,
+    /// The maximum size (in bytes) of request bodies sent to the API. Only read when building the router, so changes need a restart.
+    pub max_body_size:          usize,
+    /// The time that API requests get before they are aborted. Only read when building the router, so changes need a restart.
+    pub request_timeout:        Duration,
+    /// The attributes with which the login cookie is set.
+    pub cookie:                 CookieConfig,
+    /// If given, the path of the client's login page, to which browsers that aren't logged in are redirected when navigating to the
+    /// client.
+    pub login_redirect:         Option<String>,
+}
+impl Default for ServerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            token_valid_min:        TOKEN_VALID_TIME_MIN,
+            remember_valid_min:     REMEMBER_VALID_TIME_MIN,
+            password_min_len:       PASSWORD_MIN_LEN,
+            argon2:                 Argon2Params::default(),
+            auth_rate_limit:        AUTH_RATE_LIMIT,
+            auth_rate_window:       Duration::from_secs(AUTH_RATE_WINDOW_SECS),
+            min_client_version:     Version::new(0, 0, 0),
+            require_client_version: false,
+            backup_dir:             PathBuf::from("/data/backups"),
+            backup_keep:            BACKUP_KEEP,
+            max_body_size:          64 * 1024,
+            request_timeout:        Duration::from_secs(30),
+            cookie:                 CookieConfig::default(),
+            login_redirect:         None,
+        }
+    }
+}
+
+
 
-use crate::database::Database;
 
 
 /***** LIBRARY *****/
@@ -35,11 +138,30 @@ impl ServerState {
     /// - `name`: Some name for the server executable that can be shared with clients upon request.
     /// - `version`: Some version for the server executable that can be shared with clients upon request.
     /// - `db`: Some already initialized [`Database`] connection to use to store persistent state.
+    /// - `config`: The [`ServerConfig`] that determines the tunable parameters of the server.
+    ///
+    /// # Returns
+    /// A new ServerState.
+    #[inline]
+    pub fn new(name: &'static str, version: Version, db: Database, config: ServerConfig) -> Self {
+        Self(Arc::new(InternalServerState::new(name, version, db, config)))
+    }
+
+    /// Constructor for the ServerState that uses an existing [`Key`] instead of generating one.
+    ///
+    /// # Arguments
+    /// - `name`: Some name for the server executable that can be shared with clients upon request.
+    /// - `version`: Some version for the server executable that can be shared with clients upon request.
+    /// - `db`: Some already initialized [`Database`] connection to use to store persistent state.
+    /// - `config`: The [`ServerConfig`] that determines the tunable parameters of the server.
+    /// - `key`: The [`Key`] with which to encrypt cookies (see [`load_or_generate_key()`](crate::auth::load_or_generate_key)).
     ///
     /// # Returns
     /// A new ServerState.
     #[inline]
-    pub fn new(name: &'static str, version: Version, db: Database) -> Self { Self(Arc::new(InternalServerState::new(name, version, db))) }
+    pub fn with_key(name: &'static str, version: Version, db: Database, config: ServerConfig, key: Key) -> Self {
+        Self(Arc::new(InternalServerState::with_key(name, version, db, config, key)))
+    }
 }
 impl Deref for ServerState {
     type Target = InternalServerState;
@@ -66,8 +188,24 @@ pub struct InternalServerState {
     /// The database that we use for the data-wise state.
     pub db: Database,
 
-    /// Some key that we generate every time the server starts.
+    /// The tunable parameters of the server. These may be swapped at runtime when the configuration is reloaded (see
+    /// [`InternalServerState::reload_config()`]), so don't hold on to the lock for longer than necessary.
+    pub config:     RwLock<ServerConfig>,
+    /// The limiter of requests to the `/v1/auth` paths. Its rate is updated by [`InternalServerState::reload_config()`].
+    pub limiter:    RateLimiter,
+    /// The hash that logins for unknown users are checked against (see [`check_dummy_password()`](crate::auth::check_dummy_password)),
+    /// generated with the current [`ServerConfig::argon2`] parameters. Is [`None`] if we failed to generate it.
+    pub dummy_hash: RwLock<Option<String>>,
+
+    /// The key with which cookies are encrypted. Unless loaded from disk, this is generated every time the server starts.
     pub key: Key,
+
+    /// The channels on which [`Event`]s are fanned out to the clients connected to a campaign, by campaign identifier.
+    pub channels: DashMap<u64, broadcast::Sender<Event>>,
+
+    /// Whether the server is in maintenance mode, in which all but the health and administrative paths are refused. Toggled by root users
+    /// through the `/v1/admin/maintenance` path.
+    pub maintenance: AtomicBool,
 }
 impl InternalServerState {
     /// Constructor for the InternalServerState.
@@ -76,9 +214,110 @@ impl InternalServerState {
     /// - `name`: Some name for the server executable that can be shared with clients upon request.
     /// - `version`: Some version for the server executable that can be shared with clients upon request.
     /// - `db`: Some already initialized [`Database`] connection to use to store persistent state.
+    /// - `config`: The [`ServerConfig`] that determines the tunable parameters of the server.
     ///
     /// # Returns
     /// A new InternalServerState.
     #[inline]
-    pub fn new(name: &'static str, version: Version, db: Database) -> Self { Self { name, version, db, key: Key::generate() } }
+    pub fn new(name: &'static str, version: Version, db: Database, config: ServerConfig) -> Self { Self::with_key(name, version, db, config, Key::generate()) }
+
+    /// Constructor for the InternalServerState that uses an existing [`Key`] instead of generating one.
+    ///
+    /// # Arguments
+    /// - `name`: Some name for the server executable that can be shared with clients upon request.
+    /// - `version`: Some version for the server executable that can be shared with clients upon request.
+    /// - `db`: Some already initialized [`Database`] connection to use to store persistent state.
+    /// - `config`: The [`ServerConfig`] that determines the tunable parameters of the server.
+    /// - `key`: The [`Key`] with which to encrypt cookies.
+    ///
+    /// # Returns
+    /// A new InternalServerState.
+    #[inline]
+    pub fn with_key(name: &'static str, version: Version, db: Database, config: ServerConfig, key: Key) -> Self {
+        let limiter: RateLimiter = RateLimiter::new(config.auth_rate_limit, config.auth_rate_window);
+        let dummy_hash: Option<String> = Self::generate_dummy_hash(&config.argon2);
+        Self {
+            name,
+            version,
+            db,
+            config: RwLock::new(config),
+            limiter,
+            dummy_hash: RwLock::new(dummy_hash),
+            key,
+            channels: DashMap::new(),
+            maintenance: AtomicBool::new(false),
+        }
+    }
+
+    /// Generates the dummy hash for the [`InternalServerState::dummy_hash`].
+    ///
+    /// # Arguments
+    /// - `params`: The [`Argon2Params`] with which new passwords are hashed.
+    ///
+    /// # Returns
+    /// The new dummy hash, or [`None`] if we failed to generate it (which is logged).
+    fn generate_dummy_hash(params: &Argon2Params) -> Option<String> {
+        debug!("Generating dummy password hash for {params:?}...");
+        match dummy_password_hash(params) {
+            Ok(hash) => Some(hash),
+            Err(err) => {
+                error!("{}", trace!(("Failed to generate dummy password hash (logins for unknown users will be answered faster)"), err));
+                None
+            },
+        }
+    }
+
+    /// Swaps the [`ServerConfig`] for a new one, e.g., after the configuration file was reloaded.
+    ///
+    /// This also updates everything derived from it, i.e., the rate of the [`InternalServerState::limiter`] and the
+    /// [`InternalServerState::dummy_hash`].
+    ///
+    /// # Arguments
+    /// - `config`: The new [`ServerConfig`].
+    pub fn reload_config(&self, config: ServerConfig) {
+        // NOTE: We hash before taking any lock, and only if the parameters changed, as it's slow
+        if config.argon2 != self.config.read().argon2 {
+            let dummy_hash: Option<String> = Self::generate_dummy_hash(&config.argon2);
+            *self.dummy_hash.write() = dummy_hash;
+        }
+        self.limiter.set_rate(config.auth_rate_limit, config.auth_rate_window);
+        *self.config.write() = config;
+    }
+
+    /// Subscribes to the [`Event`]s of a campaign, creating its channel if nobody else listens to it yet.
+    ///
+    /// Call [`InternalServerState::unsubscribe()`] after dropping the returned receiver to clean up the channel if it's no longer used.
+    ///
+    /// # Arguments
+    /// - `campaign`: The identifier of the campaign to subscribe to.
+    ///
+    /// # Returns
+    /// A [`broadcast::Receiver`] that receives all [`Event`]s published to the campaign from now on.
+    pub fn subscribe(&self, campaign: u64) -> broadcast::Receiver<Event> {
+        self.channels.entry(campaign).or_insert_with(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0).subscribe()
+    }
+
+    /// Removes the channel of a campaign if nobody listens to it anymore.
+    ///
+    /// # Arguments
+    /// - `campaign`: The identifier of the campaign to clean up the channel of.
+    pub fn unsubscribe(&self, campaign: u64) {
+        if self.channels.remove_if(&campaign, |_, tx| tx.receiver_count() == 0).is_some() {
+            debug!("Removed event channel of campaign {campaign}");
+        }
+    }
+
+    /// Publishes an [`Event`] to all clients connected to a campaign.
+    ///
+    /// Does nothing if nobody is connected.
+    ///
+    /// # Arguments
+    /// - `campaign`: The identifier of the campaign to publish to.
+    /// - `event`: The [`Event`] to publish.
+    pub fn publish(&self, campaign: u64, event: Event) {
+        if let Some(tx) = self.channels.get(&campaign) {
+            // NOTE: Only fails if there are no receivers, which is fine
+            let _ = tx.send(event);
+        }
+    }
 }
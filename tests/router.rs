@@ -0,0 +1,572 @@
+//  ROUTER.rs
+//    by Lut99
+//
+//  Created:
+//    14 Oct 2026, 18:49:06
+//  Last edited:
+//    14 Oct 2026, 19:07:44
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Integration tests that drive the fully assembled [`Router`] (see
+//!   [`build_router()`]) with requests, against an in-memory database
+//!   seeded with a known root and player.
+//
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::connect_info::MockConnectInfo;
+use axum::http::header::{ACCEPT, ALLOW, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE};
+use axum::http::{Request, Response, StatusCode};
+use axum::Router;
+use dnd_server::auth::{hash_password, Argon2Params, Role, LOGIN_TOKEN_NAME, PASSWORD_MIN_LEN};
+use dnd_server::database::{Database, UserSummary};
+use dnd_server::paths::openapi::{self, ENDPOINTS};
+use dnd_server::paths::version::VersionResponse;
+use dnd_server::spec::ApiError;
+use dnd_server::state::{ServerConfig, ServerState};
+use dnd_server::{build_admin_router, build_public_router, build_router, middleware};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use totp_rs::TOTP;
+use tower::ServiceExt as _;
+use uuid::Uuid;
+
+
+/***** CONSTANTS *****/
+/// The name of the root user that every test server is seeded with.
+const ROOT_NAME: &str = "root";
+/// The password of the root user that every test server is seeded with.
+const ROOT_PASS: &str = "rootpass1";
+
+/// The name of the player that every test server is seeded with.
+const USER_NAME: &str = "amy";
+/// The password of the player that every test server is seeded with.
+const USER_PASS: &str = "hunter22";
+
+/// The name of the dungeon master that every test server is seeded with.
+const DM_NAME: &str = "dm";
+/// The password of the dungeon master that every test server is seeded with.
+const DM_PASS: &str = "dragons42";
+
+/// The (cheapest possible) Argon2 parameters to hash passwords with, such that the tests don't spend all their time hashing.
+const TEST_ARGON2: Argon2Params = Argon2Params { m_cost: 8, t_cost: 1, p_cost: 1 };
+
+/// The address the requests in the tests pretend to come from.
+const CLIENT_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 4242);
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Adds a user to the given database.
+///
+/// # Arguments
+/// - `db`: The [`Database`] to add the user to.
+/// - `name`: The name of the new user.
+/// - `pass`: The (plaintext) password of the new user.
+/// - `role`: The [`Role`] of the new user.
+///
+/// # Returns
+/// The identifier of the new user.
+async fn add_user(db: &Database, name: &str, pass: &str, role: Role) -> u64 {
+    let hash: String = hash_password(pass, &TEST_ARGON2).expect("Failed to hash password");
+    db.create_user(name, hash, role).await.expect("Failed to create user")
+}
+
+/// Builds a fresh [`ServerState`] on an in-memory database, which is seeded with the root user (from a root file, like the binary does) and
+/// with the player [`USER_NAME`] and the dungeon master [`DM_NAME`].
+///
+/// # Returns
+/// A new [`ServerState`] that doesn't share anything with that of other tests.
+async fn test_state() -> ServerState {
+    let db: Database = Database::sqlite_in_memory().expect("Failed to create in-memory database");
+    db.migrate().await.expect("Failed to migrate in-memory database");
+
+    // Seed the root
+    let root_path: PathBuf = std::env::temp_dir().join(format!("dnd-server-test-root-{}.toml", Uuid::new_v4()));
+    fs::write(&root_path, format!("[root.creds]\nname = \"{ROOT_NAME}\"\npass = \"{ROOT_PASS}\"\n")).expect("Failed to write root file");
+    let res = db.seed_root(&root_path, false, PASSWORD_MIN_LEN, &TEST_ARGON2).await;
+    let _ = fs::remove_file(&root_path);
+    res.expect("Failed to seed root user");
+
+    // Seed the other users
+    add_user(&db, USER_NAME, USER_PASS, Role::Player).await;
+    add_user(&db, DM_NAME, DM_PASS, Role::DungeonMaster).await;
+
+    // NOTE: The tests log in a lot, so we don't want to be rate limited
+    let config: ServerConfig = ServerConfig { argon2: TEST_ARGON2, auth_rate_limit: u32::MAX, ..Default::default() };
+    ServerState::new(env!("CARGO_PKG_NAME"), Version::parse(env!("CARGO_PKG_VERSION")).unwrap(), db, config)
+}
+
+/// Builds the [`Router`] for the given state the same way the binary does.
+///
+/// # Arguments
+/// - `state`: The [`ServerState`] to build the router for.
+///
+/// # Returns
+/// A [`Router`] that pretends all requests come from [`CLIENT_ADDR`].
+fn test_router(state: ServerState) -> Router {
+    build_router(state, &PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/client")))
+        .layer(axum::middleware::from_fn(middleware::request_id::handle))
+        .layer(MockConnectInfo(SocketAddr::from(CLIENT_ADDR)))
+}
+
+/// Sends a single request through the router.
+///
+/// # Arguments
+/// - `router`: The [`Router`] to send the request through.
+/// - `request`: The [`Request`] to send.
+///
+/// # Returns
+/// The [`Response`] of the router.
+async fn send(router: &Router, request: Request<Body>) -> Response<Body> {
+    match router.clone().oneshot(request).await {
+        Ok(res) => res,
+        Err(err) => match err {},
+    }
+}
+
+/// Builds a request with a JSON body.
+///
+/// # Arguments
+/// - `method`: The method of the request.
+/// - `uri`: The path of the request.
+/// - `cookie`: The login cookie to send along, if any (see [`login()`]).
+/// - `body`: The body to serialize.
+///
+/// # Returns
+/// A new [`Request`].
+fn json_request(method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri).header(CONTENT_TYPE, "application/json");
+    if let Some(cookie) = cookie {
+        request = request.header(COOKIE, cookie);
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+/// Builds a request without a body.
+///
+/// # Arguments
+/// - `method`: The method of the request.
+/// - `uri`: The path of the request.
+/// - `cookie`: The login cookie to send along, if any (see [`login()`]).
+///
+/// # Returns
+/// A new [`Request`].
+fn empty_request(method: &str, uri: &str, cookie: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(cookie) = cookie {
+        request = request.header(COOKIE, cookie);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+/// Logs in as the given user.
+///
+/// # Arguments
+/// - `router`: The [`Router`] to log in with.
+/// - `name`: The name of the user.
+/// - `pass`: The password of the user.
+///
+/// # Returns
+/// The `Cookie`-header value with the login token to send along with other requests, or the [`Response`] if logging in failed.
+async fn login(router: &Router, name: &str, pass: &str) -> Result<String, Response<Body>> {
+    let res: Response<Body> = send(router, json_request("POST", "/v1/auth/login", None, json!({ "name": name, "pass": pass }))).await;
+    if res.status() != StatusCode::OK {
+        return Err(res);
+    }
+    Ok(login_cookie(&res).expect("Successful login did not set login cookie"))
+}
+
+/// Finds the login cookie set by a response.
+///
+/// # Arguments
+/// - `res`: The [`Response`] to find the `Set-Cookie`-header in.
+///
+/// # Returns
+/// The `Cookie`-header value with the login token to send along with other requests, or [`None`] if the response didn't set it.
+fn login_cookie(res: &Response<Body>) -> Option<String> {
+    let prefix: String = format!("{LOGIN_TOKEN_NAME}=");
+    res.headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|value| value.starts_with(&prefix))
+        .and_then(|value| value.split(';').next())
+        .map(String::from)
+}
+
+/// Reads the body of a response as text.
+///
+/// # Arguments
+/// - `res`: The [`Response`] to read the body of.
+///
+/// # Returns
+/// The body as a string.
+async fn body_text(res: Response<Body>) -> String {
+    let body = to_bytes(res.into_body(), usize::MAX).await.expect("Failed to read response body");
+    String::from_utf8(body.to_vec()).expect("Response body is not valid UTF-8")
+}
+
+/// Reads the body of a response as JSON.
+///
+/// # Arguments
+/// - `res`: The [`Response`] to read the body of.
+///
+/// # Returns
+/// The parsed body.
+async fn body_json<T: DeserializeOwned>(res: Response<Body>) -> T {
+    let body = to_bytes(res.into_body(), usize::MAX).await.expect("Failed to read response body");
+    match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(err) => panic!("Failed to parse response body {:?} as JSON: {err}", String::from_utf8_lossy(&body)),
+    }
+}
+
+
+
+
+
+/***** TESTS *****/
+#[tokio::test]
+async fn test_version() {
+    let router: Router = test_router(test_state().await);
+
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/version", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: VersionResponse = body_json(res).await;
+    assert_eq!(body.name, env!("CARGO_PKG_NAME"));
+    assert_eq!(body.version, Version::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    assert!(body.user.is_none());
+}
+
+#[tokio::test]
+async fn test_login() {
+    let router: Router = test_router(test_state().await);
+
+    // The seeded users can login...
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let user: UserSummary = body_json(res).await;
+    assert_eq!(user.name, USER_NAME);
+    assert_eq!(user.role, Role::Player);
+    login(&router, ROOT_NAME, ROOT_PASS).await.expect("Failed to login as seeded root");
+
+    // ...and the version endpoint knows who they are
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/version", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: VersionResponse = body_json(res).await;
+    assert_eq!(body.user.map(|user| user.name), Some(USER_NAME.into()));
+}
+
+#[tokio::test]
+async fn test_login_failure() {
+    let router: Router = test_router(test_state().await);
+
+    // A wrong password and an unknown user are indistinguishable
+    for (name, pass) in [(USER_NAME, "wrongpass1"), ("nobody", USER_PASS)] {
+        let res: Response<Body> = login(&router, name, pass).await.expect_err("Logged in with invalid credentials");
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert!(!res.headers().contains_key(SET_COOKIE));
+        let err: ApiError = body_json(res).await;
+        assert_eq!(err.kind, "invalid_credentials");
+    }
+}
+
+#[tokio::test]
+async fn test_auth_middleware_rejects() {
+    let router: Router = test_router(test_state().await);
+
+    // No cookie at all
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", None)).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "missing_token");
+
+    // A cookie that was revoked by logging out
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let res: Response<Body> = send(&router, empty_request("POST", "/v1/auth/logout", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "invalid_token");
+}
+
+#[tokio::test]
+async fn test_auth_and_role_stacked() {
+    let router: Router = test_router(test_state().await);
+    let create = |cookie: Option<&str>| json_request("POST", "/v1/campaigns", cookie, json!({ "name": "Curse of Strahd" }));
+
+    // The token checker runs first...
+    let res: Response<Body> = send(&router, create(None)).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "missing_token");
+
+    // ...then the role checker...
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let res: Response<Body> = send(&router, create(Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "insufficient_role");
+
+    // ...and only then the handler
+    let cookie: String = login(&router, DM_NAME, DM_PASS).await.expect("Failed to login as seeded dungeon master");
+    let res: Response<Body> = send(&router, create(Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_refresh_revokes() {
+    let router: Router = test_router(test_state().await);
+    let old: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+
+    // Refresh to get a new token
+    let res: Response<Body> = send(&router, empty_request("POST", "/v1/auth/refresh", Some(&old))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let new: String = login_cookie(&res).expect("Refresh did not set login cookie");
+    assert_ne!(new, old);
+
+    // Only the new one is still accepted, also for refreshing
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&new))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&old))).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res: Response<Body> = send(&router, empty_request("POST", "/v1/auth/refresh", Some(&old))).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_totp_enrollment() {
+    let router: Router = test_router(test_state().await);
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let enroll = |body: serde_json::Value| json_request("POST", "/v1/auth/totp", Some(&cookie), body);
+    let confirm = |code: &str| json_request("POST", "/v1/auth/totp/confirm", Some(&cookie), json!({ "code": code }));
+
+    // Enrolling needs proof that it's really the user
+    for body in [json!({}), json!({ "pass": "wrongpass1" }), json!({ "totp": "123456" })] {
+        let res: Response<Body> = send(&router, enroll(body)).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let err: ApiError = body_json(res).await;
+        assert_eq!(err.kind, "invalid_credentials");
+    }
+    let res: Response<Body> = send(&router, enroll(json!({ "pass": USER_PASS }))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let totp: TOTP = TOTP::from_url(body_text(res).await).expect("Enrolling did not return a valid otpauth URI");
+
+    // The secret is only pending until confirmed...
+    login(&router, USER_NAME, USER_PASS).await.expect("Pending TOTP secret was already required to login");
+    let code: String = totp.generate_current().unwrap();
+    let wrong: String = format!("{:06}", (code.parse::<u32>().unwrap() + 500_000) % 1_000_000);
+    let res: Response<Body> = send(&router, confirm(&wrong)).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "invalid_totp");
+    let res: Response<Body> = send(&router, confirm(&code)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res: Response<Body> = send(&router, confirm(&code)).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "no_pending_totp");
+
+    // ...after which it's required to login, and may be used to re-enroll
+    let res: Response<Body> = login(&router, USER_NAME, USER_PASS).await.expect_err("Logged in without TOTP code");
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "missing_totp");
+    let res: Response<Body> = send(&router, enroll(json!({ "totp": code }))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_v1_routers_merged() {
+    let router: Router = test_router(test_state().await);
+
+    // Paths from all routers under `/v1` are reachable side by side, instead of one nest shadowing the others
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/version", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let _: VersionResponse = body_json(res).await;
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_path_constants_mounted() {
+    let router: Router = test_router(test_state().await);
+
+    // Every documented path should hit an actual route, i.e., not get refused nor fall through to the client files
+    for path in ENDPOINTS.iter().map(|endpoint| endpoint.path).chain([&openapi::PATH]) {
+        let uri: String = path.path.split('/').map(|seg| if seg.starts_with(':') { "1" } else { seg }).collect::<Vec<&str>>().join("/");
+        let res: Response<Body> = send(&router, empty_request(path.method.as_str(), &uri, None)).await;
+        assert_ne!(res.status(), StatusCode::NOT_FOUND, "{} {} is not mounted", path.method, path.path);
+        assert_ne!(res.status(), StatusCode::METHOD_NOT_ALLOWED, "{} {} is not mounted with that method", path.method, path.path);
+        let content_type: Option<&str> = res.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+        assert!(!content_type.unwrap_or_default().starts_with("text/html"), "{} {} is served by the client files", path.method, path.path);
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_path_serves_index() {
+    let router: Router = test_router(test_state().await);
+    let index: String = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/src/client/index.html")).unwrap();
+
+    // Unknown paths are left to the client's own routing...
+    let res: Response<Body> = send(&router, empty_request("GET", "/campaigns/42", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/html"));
+    assert_eq!(body_text(res).await, index);
+
+    // ...but the API still takes precedence
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/version", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("application/json"));
+    let _: VersionResponse = body_json(res).await;
+}
+
+#[tokio::test]
+async fn test_oversized_body() {
+    let router: Router = test_router(test_state().await);
+
+    let pass: String = "a".repeat(2 * ServerConfig::default().max_body_size);
+    let res: Response<Body> = send(&router, json_request("POST", "/v1/auth/login", None, json!({ "name": USER_NAME, "pass": pass }))).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_admin_router_login() {
+    let router: Router = build_admin_router(test_state().await)
+        .layer(axum::middleware::from_fn(middleware::request_id::handle))
+        .layer(MockConnectInfo(SocketAddr::from(CLIENT_ADDR)));
+
+    // Root can log in on the admin listener directly and use the admin paths with that...
+    let cookie: String = login(&router, ROOT_NAME, ROOT_PASS).await.expect("Failed to log in as root on the admin router");
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/users", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // ...and log out again
+    let res: Response<Body> = send(&router, empty_request("POST", "/v1/auth/logout", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_login_rehashes_bcrypt() {
+    let state: ServerState = test_state().await;
+    let router: Router = test_router(state.clone());
+    let hash: String = bcrypt::hash("legacy-pass", 4).unwrap();
+    state.db.create_user("legacy", hash, Role::Player).await.unwrap();
+
+    // Users imported with a bcrypt hash can log in, after which their hash is upgraded...
+    login(&router, "legacy", "legacy-pass").await.expect("Failed to log in with bcrypt hash");
+    let pass: String = state.db.get_user_by_name("legacy").await.unwrap().unwrap().pass;
+    assert!(pass.starts_with("$argon2id$"), "Password hash {pass:?} was not upgraded to Argon2");
+
+    // ...with which they can still log in (and only with the right password)
+    login(&router, "legacy", "legacy-pass").await.expect("Failed to log in with upgraded hash");
+    let res: Response<Body> = login(&router, "legacy", "wrong-pass").await.expect_err("Logged in with wrong password");
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_login_rehashes_weaker_params() {
+    let state: ServerState = test_state().await;
+    let router: Router = test_router(state.clone());
+
+    // Raise the cost after the users were created...
+    let params: Argon2Params = Argon2Params { m_cost: 16, t_cost: 2, p_cost: 1 };
+    let config: ServerConfig = ServerConfig { argon2: params, ..state.config.read().clone() };
+    state.reload_config(config);
+
+    // ...such that their hashes are upgraded when they log in
+    login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let pass: String = state.db.get_user_by_name(USER_NAME).await.unwrap().unwrap().pass;
+    assert!(pass.contains("m=16,t=2,p=1"), "Password hash {pass:?} was not upgraded to the new parameters");
+    login(&router, USER_NAME, USER_PASS).await.expect("Failed to login with upgraded hash");
+}
+
+#[tokio::test]
+async fn test_login_redirect() {
+    let state: ServerState = test_state().await;
+    let config: ServerConfig = ServerConfig { login_redirect: Some("/login".into()), ..state.config.read().clone() };
+    state.reload_config(config);
+    let router: Router = test_router(state);
+    let navigate = |uri: &str, cookie: Option<&str>| {
+        let mut request = Request::builder().method("GET").uri(uri).header(ACCEPT, "text/html,application/xhtml+xml;q=0.9");
+        if let Some(cookie) = cookie {
+            request = request.header(COOKIE, cookie);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    // Browsers that aren't logged in are sent to the login page...
+    let res: Response<Body> = send(&router, navigate("/", None)).await;
+    assert_eq!(res.status(), StatusCode::FOUND);
+    assert_eq!(res.headers().get(LOCATION).and_then(|value| value.to_str().ok()), Some("/login"));
+    let res: Response<Body> = send(&router, navigate("/", Some(&format!("{LOGIN_TOKEN_NAME}=garbage")))).await;
+    assert_eq!(res.status(), StatusCode::FOUND);
+
+    // ...but not when they're navigating to the login page itself, or aren't navigating at all
+    let res: Response<Body> = send(&router, navigate("/login", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res: Response<Body> = send(&router, empty_request("GET", "/", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // Logged-in browsers get the page
+    let cookie: String = login(&router, USER_NAME, USER_PASS).await.expect("Failed to login as seeded user");
+    let res: Response<Body> = send(&router, navigate("/", Some(&cookie))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_wrong_method() {
+    let router: Router = test_router(test_state().await);
+
+    let res: Response<Body> = send(&router, empty_request("POST", "/v1/version", None)).await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers().get(ALLOW).unwrap(), "GET, HEAD");
+    let err: ApiError = body_json(res).await;
+    assert_eq!(err.kind, "method_not_allowed");
+}
+
+#[tokio::test]
+async fn test_public_router_hides_admin_paths() {
+    let router: Router = build_public_router(test_state().await, &PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/client")))
+        .layer(axum::middleware::from_fn(middleware::request_id::handle))
+        .layer(MockConnectInfo(SocketAddr::from(CLIENT_ADDR)));
+
+    // The admin paths aren't mounted, so they're left to the client files like any unknown path (instead of answering which methods they
+    // support)
+    let res: Response<Body> = send(&router, empty_request("GET", "/v1/admin/backup", None)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/html"));
+}
+
+#[tokio::test]
+async fn test_login_ignores_case() {
+    let state: ServerState = test_state().await;
+    let router: Router = test_router(state.clone());
+
+    // Any casing of the name logs in as the same user...
+    for name in ["amy", "AMY", "Amy", "aMy"] {
+        let cookie: String = login(&router, name, USER_PASS).await.unwrap_or_else(|_| panic!("Failed to login as {name:?}"));
+        let res: Response<Body> = send(&router, empty_request("GET", "/v1/whoami", Some(&cookie))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let user: UserSummary = body_json(res).await;
+        assert_eq!(user.name, USER_NAME, "Logging in as {name:?} did not preserve the stored casing");
+    }
+    // ...but the password still has to match exactly
+    let res: Response<Body> = login(&router, "AMY", &USER_PASS.to_uppercase()).await.expect_err("Logged in with wrong-cased password");
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    // Nor can anyone register the same name in another casing
+    let res: Response<Body> = send(&router, json_request("POST", "/v1/auth/register", None, json!({ "name": "Amy", "pass": "another-pass" }))).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+}